@@ -0,0 +1,52 @@
+//! Keeps a tank between two levels by switching a fill pump.
+//!
+//! Wiring:
+//! - Fill pump contactor on Relay 1
+//! - Level sensor (0-10V) on Analog Input 2
+//! - Manual stop switch on Input 1
+//!
+//! The pump starts when the tank drops below 30% and stops at 80%, or immediately
+//! while the stop switch is closed.
+
+use automation_hat::{Automation, AutomationHAT, Channel, HatConfig, HatError, HatType};
+use std::thread;
+use std::time::Duration;
+
+const START_LEVEL: f64 = 0.3;
+const STOP_LEVEL: f64 = 0.8;
+
+fn main() -> Result<(), HatError> {
+    let config = HatConfig::new().analog_max_value(2, 10.0)?;
+    let hat = AutomationHAT::try_with_config(HatType::AutomationHAT, config)?;
+    let mut plant = Automation::new(
+        hat,
+        [
+            ("pump", "relay1".parse::<Channel>()?),
+            ("tank_level", "analog2".parse()?),
+            ("stop_switch", "input1".parse()?),
+        ],
+    )?;
+
+    loop {
+        let level = plant.read("tank_level")?;
+        let stopped = plant.read("stop_switch")? > 0.5;
+        let pumping = plant.read("pump")? > 0.5;
+
+        if stopped || level >= STOP_LEVEL {
+            plant.deactivate("pump")?;
+        } else if !pumping && level < START_LEVEL {
+            plant.activate("pump")?;
+        }
+
+        println!(
+            "Level: {:5.1}%  Pump: {}",
+            level * 100.0,
+            if plant.read("pump")? > 0.5 {
+                "on"
+            } else {
+                "off"
+            }
+        );
+        thread::sleep(Duration::from_millis(500));
+    }
+}
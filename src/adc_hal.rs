@@ -0,0 +1,91 @@
+//! `embedded-hal` ADC trait support for the analog inputs.
+//!
+//! `embedded-hal` 1.0 has no ADC traits, so this module targets the
+//! `adc::OneShot` and `adc::Channel` traits from **`embedded-hal` 0.2.7** (with the
+//! `unproven` feature), which is what existing generic sensor drivers expect. It is
+//! only compiled with the `embedded-hal-02` cargo feature.
+//!
+//! `AnalogInputs` acts as the ADC, and each input terminal is represented by a
+//! zero-sized channel marker. Readings are returned as the terminal voltage in
+//! millivolts, so the board's divider scaling is already applied.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use automation_hat::adc_hal::Analog1;
+//! use automation_hat::{AutomationHAT, HatType};
+//! use embedded_hal_02::adc::OneShot;
+//!
+//! let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+//! let millivolts: u16 = nb::block!(hat.analog_inputs.read(&mut Analog1)).unwrap();
+//! ```
+
+use crate::{AnalogInputs, HatError};
+
+use embedded_hal_02::adc::{Channel, OneShot};
+
+/// Channel marker for Analog Input 1
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Analog1;
+
+/// Channel marker for Analog Input 2
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Analog2;
+
+/// Channel marker for Analog Input 3
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Analog3;
+
+/// Channel marker for Analog Input 4 (0-3.3V range)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Analog4;
+
+impl Channel<AnalogInputs> for Analog1 {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        1
+    }
+}
+
+impl Channel<AnalogInputs> for Analog2 {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        2
+    }
+}
+
+impl Channel<AnalogInputs> for Analog3 {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        3
+    }
+}
+
+impl Channel<AnalogInputs> for Analog4 {
+    type ID = u8;
+
+    fn channel() -> u8 {
+        4
+    }
+}
+
+impl<PIN> OneShot<AnalogInputs, u16, PIN> for AnalogInputs
+where
+    PIN: Channel<AnalogInputs, ID = u8>,
+{
+    type Error = HatError;
+
+    /// Reads the terminal voltage of the channel in millivolts.
+    ///
+    /// The conversion happens synchronously, so this never returns `WouldBlock`.
+    /// Negative readings are clamped to zero.
+    fn read(&mut self, _pin: &mut PIN) -> nb::Result<u16, HatError> {
+        let index = PIN::channel() as usize;
+        let input = self.get_mut(index).ok_or(HatError::InvalidChannel(index))?;
+        let volts = input.read_voltage()?;
+        Ok((volts * 1000.0).clamp(0.0, u16::MAX as f64) as u16)
+    }
+}
@@ -4,14 +4,446 @@
 //! Analog inputs can read variable voltage levels and have indicator LEDs
 //! that can show input levels proportionally.
 
+use crate::calibration::{Calibration, TwoPointCalibration};
+use crate::error::{HatError, Result};
+use crate::instrumentation;
 use crate::lights::LED;
+use crate::sampler::{AnalogWatcher, SampleCache};
 use ads1x1x::{
-    Ads1x1x, channel,
-    ic::{Ads1015, Resolution12Bit},
-    mode::Continuous,
+    Ads1x1x, ComparatorMode, ComparatorQueue, ModeChangeError, channel,
+    ic::{Ads1015, Ads1115, Resolution12Bit, Resolution16Bit},
+    mode::{Continuous, OneShot},
 };
 use linux_embedded_hal::I2cdev;
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "uom")]
+use uom::si::{electric_potential::volt, f64::ElectricPotential};
+
+/// Full-scale range the ADC is configured for, in volts at the ADC pin.
+///
+/// ±4.096V is the narrowest range that covers the 3.3V an input presents to the ADC
+/// at its full-scale terminal voltage.
+pub(crate) const ADC_FULL_SCALE_VOLTS: f64 = 4.096;
+
+/// Voltage at the ADC pin when an analog input is at its full-scale terminal voltage.
+///
+/// Analog input 4 is wired straight to the ADC, so this is also its range.
+pub(crate) const ADC_INPUT_SPAN_VOLTS: f64 = 3.3;
+
+/// Ratio of terminal voltage to ADC voltage on the buffered analog inputs 1-3.
+///
+/// Pimoroni rates these inputs 0-25.85V for the same 0-3.3V at the ADC as input 4,
+/// which has no divider. The HAT, pHAT and HAT Mini share this network.
+pub(crate) const INPUT_DIVIDER_RATIO: f64 = 25.85 / ADC_INPUT_SPAN_VOLTS;
+
+/// Magnitude of a full-scale ADS1015 result, which is 12-bit signed
+const ADS1015_FULL_SCALE_COUNTS: f64 = 2048.0;
+
+/// Magnitude of a full-scale ADS1115 result, which is 16-bit signed
+const ADS1115_FULL_SCALE_COUNTS: f64 = 32768.0;
+
+/// Common-mode input impedance of the ADS1015 and ADS1115 in the ±4.096V range, in ohms.
+///
+/// Both chips sample through switched capacitors clocked by a fixed internal
+/// modulator, so this does not change with the data rate.
+pub const ADC_INPUT_IMPEDANCE_OHMS: f64 = 6.0e6;
+
+/// The analog-to-digital converter fitted to the board.
+///
+/// Both chips share a footprint, register map and I2C address, and are configured
+/// for the same input range, so they read the same voltages; they differ in
+/// resolution and speed. The ADS1015 returns 12-bit results (2mV steps at the ADC,
+/// about 15.7mV at the 0-25.85V terminals) at up to 3300 samples per second. The
+/// ADS1115 returns 16-bit results (125uV steps at the ADC, about 1mV at the
+/// terminals) at up to 860 samples per second, so each conversion takes longer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdcVariant {
+    /// The 12-bit ADS1015 fitted by Pimoroni
+    #[default]
+    Ads1015,
+    /// The 16-bit ADS1115 found on some revisions and user-modified boards
+    Ads1115,
+}
+
+impl AdcVariant {
+    /// The magnitude of a raw result at `ADC_FULL_SCALE_VOLTS`.
+    fn full_scale_counts(self) -> f64 {
+        match self {
+            AdcVariant::Ads1015 => ADS1015_FULL_SCALE_COUNTS,
+            AdcVariant::Ads1115 => ADS1115_FULL_SCALE_COUNTS,
+        }
+    }
+
+    /// Longest time one conversion takes at the chip's default data rate, which the
+    /// crate never changes: 1600 samples per second on the ADS1015 and 128 on the
+    /// ADS1115, plus 10% for the tolerance of the internal oscillator.
+    pub(crate) fn conversion_period(self) -> Duration {
+        match self {
+            AdcVariant::Ads1015 => Duration::from_micros(688),
+            AdcVariant::Ads1115 => Duration::from_micros(8594),
+        }
+    }
+
+    /// Time from pointing the multiplexer at another channel in continuous mode until
+    /// a conversion of that channel is ready.
+    ///
+    /// The conversion in progress when the channel changes still finishes on the old
+    /// channel, so the first conversion of the new one can take two periods.
+    fn settle_time(self) -> Duration {
+        self.conversion_period() * 2
+    }
+}
+
+/// Converts a raw ADC reading into the voltage present on the terminal of an input
+/// whose full-scale terminal voltage is `full_scale`.
+///
+/// The raw reading gives the voltage at the ADC pin, which the input's divider scales
+/// up by `full_scale / ADC_INPUT_SPAN_VOLTS`: `INPUT_DIVIDER_RATIO` on inputs 1-3 at
+/// their default 25.85V, and 1 on input 4 at 3.3V.
+pub(crate) fn terminal_voltage_from_raw(raw: i16, variant: AdcVariant, full_scale: f64) -> f64 {
+    let adc_volts = raw as f64 / variant.full_scale_counts() * ADC_FULL_SCALE_VOLTS;
+    adc_volts * full_scale / ADC_INPUT_SPAN_VOLTS
+}
+
+/// Converts a terminal voltage into the raw ADC reading it would produce on an input
+/// whose full-scale terminal voltage is `full_scale`.
+///
+/// The result is clamped to the converter's range.
+pub(crate) fn raw_from_terminal_voltage(volts: f64, variant: AdcVariant, full_scale: f64) -> i16 {
+    let limit = variant.full_scale_counts();
+    let adc_volts = volts / full_scale * ADC_INPUT_SPAN_VOLTS;
+    (adc_volts / ADC_FULL_SCALE_VOLTS * limit)
+        .round()
+        .clamp(-limit, limit - 1.0) as i16
+}
+
+/// ADS1015 channel of analog input 4, which `AnalogInput::read_pot` reads as the rail
+const RAIL_CHANNEL: u8 = 3;
+
+/// Lowest rail voltage `AnalogInput::read_pot` accepts as a wired supply
+const MIN_RAIL_VOLTS: f64 = 1.0;
+
+/// Delay before the first retry in `read_retry`, doubled on each further attempt
+static RETRY_BACKOFF: Duration = Duration::from_millis(5);
+/// Longest delay between retries in `read_retry`, reached after five doublings
+static MAX_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// The `ads1x1x` ADS1015 driver in continuous conversion mode
+type ContinuousAdc = Ads1x1x<I2cdev, Ads1015, Resolution12Bit, Continuous>;
+
+/// The `ads1x1x` ADS1015 driver in one-shot conversion mode
+type OneShotAdc = Ads1x1x<I2cdev, Ads1015, Resolution12Bit, OneShot>;
+
+/// The `ads1x1x` ADS1115 driver in continuous conversion mode
+type ContinuousAdc16 = Ads1x1x<I2cdev, Ads1115, Resolution16Bit, Continuous>;
+
+/// The `ads1x1x` ADS1115 driver in one-shot conversion mode
+type OneShotAdc16 = Ads1x1x<I2cdev, Ads1115, Resolution16Bit, OneShot>;
+
+/// Shared handle to the ADC driver
+pub(crate) type SharedAdc = Arc<Mutex<Adc>>;
+
+/// The ADS1015 or ADS1115 driver shared by the analog inputs.
+///
+/// Wraps the `ads1x1x` driver so that its conversion mode can be switched at runtime
+/// with `AnalogInputs::set_continuous`. Create one from an `ads1x1x` ADS1015 or
+/// ADS1115 driver in either mode with `Adc::from`.
+pub struct Adc {
+    /// The driver in its current mode, only `None` if a mode change lost the device
+    mode: Option<AdcMode>,
+    /// Which converter the driver talks to
+    variant: AdcVariant,
+    /// Channel the multiplexer has settled on in continuous mode, `None` if unknown
+    selected: Option<u8>,
+}
+
+/// The `ads1x1x` driver in one of its type-state modes.
+enum AdcMode {
+    /// ADS1015 converting continuously; reads return the latest conversion
+    Continuous(ContinuousAdc),
+    /// ADS1015 idle between reads; each read starts a single conversion
+    OneShot(OneShotAdc),
+    /// ADS1115 converting continuously
+    Continuous16(ContinuousAdc16),
+    /// ADS1115 idle between reads
+    OneShot16(OneShotAdc16),
+}
+
+impl From<ContinuousAdc> for Adc {
+    fn from(driver: ContinuousAdc) -> Self {
+        Adc {
+            mode: Some(AdcMode::Continuous(driver)),
+            variant: AdcVariant::Ads1015,
+            selected: None,
+        }
+    }
+}
+
+impl From<OneShotAdc> for Adc {
+    fn from(driver: OneShotAdc) -> Self {
+        Adc {
+            mode: Some(AdcMode::OneShot(driver)),
+            variant: AdcVariant::Ads1015,
+            selected: None,
+        }
+    }
+}
+
+impl From<ContinuousAdc16> for Adc {
+    fn from(driver: ContinuousAdc16) -> Self {
+        Adc {
+            mode: Some(AdcMode::Continuous16(driver)),
+            variant: AdcVariant::Ads1115,
+            selected: None,
+        }
+    }
+}
+
+impl From<OneShotAdc16> for Adc {
+    fn from(driver: OneShotAdc16) -> Self {
+        Adc {
+            mode: Some(AdcMode::OneShot16(driver)),
+            variant: AdcVariant::Ads1115,
+            selected: None,
+        }
+    }
+}
+
+/// Selects a single-ended channel on a continuous-mode driver of either chip.
+macro_rules! select_channel {
+    ($driver:expr, $channel:expr) => {
+        match $channel {
+            0 => $driver.select_channel(channel::SingleA0),
+            1 => $driver.select_channel(channel::SingleA1),
+            2 => $driver.select_channel(channel::SingleA2),
+            3 => $driver.select_channel(channel::SingleA3),
+            _ => return Err(HatError::InvalidChannel($channel as usize)),
+        }
+        .map_err(|error| {
+            HatError::Analog(format!(
+                "Failed to read value from channel {}: {:?}",
+                $channel, error
+            ))
+        })
+    };
+}
+
+/// Reads the latest conversion of a single-ended channel on a continuous-mode driver
+/// of either chip, first selecting the channel and waiting for it to settle unless
+/// `$selected` shows the multiplexer is already there.
+macro_rules! read_continuous {
+    ($driver:expr, $channel:expr, $selected:expr, $settle:expr) => {
+        match $selected == Some($channel) {
+            true => Ok(()),
+            false => select_channel!($driver, $channel).map(|_| thread::sleep($settle)),
+        }
+        .and_then(|_| {
+            $driver.read().map_err(|error| {
+                HatError::Analog(format!(
+                    "Failed to read value from channel {}: {:?}",
+                    $channel, error
+                ))
+            })
+        })
+    };
+}
+
+/// Runs a single conversion of a single-ended channel on a one-shot driver of either chip.
+macro_rules! read_one_shot {
+    ($driver:expr, $channel:expr) => {
+        match $channel {
+            0 => nb::block!($driver.read(channel::SingleA0)),
+            1 => nb::block!($driver.read(channel::SingleA1)),
+            2 => nb::block!($driver.read(channel::SingleA2)),
+            3 => nb::block!($driver.read(channel::SingleA3)),
+            _ => return Err(HatError::InvalidChannel($channel as usize)),
+        }
+        .map_err(|error| {
+            HatError::Analog(format!(
+                "Failed to read value from channel {}: {:?}",
+                $channel, error
+            ))
+        })
+    };
+}
+
+/// Writes a traditional comparator window to a driver of either chip, in either mode.
+macro_rules! configure_comparator {
+    ($driver:expr, $low:expr, $high:expr) => {
+        $driver
+            .set_low_threshold_raw($low)
+            .and_then(|_| $driver.set_high_threshold_raw($high))
+            .and_then(|_| $driver.set_comparator_mode(ComparatorMode::Traditional))
+            .and_then(|_| $driver.set_comparator_queue(ComparatorQueue::One))
+            .map_err(|error| {
+                HatError::Analog(format!("Failed to configure comparator: {:?}", error))
+            })
+    };
+}
+
+/// Moves a driver of either chip between modes, keeping the old mode on failure.
+macro_rules! change_mode {
+    ($driver:expr, $into:ident, $to:ident, $from:ident, $name:literal) => {
+        match $driver.$into() {
+            Ok(driver) => (AdcMode::$to(driver), Ok(())),
+            Err(ModeChangeError::I2C(error, driver)) => (
+                AdcMode::$from(driver),
+                Err(HatError::Analog(format!(
+                    "Failed to enter {} mode: {:?}",
+                    $name, error
+                ))),
+            ),
+        }
+    };
+}
+
+impl Adc {
+    /// Returns whether the ADC is converting continuously.
+    pub fn is_continuous(&self) -> bool {
+        matches!(
+            self.mode,
+            Some(AdcMode::Continuous(_) | AdcMode::Continuous16(_))
+        )
+    }
+
+    /// Returns which converter this driver talks to.
+    pub fn variant(&self) -> AdcVariant {
+        self.variant
+    }
+
+    /// Switches the ADC between continuous and one-shot conversion.
+    ///
+    /// If the mode change fails on the bus, the driver is kept in its previous mode.
+    pub(crate) fn set_continuous(&mut self, continuous: bool) -> Result<()> {
+        let mode = self.mode.take().ok_or_else(|| {
+            HatError::Analog("ADC driver was lost by an earlier mode change".to_string())
+        })?;
+        let (mode, result) = match (mode, continuous) {
+            (AdcMode::OneShot(driver), true) => {
+                change_mode!(driver, into_continuous, Continuous, OneShot, "continuous")
+            }
+            (AdcMode::Continuous(driver), false) => {
+                change_mode!(driver, into_one_shot, OneShot, Continuous, "one-shot")
+            }
+            (AdcMode::OneShot16(driver), true) => {
+                change_mode!(
+                    driver,
+                    into_continuous,
+                    Continuous16,
+                    OneShot16,
+                    "continuous"
+                )
+            }
+            (AdcMode::Continuous16(driver), false) => {
+                change_mode!(driver, into_one_shot, OneShot16, Continuous16, "one-shot")
+            }
+            (mode, _) => (mode, Ok(())),
+        };
+        self.mode = Some(mode);
+        self.selected = None;
+        result.inspect_err(|_| instrumentation::i2c_error("ads1015"))
+    }
+}
+
+/// One channel of an `AnalogSnapshot`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnalogReading {
+    /// Normalized value (0.0-1.0 of the channel's full scale)
+    pub value: f64,
+    /// Voltage at the input terminal
+    pub voltage: f64,
+}
+
+/// Statistics of a burst of conversions, returned by `AnalogInput::read_stats`.
+///
+/// Every voltage is the corrected terminal voltage, as returned by `read_voltage`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnalogStats {
+    /// Number of conversions taken
+    pub samples: usize,
+    /// Mean voltage
+    pub mean: f64,
+    /// Lowest voltage
+    pub min: f64,
+    /// Highest voltage
+    pub max: f64,
+    /// Population standard deviation of the voltages, a measure of the signal's noise
+    pub std_dev: f64,
+}
+
+/// A coherent reading of every analog channel, taken under one lock of the ADC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnalogSnapshot {
+    /// Wall-clock time at which the channels were captured
+    pub timestamp: SystemTime,
+    /// Readings indexed by ADC channel (input 1 at index 0); `None` for inputs without an ADC
+    pub channels: [Option<AnalogReading>; 4],
+}
+
+impl AnalogSnapshot {
+    /// Returns the reading of the input with the given board label (1-4).
+    pub fn get(&self, index: usize) -> Option<AnalogReading> {
+        self.channels.get(index.wrapping_sub(1)).copied().flatten()
+    }
+}
+
+/// How `AnalogInput::read_scaled` treats voltages outside its transfer function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRange {
+    /// Report the value of the nearest end point
+    #[default]
+    Clamp,
+    /// Extend the first or last segment beyond its end point
+    Extrapolate,
+}
+
+/// Scale of the logarithmic LED curve; a 1% signal lights the LED to about 15%
+const LOG_CURVE_SCALE: f64 = 99.0;
+
+/// How an analog input's LED brightness responds to its signal.
+///
+/// The curve is applied after any LED source or range, to a level clamped into
+/// 0.0-1.0, so its result is always a valid brightness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LedCurve {
+    /// Brightness proportional to the signal
+    #[default]
+    Linear,
+    /// Logarithmic response that makes small signals clearly visible
+    Logarithmic,
+    /// Square-law response that keeps the LED dim until the signal is high
+    Square,
+}
+
+impl LedCurve {
+    /// Maps a signal level to an LED brightness.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The signal level; clamped to 0.0-1.0, with NaN treated as 0.0
+    ///
+    /// # Returns
+    ///
+    /// The brightness, from 0.0 to 1.0
+    pub fn apply(&self, level: f64) -> f64 {
+        let level = match level.is_nan() {
+            true => 0.0,
+            false => level.clamp(0.0, 1.0),
+        };
+        match self {
+            LedCurve::Linear => level,
+            LedCurve::Logarithmic => {
+                (1.0 + LOG_CURVE_SCALE * level).ln() / (1.0 + LOG_CURVE_SCALE).ln()
+            }
+            LedCurve::Square => level * level,
+        }
+    }
+}
 
 /// Controls an analog input on the Automation HAT.
 ///
@@ -20,21 +452,46 @@ use std::sync::{Arc, Mutex};
 /// and normalized to a value between 0.0 and 1.0 based on the max_value.
 /// Each input can have an associated LED that indicates the input level.
 pub struct AnalogInput {
-    /// Reference to the ADS1015 ADC driver
-    driver: Arc<Mutex<Ads1x1x<I2cdev, Ads1015, Resolution12Bit, Continuous>>>,
+    /// Reference to the ADS1015 ADC driver, or `None` if analog support was not initialized
+    driver: Option<SharedAdc>,
     /// Optional LED indicator for this input
     led: Option<LED>,
     /// Channel number on the ADS1015 (0-3)
     channel: u8,
+    /// The converter behind this input, which sets the raw reading scale
+    variant: AdcVariant,
     /// Current normalized value (0.0-1.0)
     pub value: f64,
-    /// Maximum raw ADC value used for normalization
+    /// Full-scale terminal voltage, which normalizes to 1.0 and sets the divider ratio
     pub max_value: f64,
+    /// Last state reported by the hysteresis-aware digital reads
+    digital_state: bool,
+    /// Maps the normalized value to LED brightness, `None` for the value itself
+    led_source: Option<Box<dyn Fn(f64) -> f64 + Send>>,
+    /// Terminal voltages lighting the LED off and fully, `None` for the full scale
+    led_range: Option<(f64, f64)>,
+    /// Response of the LED brightness to the level from the source or range
+    led_curve: LedCurve,
+    /// Background sampler cache serving reads, if an `AnalogSampler` was started
+    sampler: Option<Arc<SampleCache>>,
+    /// Voltage and engineering value pairs used by `read_scaled`, sorted by voltage
+    transfer: Vec<(f64, f64)>,
+    /// How `read_scaled` treats voltages outside `transfer`
+    out_of_range: OutOfRange,
+    /// Gain and offset correction applied to every conversion
+    calibration: Calibration,
+    /// Output impedance of the connected source in ohms, 0 for no loading correction
+    source_impedance: f64,
+    /// Load the input presents to the source in ohms
+    input_impedance: f64,
 }
 
 impl AnalogInput {
     /// Creates a new analog input for the specified ADC channel.
     ///
+    /// Since 0.2 the driver is the crate's `Adc` wrapper rather than an `ads1x1x`
+    /// driver in continuous mode; wrap an existing driver with `Adc::from`.
+    ///
     /// # Arguments
     ///
     /// * `driver` - Shared reference to the ADS1015 ADC driver
@@ -44,20 +501,108 @@ impl AnalogInput {
     /// # Returns
     ///
     /// A new `AnalogInput` instance with the specified channel and LED
-    pub fn new(
-        driver: Arc<Mutex<Ads1x1x<I2cdev, Ads1015, Resolution12Bit, Continuous>>>,
+    pub fn new(driver: SharedAdc, led: Option<LED>, channel: u8) -> Self {
+        Self::new_with_max_value(driver, led, channel, crate::ANALOG_MAX_VALUE)
+    }
+
+    /// Creates a new analog input for the specified ADC channel with a custom full-scale value.
+    ///
+    /// Use this when the input's divider differs from the 0-25.85V range of the
+    /// three buffered inputs, such as the 3.3V fourth channel or a modified board.
+    ///
+    /// # Arguments
+    ///
+    /// * `driver` - Shared reference to the ADS1015 ADC driver
+    /// * `led` - Optional LED indicator for this input
+    /// * `channel` - The channel number (0-3) on the ADS1015 ADC
+    /// * `max_value` - The voltage that normalizes to a reading of 1.0
+    ///
+    /// # Returns
+    ///
+    /// A new `AnalogInput` instance with the specified channel, LED and full-scale value
+    pub fn new_with_max_value(
+        driver: SharedAdc,
         led: Option<LED>,
         channel: u8,
+        max_value: f64,
     ) -> Self {
+        let variant = driver.lock().unwrap().variant();
         AnalogInput {
-            driver,
+            driver: Some(driver),
             led,
             channel,
+            variant,
             value: 0.0,
-            max_value: 25.85,
+            max_value,
+            digital_state: false,
+            led_source: None,
+            led_range: None,
+            led_curve: LedCurve::default(),
+            sampler: None,
+            transfer: Vec::new(),
+            out_of_range: OutOfRange::default(),
+            calibration: Calibration::default(),
+            source_impedance: 0.0,
+            input_impedance: ADC_INPUT_IMPEDANCE_OHMS,
         }
     }
 
+    /// Creates an analog input that has no ADC behind it.
+    ///
+    /// This is used when analog support is disabled, or fails to initialize under
+    /// `analog_strict(false)`, at construction time. Every read of the returned instance
+    /// fails with `HatError::AnalogUnavailable`.
+    ///
+    /// # Arguments
+    ///
+    /// * `led` - Optional LED indicator for this input
+    /// * `channel` - The channel number (0-3) on the ADS1015 ADC
+    ///
+    /// # Returns
+    ///
+    /// A new `AnalogInput` instance that is not connected to an ADC
+    pub fn disabled(led: Option<LED>, channel: u8) -> Self {
+        AnalogInput {
+            driver: None,
+            led,
+            channel,
+            variant: AdcVariant::default(),
+            value: 0.0,
+            max_value: crate::ANALOG_MAX_VALUE,
+            digital_state: false,
+            led_source: None,
+            led_range: None,
+            led_curve: LedCurve::default(),
+            sampler: None,
+            transfer: Vec::new(),
+            out_of_range: OutOfRange::default(),
+            calibration: Calibration::default(),
+            source_impedance: 0.0,
+            input_impedance: ADC_INPUT_IMPEDANCE_OHMS,
+        }
+    }
+
+    /// Returns whether this input has an ADC behind it and can be read.
+    ///
+    /// Inputs created with `disabled`, or on a board built with analog support turned
+    /// off, are not available and fail every read.
+    pub fn is_available(&self) -> bool {
+        self.driver.is_some()
+    }
+
+    /// Returns `HatError::AnalogUnavailable` if this input has no ADC behind it.
+    pub(crate) fn ensure_available(&self) -> Result<()> {
+        match self.driver {
+            Some(_) => Ok(()),
+            None => Err(self.unavailable()),
+        }
+    }
+
+    /// Returns the error reported by operations on an input with no ADC behind it.
+    fn unavailable(&self) -> HatError {
+        HatError::AnalogUnavailable(self.channel as usize + 1)
+    }
+
     /// Reads the current value from the analog input.
     ///
     /// This method reads a raw value from the ADC, normalizes it to a value between
@@ -67,36 +612,1120 @@ impl AnalogInput {
     /// # Returns
     ///
     /// * `Ok(f64)` - The normalized input value between 0.0 and 1.0
-    /// * `Err(String)` - If reading the input or updating the LED failed
-    pub fn read(&mut self) -> Result<f64, String> {
-        let mut driver = self.driver.lock().unwrap();
-        match self.channel {
-            0 => driver
-                .select_channel(channel::SingleA0)
-                .map_err(|error| format!("Failed to read value from channel 0: {:?}", error)),
-            1 => driver
-                .select_channel(channel::SingleA1)
-                .map_err(|error| format!("Failed to read value from channel 1: {:?}", error)),
-            2 => driver
-                .select_channel(channel::SingleA2)
-                .map_err(|error| format!("Failed to read value from channel 2: {:?}", error)),
-            3 => driver
-                .select_channel(channel::SingleA3)
-                .map_err(|error| format!("Failed to read value from channel 3: {:?}", error)),
-            _ => return Err("Invalid channel".to_string()),
-        }?;
-
-        let value = driver.read().unwrap();
-
-        self.value = ((value as f64 / 10.0) * 2.048) / self.max_value;
-
-        if self.led.is_some() {
-            // Update LED brightness based on analog value
-            if let Err(e) = self.led.as_mut().unwrap().set_brightness(self.value) {
-                return Err(format!("Failed to update LED: {}", e));
+    /// * `Err(HatError::AnalogUnavailable)` - If the input has no ADC behind it; the ADC
+    ///   is never touched
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    pub fn read(&mut self) -> Result<f64> {
+        self.sample()?;
+        self.update_led()?;
+        Ok(self.value)
+    }
+
+    /// Calls `callback` from a background thread whenever the input moves by at
+    /// least `min_delta` volts.
+    ///
+    /// The watcher samples the input every 20ms and averages the last five samples,
+    /// so change detection runs on a smoothed voltage covering a 100ms window and
+    /// noise smaller than `min_delta` does not trigger the callback. The first
+    /// callback reports the initial smoothed voltage; after that, each change is
+    /// measured against the value last reported. Failed conversions are skipped.
+    /// While an `AnalogSampler` is running, the watcher reads from its cache.
+    ///
+    /// The watcher works on the shared ADC directly, so it neither updates `value`
+    /// nor the LED of this input.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_delta` - The change in terminal voltage, in volts, needed to trigger the callback
+    /// * `callback` - Called with the smoothed terminal voltage
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AnalogWatcher)` - A handle that stops the watcher when stopped or dropped
+    /// * `Err(HatError)` - If the input is unavailable or the thread could not be started
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let watcher = hat
+    ///     .analog_inputs
+    ///     .one
+    ///     .on_change(0.1, |volts| println!("Analog 1: {:.2}V", volts))?;
+    /// // ...
+    /// watcher.stop();
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn on_change(
+        &mut self,
+        min_delta: f64,
+        callback: impl FnMut(f64) + Send + 'static,
+    ) -> Result<AnalogWatcher> {
+        let Some(driver) = self.driver.clone() else {
+            return Err(self.unavailable());
+        };
+        let (variant, full_scale, correction) = (self.variant, self.max_value, self.correction());
+        AnalogWatcher::start(
+            driver,
+            self.sampler.clone(),
+            self.channel,
+            move |raw| correction.apply(terminal_voltage_from_raw(raw, variant, full_scale)),
+            min_delta.abs(),
+            callback,
+        )
+    }
+
+    /// Reads the analog input, retrying the conversion on transient ADC errors.
+    ///
+    /// Each failed conversion is retried after a short backoff (5ms, doubling each
+    /// time up to 100ms), so a single NACK on a noisy bus does not fail the caller. An
+    /// input with no ADC behind it fails immediately. The LED is updated once, after a
+    /// successful conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempts` - Total number of conversions to try; 0 is treated as 1
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The normalized input value between 0.0 and 1.0
+    /// * `Err(HatError)` - The last error if every attempt failed, or if updating the
+    ///   LED failed
+    pub fn read_retry(&mut self, attempts: u8) -> Result<f64> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            match self.sample() {
+                Ok(_) => break,
+                Err(error) if self.driver.is_none() || attempt >= attempts => {
+                    return Err(error);
+                }
+                Err(_) => {
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+                    attempt += 1;
+                }
             }
         }
+        self.update_led()?;
+        Ok(self.value)
+    }
+
+    /// Updates the LED, if attached, from the most recent reading.
+    fn update_led(&mut self) -> Result<()> {
+        if self.led.is_none() {
+            return Ok(());
+        }
+        // Update LED brightness based on analog value
+        let level = match (&self.led_source, self.led_range) {
+            (Some(source), _) => source(self.value),
+            (None, Some((min, max))) => (self.terminal_voltage() - min) / (max - min),
+            (None, None) => self.value,
+        };
+        let brightness = self.led_curve.apply(level);
+        if let Some(led) = &mut self.led {
+            led.set_brightness_clamped(brightness)?;
+        }
+        Ok(())
+    }
+
+    /// Sets how the LED brightness is derived from each reading.
+    ///
+    /// By default `read` lights the LED in proportion to the normalized value. A
+    /// source lets the LED show a derived signal instead, such as the distance from a
+    /// setpoint. The closure receives the normalized value (0.0-1.0) and its result is
+    /// clamped to 0.0-1.0. The value returned by `read` is unaffected.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Maps the normalized reading to an LED brightness
+    pub fn set_led_source(&mut self, source: impl Fn(f64) -> f64 + Send + 'static) {
+        self.led_source = Some(Box::new(source));
+        self.led_range = None;
+    }
+
+    /// Lights the LED over part of the input range instead of the full scale.
+    ///
+    /// A signal that only varies between, say, 2V and 4V barely lights the LED on a
+    /// 25.85V input. With a range set, the LED is off at or below `min` volts, fully
+    /// lit at or above `max` volts, and proportional in between. The value returned
+    /// by `read` is unaffected. This replaces any source set with `set_led_source`.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The terminal voltage at which the LED is off
+    /// * `max` - The terminal voltage at which the LED is fully lit
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the range was set
+    /// * `Err(HatError::Config)` - If either voltage is not finite or `min` is not below `max`
+    pub fn set_led_range(&mut self, min: f64, max: f64) -> Result<()> {
+        if !min.is_finite() || !max.is_finite() || min >= max {
+            return Err(HatError::Config(format!(
+                "Invalid LED range {}V to {}V; the minimum must be below the maximum",
+                min, max
+            )));
+        }
+        self.led_range = Some((min, max));
+        self.led_source = None;
+        Ok(())
+    }
+
+    /// Sets how the LED brightness responds to the signal.
+    ///
+    /// The default is linear. A logarithmic curve makes small signals clearly visible,
+    /// and a square curve keeps the LED dim until the signal approaches full scale.
+    /// The curve shapes whatever the LED would otherwise show, whether the normalized
+    /// value, a `set_led_range` range or a `set_led_source` mapping. The level is
+    /// clamped before the curve is applied, so over-range readings light the LED fully
+    /// rather than failing. The value returned by `read` is unaffected.
+    ///
+    /// # Arguments
+    ///
+    /// * `curve` - The response curve
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType, LedCurve};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // Make a 0-0.5V signal on a 25.85V input visible
+    /// hat.analog_inputs.one.set_led_curve(LedCurve::Logarithmic);
+    /// ```
+    pub fn set_led_curve(&mut self, curve: LedCurve) {
+        self.led_curve = curve;
+    }
+
+    /// Returns the response curve of the LED brightness.
+    pub fn led_curve(&self) -> LedCurve {
+        self.led_curve
+    }
+
+    /// Restores the default LED behavior of tracking the normalized value.
+    ///
+    /// Clears both a source set with `set_led_source` and a range set with
+    /// `set_led_range`. The LED curve is kept.
+    pub fn clear_led_source(&mut self) {
+        self.led_source = None;
+        self.led_range = None;
+    }
+
+    /// Reads the current value from the analog input as a voltage.
+    ///
+    /// The voltage is the normalized reading scaled by `max_value`, and the LED
+    /// is updated in the same way as `read`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The measured voltage in volts
+    /// * `Err(HatError::AnalogUnavailable)` - If the input has no ADC behind it
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    pub fn read_voltage(&mut self) -> Result<f64> {
+        self.ensure_available()?;
+        self.read()?;
+        Ok(self.terminal_voltage())
+    }
+
+    /// Defines a piecewise-linear map from terminal voltage to an engineering value,
+    /// used by `read_scaled`.
+    ///
+    /// Industrial sensors usually specify their output this way, e.g. a pressure
+    /// transducer reading 0 PSI at 0.5V and 100 PSI at 4.5V. Between points the value
+    /// is interpolated linearly; outside them it is clamped by default (see
+    /// `set_out_of_range`).
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - At least two `(volts, value)` pairs with strictly increasing voltages
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the transfer function was set
+    /// * `Err(HatError::Config)` - If there are fewer than two points, a point is not
+    ///   finite, or the voltages are not strictly increasing
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.analog_inputs.one.set_transfer(&[(0.5, 0.0), (4.5, 100.0)])?;
+    /// let psi = hat.analog_inputs.one.read_scaled()?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn set_transfer(&mut self, points: &[(f64, f64)]) -> Result<()> {
+        if points.len() < 2 {
+            return Err(HatError::Config(
+                "A transfer function needs at least two points".to_string(),
+            ));
+        }
+        if points
+            .iter()
+            .any(|(volts, value)| !volts.is_finite() || !value.is_finite())
+        {
+            return Err(HatError::Config(
+                "Transfer function points must be finite".to_string(),
+            ));
+        }
+        if points.windows(2).any(|pair| pair[1].0 <= pair[0].0) {
+            return Err(HatError::Config(
+                "Transfer function voltages must be strictly increasing".to_string(),
+            ));
+        }
+        self.transfer = points.to_vec();
+        Ok(())
+    }
+
+    /// Removes the transfer function, after which `read_scaled` fails.
+    pub fn clear_transfer(&mut self) {
+        self.transfer.clear();
+    }
+
+    /// Sets how `read_scaled` treats voltages outside the transfer function.
+    pub fn set_out_of_range(&mut self, out_of_range: OutOfRange) {
+        self.out_of_range = out_of_range;
+    }
+
+    /// Reads the input and maps its terminal voltage through the transfer function.
+    ///
+    /// The LED is updated in the same way as `read`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The reading in the units of the transfer function
+    /// * `Err(HatError)` - If no transfer function is set, or reading the input or
+    ///   updating the LED failed
+    pub fn read_scaled(&mut self) -> Result<f64> {
+        if self.transfer.is_empty() {
+            return Err(HatError::Config(format!(
+                "Analog input {} has no transfer function",
+                self.channel + 1
+            )));
+        }
+        let volts = self.read_voltage()?;
+        Ok(apply_transfer(&self.transfer, self.out_of_range, volts))
+    }
 
+    /// Starts a two-point calibration of this input.
+    ///
+    /// Applying two accurately known voltages and measuring each one corrects both
+    /// the gain error of the input divider and any offset. Choose points near the
+    /// ends of the range you care about. The returned `TwoPointCalibration` asks for
+    /// each voltage in turn; once both are captured, `finish` stores the correction on
+    /// this input and returns it so it can be saved and restored with
+    /// `set_calibration` on later runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_known` - The lower reference voltage, applied first
+    /// * `high_known` - The higher reference voltage, applied second
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TwoPointCalibration)` - The calibration, ready for the first point
+    /// * `Err(HatError::Config)` - If a voltage is not finite or `low_known` is not
+    ///   below `high_known`
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::io::stdin;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let mut calibration = hat.analog_inputs.one.calibrate_two_point(1.0, 20.0)?;
+    /// while let Some(volts) = calibration.next_point() {
+    ///     println!("Apply {}V to analog input 1, then press Enter", volts);
+    ///     stdin().read_line(&mut String::new()).unwrap();
+    ///     calibration.capture()?;
+    /// }
+    /// let coefficients = calibration.finish()?;
+    /// println!("gain {} offset {}", coefficients.gain, coefficients.offset);
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn calibrate_two_point(
+        &mut self,
+        low_known: f64,
+        high_known: f64,
+    ) -> Result<TwoPointCalibration<'_>> {
+        if self.driver.is_none() {
+            return Err(self.unavailable());
+        }
+        TwoPointCalibration::new(self, low_known, high_known)
+    }
+
+    /// Applies a gain and offset correction to every subsequent reading.
+    ///
+    /// Use this to restore coefficients saved from an earlier `calibrate_two_point`.
+    ///
+    /// # Arguments
+    ///
+    /// * `calibration` - The correction to apply
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the correction was applied
+    /// * `Err(HatError::Config)` - If the gain or offset is not finite, or the gain is
+    ///   not positive
+    pub fn set_calibration(&mut self, calibration: Calibration) -> Result<()> {
+        if !calibration.gain.is_finite() || !calibration.offset.is_finite() {
+            return Err(HatError::Config(
+                "Calibration coefficients must be finite".to_string(),
+            ));
+        }
+        if calibration.gain <= 0.0 {
+            return Err(HatError::Config(
+                "Calibration gain must be positive".to_string(),
+            ));
+        }
+        self.calibration = calibration;
+        Ok(())
+    }
+
+    /// Returns the correction applied to this input's readings.
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Removes any calibration, so readings are taken from the nominal divider ratio.
+    pub fn clear_calibration(&mut self) {
+        self.calibration = Calibration::default();
+    }
+
+    /// Corrects readings for the voltage lost across a high-impedance source.
+    ///
+    /// A source with output impedance `R_s` driving an input that loads it with
+    /// `R_in` only delivers `R_in / (R_s + R_in)` of its open-circuit voltage, so
+    /// high-impedance sensors and dividers read low. With the source impedance set,
+    /// every reading is scaled up by `(R_s + R_in) / R_in` before any calibration is
+    /// applied. This is a first-order, resistive correction: it assumes the source
+    /// has settled between conversions and ignores its capacitance.
+    ///
+    /// `R_in` defaults to `ADC_INPUT_IMPEDANCE_OHMS`, the ADC's own input impedance,
+    /// which is what a source sees on an unbuffered input. On inputs with a divider
+    /// ahead of the ADC, set the resistance measured across the terminals with
+    /// `set_input_impedance`. The ADC's impedance does not depend on its data rate, so
+    /// the correction is the same at every sample rate.
+    ///
+    /// # Arguments
+    ///
+    /// * `ohms` - The source's output impedance; 0 disables the correction
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the correction was set
+    /// * `Err(HatError::Config)` - If `ohms` is negative or not finite
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // A 1MΩ thermistor bridge read directly by the ADC
+    /// hat.analog_inputs.four.set_source_impedance(1.0e6)?;
+    /// let volts = hat.analog_inputs.four.read_voltage()?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn set_source_impedance(&mut self, ohms: f64) -> Result<()> {
+        if !ohms.is_finite() || ohms < 0.0 {
+            return Err(HatError::Config(format!(
+                "Source impedance must be a finite, non-negative resistance, not {}",
+                ohms
+            )));
+        }
+        self.source_impedance = ohms;
+        Ok(())
+    }
+
+    /// Returns the source impedance used for the loading correction, in ohms.
+    pub fn source_impedance(&self) -> f64 {
+        self.source_impedance
+    }
+
+    /// Sets the load this input presents to its source, used by the loading
+    /// correction of `set_source_impedance`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ohms` - The resistance between the input terminal and ground
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the input impedance was set
+    /// * `Err(HatError::Config)` - If `ohms` is not a finite, positive resistance
+    pub fn set_input_impedance(&mut self, ohms: f64) -> Result<()> {
+        if !ohms.is_finite() || ohms <= 0.0 {
+            return Err(HatError::Config(format!(
+                "Input impedance must be a finite, positive resistance, not {}",
+                ohms
+            )));
+        }
+        self.input_impedance = ohms;
+        Ok(())
+    }
+
+    /// Returns the load this input presents to its source, in ohms.
+    pub fn input_impedance(&self) -> f64 {
+        self.input_impedance
+    }
+
+    /// Converts this input `samples` times and returns the mean terminal voltage
+    /// without calibration.
+    ///
+    /// The conversions are distinct and bypass any running sampler, as in
+    /// `read_stats`. The source impedance correction is applied. Neither `value` nor
+    /// the LED is updated.
+    pub(crate) fn read_uncalibrated_mean(&mut self, samples: usize) -> Result<f64> {
+        let raws = self.convert_burst(samples)?;
+        let total: f64 = raws
+            .iter()
+            .map(|&raw| terminal_voltage_from_raw(raw, self.variant, self.max_value))
+            .sum();
+        Ok(total / raws.len() as f64 * self.loading_factor())
+    }
+
+    /// Reads the terminal voltage of the analog input as a typed quantity.
+    ///
+    /// Identical to `read_voltage`, but the result carries its unit so it can be
+    /// combined with other `uom` quantities without unit-confusion bugs.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ElectricPotential)` - The measured voltage
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    #[cfg(feature = "uom")]
+    pub fn read_electric_potential(&mut self) -> Result<ElectricPotential> {
+        let volts = self.read_voltage()?;
+        Ok(ElectricPotential::new::<volt>(volts))
+    }
+
+    /// Reads the current value from the analog input as a percentage of full scale.
+    ///
+    /// This is `read` scaled to 0-100, and updates the LED in the same way. Use
+    /// `read` for a normalized 0.0-1.0 value or `read_voltage` for volts.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The input value between 0.0 and 100.0
+    /// * `Err(HatError::AnalogUnavailable)` - If the input has no ADC behind it
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    pub fn read_percent(&mut self) -> Result<f64> {
+        self.ensure_available()?;
+        let value = self.read()?;
+        Ok(value * 100.0)
+    }
+
+    /// Returns the terminal voltage of the most recent reading.
+    ///
+    /// This does not perform a conversion; it reports the last value captured by
+    /// `read` (or any of the other read methods) in volts at the input terminal.
+    ///
+    /// # Returns
+    ///
+    /// The last measured terminal voltage in volts
+    pub fn terminal_voltage(&self) -> f64 {
+        self.value * self.max_value
+    }
+
+    /// Returns the terminal voltage of the most recent reading as a typed quantity.
+    ///
+    /// Like `terminal_voltage`, this does not perform a conversion.
+    ///
+    /// # Returns
+    ///
+    /// The voltage at the input terminal as an `ElectricPotential`
+    #[cfg(feature = "uom")]
+    pub fn terminal_electric_potential(&self) -> ElectricPotential {
+        ElectricPotential::new::<volt>(self.terminal_voltage())
+    }
+
+    /// Reads the analog input as a digital signal by comparing its voltage to a threshold.
+    ///
+    /// If an LED is attached it is driven fully on or off to match the result,
+    /// rather than proportionally as in `read`.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_volts` - The voltage above which the input reads as high
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If the voltage is above the threshold
+    /// * `Ok(false)` - If the voltage is at or below the threshold
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    pub fn read_as_digital(&mut self, threshold_volts: f64) -> Result<bool> {
+        self.read_as_digital_with_hysteresis(threshold_volts, 0.0)
+    }
+
+    /// Reads the analog input as a digital signal with hysteresis.
+    ///
+    /// The input reads high once its voltage rises above `threshold_volts`, and only
+    /// reads low again once it falls below `threshold_volts - hysteresis_volts`. The
+    /// last reported state is kept between calls, so noise around the threshold does
+    /// not cause the result to chatter.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold_volts` - The voltage above which the input switches high
+    /// * `hysteresis_volts` - How far below the threshold the input must fall to switch low
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - The debounced digital state of the input
+    /// * `Err(HatError)` - If reading the input or updating the LED failed
+    pub fn read_as_digital_with_hysteresis(
+        &mut self,
+        threshold_volts: f64,
+        hysteresis_volts: f64,
+    ) -> Result<bool> {
+        self.ensure_available()?;
+        self.sample()?;
+        let voltage = self.terminal_voltage();
+
+        self.digital_state = match self.digital_state {
+            true => voltage >= threshold_volts - hysteresis_volts,
+            false => voltage > threshold_volts,
+        };
+
+        if let Some(led) = &mut self.led {
+            led.set_brightness_clamped(match self.digital_state {
+                true => 1.0,
+                false => 0.0,
+            })?;
+        }
+
+        Ok(self.digital_state)
+    }
+
+    /// Reads the analog input as a Schmitt trigger with explicit switching thresholds.
+    ///
+    /// The input reads high once its terminal voltage rises above `on_threshold` and
+    /// low again only once it falls below `off_threshold`, giving noise-immune
+    /// digital sensing of a signal wired to an analog channel. The state is kept
+    /// between calls (and shared with `read_as_digital_with_hysteresis`), so
+    /// repeated calls behave as a true Schmitt trigger.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_threshold` - The voltage above which the input switches high
+    /// * `off_threshold` - The voltage below which the input switches low
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - The trigger state after this reading
+    /// * `Err(HatError)` - If `off_threshold` is above `on_threshold`, or reading the
+    ///   input or updating the LED failed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // A 12V signal: on above 8V, off below 4V
+    /// let active = hat.analog_inputs.one.read_schmitt(8.0, 4.0)?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn read_schmitt(&mut self, on_threshold: f64, off_threshold: f64) -> Result<bool> {
+        if off_threshold > on_threshold {
+            return Err(HatError::Config(format!(
+                "Off threshold {}V is above on threshold {}V",
+                off_threshold, on_threshold
+            )));
+        }
+        self.read_as_digital_with_hysteresis(on_threshold, on_threshold - off_threshold)
+    }
+
+    /// Converts this input `samples` times in a row and summarizes the voltages.
+    ///
+    /// All conversions are taken under a single lock of the ADC, so no other reader
+    /// can move the multiplexer between them, and each is a distinct conversion of
+    /// this channel: in continuous mode reads are spaced one conversion period apart,
+    /// and in one-shot mode each read starts its own conversion. They bypass any
+    /// running `AnalogSampler`. A burst takes `samples` periods at the default data
+    /// rate, about 0.7 ms each on the ADS1015 and 8.6 ms on the ADS1115, and other
+    /// users of the ADC wait for all of it. `value` is set to the mean and the LED is
+    /// left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `samples` - The number of conversions, at least 1
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AnalogStats)` - The mean, range and standard deviation of the voltages
+    /// * `Err(HatError::Config)` - If `samples` is zero
+    /// * `Err(HatError)` - If the input is unavailable or a conversion failed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let stats = hat.analog_inputs.one.read_stats(64)?;
+    /// println!("{:.3}V ± {:.3}V", stats.mean, stats.std_dev);
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn read_stats(&mut self, samples: usize) -> Result<AnalogStats> {
+        if samples == 0 {
+            return Err(HatError::Config(
+                "At least one sample is needed for statistics".to_string(),
+            ));
+        }
+        let raws = self.convert_burst(samples)?;
+        let stats = summarize(&raws, |raw| self.corrected_voltage(raw));
+        self.value = stats.mean / self.max_value;
+        Ok(stats)
+    }
+
+    /// Reads this input as a fraction of a reference input's voltage.
+    ///
+    /// Ratiometric sensors (potentiometers, many pressure sensors) output a fraction of
+    /// their supply voltage. Reading the supply on a reference channel, such as the 3.3V
+    /// fourth input, and dividing cancels out supply drift. Both conversions happen
+    /// under a single lock of the shared ADC so no other reader can interleave; in
+    /// continuous mode each waits for the ADC to settle on its channel, so the pair
+    /// takes two settling times rather than two I2C reads.
+    ///
+    /// Both inputs' `value` fields are updated; their LEDs are left untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - The input measuring the sensor's supply voltage
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - This input's voltage divided by the reference voltage
+    /// * `Err(HatError)` - If either input is unavailable, a read fails, or the reference
+    ///   voltage is zero
+    pub fn read_ratiometric(&mut self, reference: &mut AnalogInput) -> Result<f64> {
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        let Some(reference_driver) = &reference.driver else {
+            return Err(reference.unavailable());
+        };
+
+        let (raw, reference_raw) = if Arc::ptr_eq(driver, reference_driver) {
+            let mut driver = driver.lock().unwrap();
+            let raw = convert(&mut driver, self.channel)?;
+            let reference_raw = convert(&mut driver, reference.channel)?;
+            (raw, reference_raw)
+        } else {
+            let raw = convert(&mut driver.lock().unwrap(), self.channel)?;
+            let reference_raw = convert(&mut reference_driver.lock().unwrap(), reference.channel)?;
+            (raw, reference_raw)
+        };
+
+        self.value = self.corrected_voltage(raw) / self.max_value;
+        reference.value = reference.corrected_voltage(reference_raw) / reference.max_value;
+
+        let reference_voltage = reference.terminal_voltage();
+        if reference_voltage == 0.0 {
+            return Err(HatError::Analog("Reference voltage is zero".to_string()));
+        }
+
+        Ok(self.terminal_voltage() / reference_voltage)
+    }
+
+    /// Reads a potentiometer as a position from 0.0 to 1.0, independent of its supply.
+    ///
+    /// A potentiometer wired across a supply outputs a fixed fraction of it, so its
+    /// voltage drifts with the supply while the knob stays put. This reads the wiper
+    /// on this input and the supply on analog input 4, the rail monitor, under a
+    /// single lock of the ADC, and divides one by the other.
+    ///
+    /// Wire the potentiometer's ends to the supply (typically the Pi's 3.3V) and GND,
+    /// its wiper to this input, and the same supply to analog input 4. This input's
+    /// calibration applies to the wiper; the rail is read uncalibrated. Use
+    /// `read_ratiometric` instead to read the rail through a calibrated input. The
+    /// result is clamped to 0.0-1.0 so noise at the ends of the track stays in range.
+    /// `value` is updated and the LED left untouched.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The wiper position, 0.0 at the GND end and 1.0 at the supply end
+    /// * `Err(HatError::Config)` - If called on analog input 4, or input 4 reads below
+    ///   1V because the supply is not wired to it
+    /// * `Err(HatError)` - If the input is unavailable or a read fails
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let knob = hat.analog_inputs.one.read_pot()?;
+    /// println!("Knob at {:.0}%", knob * 100.0);
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn read_pot(&mut self) -> Result<f64> {
+        if self.channel == RAIL_CHANNEL {
+            return Err(HatError::Config(
+                "Analog input 4 monitors the rail and cannot read a potentiometer".to_string(),
+            ));
+        }
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        let (raw, rail_raw) = {
+            let mut driver = driver.lock().unwrap();
+            let raw = convert(&mut driver, self.channel)?;
+            let rail_raw = convert(&mut driver, RAIL_CHANNEL)?;
+            (raw, rail_raw)
+        };
+
+        self.value = self.corrected_voltage(raw) / self.max_value;
+        // Input 4 has no divider, whatever this input's full scale
+        let rail = terminal_voltage_from_raw(rail_raw, self.variant, ADC_INPUT_SPAN_VOLTS);
+        if rail < MIN_RAIL_VOLTS {
+            return Err(HatError::Config(format!(
+                "Analog input 4 reads {:.2}V; wire the potentiometer's supply to it",
+                rail
+            )));
+        }
+        Ok((self.terminal_voltage() / rail).clamp(0.0, 1.0))
+    }
+
+    /// Points the ADS1015 comparator at this channel with the given window.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_volts` - Terminal voltage below which the comparator de-asserts
+    /// * `high_volts` - Terminal voltage above which the comparator asserts
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the thresholds were written
+    /// * `Err(HatError)` - If the input is unavailable or the ADC could not be configured
+    pub(crate) fn set_comparator(&mut self, low_volts: f64, high_volts: f64) -> Result<()> {
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        let low = raw_from_terminal_voltage(low_volts, self.variant, self.max_value);
+        let high = raw_from_terminal_voltage(high_volts, self.variant, self.max_value);
+        point_comparator(&mut driver.lock().unwrap(), self.channel, low, high)
+            .inspect_err(|_| instrumentation::i2c_error("ads1015"))
+    }
+
+    /// Disables the ADS1015 comparator, if this input has an ADC behind it.
+    pub(crate) fn clear_comparator(&mut self) -> Result<()> {
+        let Some(driver) = &self.driver else {
+            return Ok(());
+        };
+        match &mut driver.lock().unwrap().mode {
+            Some(AdcMode::Continuous(driver)) => driver.disable_comparator(),
+            Some(AdcMode::OneShot(driver)) => driver.disable_comparator(),
+            Some(AdcMode::Continuous16(driver)) => driver.disable_comparator(),
+            Some(AdcMode::OneShot16(driver)) => driver.disable_comparator(),
+            None => return Ok(()),
+        }
+        .map_err(|error| {
+            instrumentation::i2c_error("ads1015");
+            HatError::Analog(format!("Failed to disable comparator: {:?}", error))
+        })
+    }
+
+    /// Switches the shared ADC between continuous and one-shot conversion.
+    pub(crate) fn set_continuous(&mut self, continuous: bool) -> Result<()> {
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        driver.lock().unwrap().set_continuous(continuous)
+    }
+
+    /// Returns whether the shared ADC is converting continuously, or `None` if this
+    /// input has no ADC behind it.
+    pub(crate) fn is_continuous(&self) -> Option<bool> {
+        self.driver
+            .as_ref()
+            .map(|driver| driver.lock().unwrap().is_continuous())
+    }
+
+    /// Returns the shared ADC behind this input, if any.
+    pub(crate) fn driver(&self) -> Option<&SharedAdc> {
+        self.driver.as_ref()
+    }
+
+    /// Converts this channel on an ADC the caller has already locked, bypassing any
+    /// background sampler, and updates `value`. The LED is not touched.
+    ///
+    /// # Returns
+    ///
+    /// The reading, or `HatError::Analog` if the conversion failed
+    pub(crate) fn sample_locked(&mut self, driver: &mut Adc) -> Result<AnalogReading> {
+        let started = instrumentation::analog_read_started();
+        let raw = convert(driver, self.channel)?;
+        instrumentation::analog_read_finished(self.channel, started);
+        self.value = self.corrected_voltage(raw) / self.max_value;
+        Ok(AnalogReading {
+            value: self.value,
+            voltage: self.terminal_voltage(),
+        })
+    }
+
+    /// Swaps the indicator LED, e.g. when the board is reconfigured for another
+    /// variant. The new LED catches up on the next `read`.
+    pub(crate) fn replace_led(&mut self, led: Option<LED>) {
+        self.led = led;
+    }
+
+    /// Routes reads of this input through a background sampler's cache.
+    ///
+    /// # Returns
+    ///
+    /// The ADS1015 channel the sampler should convert for this input
+    pub(crate) fn attach_sampler(&mut self, cache: Arc<SampleCache>) -> u8 {
+        self.sampler = Some(cache);
+        self.channel
+    }
+
+    /// Converts this channel `samples` times under one lock of the ADC, bypassing any
+    /// sampler, and returns the raw readings.
+    fn convert_burst(&self, samples: usize) -> Result<Vec<i16>> {
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        let mut driver = driver.lock().unwrap();
+        // A continuous-mode read returns the latest conversion, so reads closer
+        // together than a period would see the same one twice
+        let spacing = driver
+            .is_continuous()
+            .then(|| driver.variant().conversion_period());
+        let started = instrumentation::analog_read_started();
+        let mut raws = Vec::with_capacity(samples);
+        for index in 0..samples {
+            if let Some(spacing) = spacing
+                && index > 0
+            {
+                thread::sleep(spacing);
+            }
+            raws.push(convert(&mut driver, self.channel)?);
+        }
+        instrumentation::analog_read_finished(self.channel, started);
+        Ok(raws)
+    }
+
+    /// Converts a raw reading into the calibrated terminal voltage of this input.
+    fn corrected_voltage(&self, raw: i16) -> f64 {
+        self.correction()
+            .apply(terminal_voltage_from_raw(raw, self.variant, self.max_value))
+    }
+
+    /// Returns the factor by which the source impedance correction scales readings.
+    fn loading_factor(&self) -> f64 {
+        (self.source_impedance + self.input_impedance) / self.input_impedance
+    }
+
+    /// Returns the loading correction and calibration combined into one linear map.
+    fn correction(&self) -> Calibration {
+        Calibration {
+            gain: self.calibration.gain * self.loading_factor(),
+            offset: self.calibration.offset,
+        }
+    }
+
+    /// Performs a conversion on this channel and updates `value` without touching the LED.
+    ///
+    /// While a background sampler is running, its latest sample is used instead.
+    fn sample(&mut self) -> Result<f64> {
+        let raw = self.sample_raw()?;
+        self.value = self.corrected_voltage(raw) / self.max_value;
         Ok(self.value)
     }
+
+    /// Returns the raw reading of this channel, from the background sampler if one
+    /// is running and from a direct conversion otherwise.
+    fn sample_raw(&mut self) -> Result<i16> {
+        let Some(driver) = &self.driver else {
+            return Err(self.unavailable());
+        };
+        let cached = self
+            .sampler
+            .as_ref()
+            .and_then(|cache| cache.latest(self.channel));
+        match cached {
+            Some(result) => result,
+            None => {
+                let started = instrumentation::analog_read_started();
+                let value = convert(&mut driver.lock().unwrap(), self.channel)?;
+                instrumentation::analog_read_finished(self.channel, started);
+                Ok(value)
+            }
+        }
+    }
+}
+
+/// Summarizes a non-empty burst of raw conversions, converted to volts by `to_volts`.
+///
+/// The standard deviation is the population one, describing the burst itself.
+fn summarize(raws: &[i16], to_volts: impl Fn(i16) -> f64) -> AnalogStats {
+    let volts: Vec<f64> = raws.iter().map(|raw| to_volts(*raw)).collect();
+    let samples = volts.len();
+    let mean = volts.iter().sum::<f64>() / samples as f64;
+    let variance = volts.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / samples as f64;
+    AnalogStats {
+        samples,
+        mean,
+        min: volts.iter().copied().fold(f64::INFINITY, f64::min),
+        max: volts.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        std_dev: variance.sqrt(),
+    }
+}
+
+/// Maps `volts` through a piecewise-linear transfer function of two or more points.
+fn apply_transfer(points: &[(f64, f64)], out_of_range: OutOfRange, volts: f64) -> f64 {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if out_of_range == OutOfRange::Clamp {
+        if volts <= first.0 {
+            return first.1;
+        }
+        if volts >= last.0 {
+            return last.1;
+        }
+    }
+    // The segment containing `volts`, or the end segment it extends beyond
+    let segment = points
+        .windows(2)
+        .find(|pair| volts < pair[1].0)
+        .unwrap_or(&points[points.len() - 2..]);
+    let ((v0, y0), (v1, y1)) = (segment[0], segment[1]);
+    y0 + (volts - v0) * (y1 - y0) / (v1 - v0)
+}
+
+/// Converts `channel` on the locked ADC and returns the raw conversion result.
+///
+/// In continuous mode the latest conversion is read. If the multiplexer was on
+/// another channel, it is switched first and the read waits out
+/// `AdcVariant::settle_time`, since the chip keeps returning conversions of the old
+/// channel until then. In one-shot mode a single conversion is started and waited
+/// for. The result is in the converter's own resolution; see
+/// `terminal_voltage_from_raw`.
+pub(crate) fn convert(driver: &mut Adc, channel: u8) -> Result<i16> {
+    // Forgotten until the read succeeds, so a failure leaves the multiplexer unknown
+    let selected = driver.selected.take();
+    let settle = driver.variant.settle_time();
+    let raw = match &mut driver.mode {
+        Some(AdcMode::Continuous(adc)) => read_continuous!(adc, channel, selected, settle),
+        Some(AdcMode::Continuous16(adc)) => read_continuous!(adc, channel, selected, settle),
+        Some(AdcMode::OneShot(adc)) => read_one_shot!(adc, channel),
+        Some(AdcMode::OneShot16(adc)) => read_one_shot!(adc, channel),
+        None => Err(HatError::Analog(
+            "ADC driver was lost by an earlier mode change".to_string(),
+        )),
+    }
+    .inspect_err(|_| instrumentation::i2c_error("ads1015"))?;
+    driver.selected = driver.is_continuous().then_some(channel);
+    Ok(raw)
+}
+
+/// Writes a comparator window to the locked ADC and, in continuous mode, points its
+/// input multiplexer at `channel`.
+fn point_comparator(driver: &mut Adc, channel: u8, low: i16, high: i16) -> Result<()> {
+    // The multiplexer moves without waiting for a conversion, so the next read settles
+    driver.selected = None;
+    match &mut driver.mode {
+        Some(AdcMode::Continuous(driver)) => {
+            select_channel!(driver, channel).and_then(|_| configure_comparator!(driver, low, high))
+        }
+        Some(AdcMode::Continuous16(driver)) => {
+            select_channel!(driver, channel).and_then(|_| configure_comparator!(driver, low, high))
+        }
+        // Each one-shot conversion selects its own channel, so there is nothing to point
+        Some(AdcMode::OneShot(driver)) => configure_comparator!(driver, low, high),
+        Some(AdcMode::OneShot16(driver)) => configure_comparator!(driver, low, high),
+        None => Err(HatError::Analog(
+            "ADC driver was lost by an earlier mode change".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_reports_mean_range_and_population_deviation() {
+        let stats = summarize(&[10, 20, 30, 40], |raw| raw as f64 / 10.0);
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 4.0);
+        assert!((stats.std_dev - 1.25_f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn summarize_of_a_steady_signal_has_no_deviation() {
+        let stats = summarize(&[-512; 16], |raw| raw as f64);
+        assert_eq!(stats.mean, -512.0);
+        assert_eq!((stats.min, stats.max), (-512.0, -512.0));
+        assert_eq!(stats.std_dev, 0.0);
+    }
+
+    #[test]
+    fn terminal_voltage_applies_only_the_divider_of_the_input() {
+        // 3.3V at the ADC is 1650 ADS1015 or 26400 ADS1115 counts in the ±4.096V range
+        let cases = [(AdcVariant::Ads1015, 1650), (AdcVariant::Ads1115, 26400)];
+        for (variant, raw) in cases {
+            assert!((terminal_voltage_from_raw(raw, variant, 25.85) - 25.85).abs() < 1e-9);
+            assert!((terminal_voltage_from_raw(raw, variant, 3.3) - 3.3).abs() < 1e-9);
+            assert_eq!(raw_from_terminal_voltage(25.85, variant, 25.85), raw);
+            assert_eq!(raw_from_terminal_voltage(3.3, variant, 3.3), raw);
+        }
+    }
+
+    #[test]
+    fn raw_from_terminal_voltage_clamps_to_the_converter_range() {
+        assert_eq!(
+            raw_from_terminal_voltage(100.0, AdcVariant::Ads1015, 25.85),
+            2047
+        );
+        assert_eq!(
+            raw_from_terminal_voltage(-100.0, AdcVariant::Ads1015, 25.85),
+            -2048
+        );
+    }
+
+    #[test]
+    fn summarize_applies_the_conversion_before_the_statistics() {
+        let raws = [100, 300];
+        let to_volts = |raw| terminal_voltage_from_raw(raw, AdcVariant::Ads1015, 25.85);
+        let stats = summarize(&raws, to_volts);
+        let (low, high) = (to_volts(100), to_volts(300));
+        assert!((stats.mean - (low + high) / 2.0).abs() < 1e-12);
+        assert!((stats.std_dev - (high - low) / 2.0).abs() < 1e-12);
+    }
+
+    // A 0.5-4.5V pressure sensor reading 0-10 bar, with a kink at 2.5V
+    const SENSOR: [(f64, f64); 3] = [(0.5, 0.0), (2.5, 6.0), (4.5, 10.0)];
+
+    #[test]
+    fn transfer_interpolates_within_each_segment() {
+        for out_of_range in [OutOfRange::Clamp, OutOfRange::Extrapolate] {
+            assert_eq!(apply_transfer(&SENSOR, out_of_range, 0.5), 0.0);
+            assert_eq!(apply_transfer(&SENSOR, out_of_range, 1.5), 3.0);
+            assert_eq!(apply_transfer(&SENSOR, out_of_range, 2.5), 6.0);
+            assert_eq!(apply_transfer(&SENSOR, out_of_range, 3.5), 8.0);
+            assert_eq!(apply_transfer(&SENSOR, out_of_range, 4.5), 10.0);
+        }
+    }
+
+    #[test]
+    fn transfer_clamps_to_the_end_points() {
+        assert_eq!(apply_transfer(&SENSOR, OutOfRange::Clamp, 0.0), 0.0);
+        assert_eq!(apply_transfer(&SENSOR, OutOfRange::Clamp, 5.0), 10.0);
+    }
+
+    #[test]
+    fn transfer_extrapolates_the_end_segments() {
+        assert_eq!(apply_transfer(&SENSOR, OutOfRange::Extrapolate, 0.0), -1.5);
+        assert_eq!(apply_transfer(&SENSOR, OutOfRange::Extrapolate, 5.0), 11.0);
+    }
 }
@@ -0,0 +1,144 @@
+//! Background LED animations for Automation HAT boards.
+//!
+//! This module provides the `Animation` handle returned by animated LED effects such as
+//! [`LED::blink`](crate::LED::blink). Every animation runs on its own thread and is
+//! tracked in a process-wide registry so that all of them can be halted at once with
+//! [`AutomationHAT::stop_all_animations`](crate::AutomationHAT::stop_all_animations).
+
+use crate::lights::LED;
+use crate::worker::{StopFlag, Worker};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// Registry of every animation thread that is still running
+static ANIMATIONS: OnceLock<Mutex<Vec<Running>>> = OnceLock::new();
+
+// Source of unique animation identifiers
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// State an LED is left in once its animation is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnimationEnd {
+    /// Turn the LED off
+    #[default]
+    Off,
+    /// Leave the LED showing the last frame that was drawn
+    LastFrame,
+    /// Return the LED to the brightness it had when the animation started
+    Restore,
+}
+
+/// A registered animation thread.
+struct Running {
+    /// Identifier shared with the `Animation` handle
+    id: u64,
+    /// The animation thread
+    worker: Worker,
+    /// The LED being animated, used to apply the end state
+    led: LED,
+}
+
+impl Running {
+    /// Signals the thread to stop, waits for it, and applies the end state.
+    fn finish(self, end: AnimationEnd) {
+        let Running {
+            mut worker,
+            mut led,
+            ..
+        } = self;
+        worker.join();
+        match end {
+            AnimationEnd::Off => {
+                let _ = led.off();
+            }
+            AnimationEnd::LastFrame => {}
+            AnimationEnd::Restore => {
+                let _ = led.set_brightness(led.brightness);
+            }
+        }
+    }
+}
+
+/// Handle to an LED animation running in the background.
+///
+/// Dropping the handle does not stop the animation; call `stop` on the handle or
+/// [`AutomationHAT::stop_all_animations`](crate::AutomationHAT::stop_all_animations).
+#[derive(Debug)]
+pub struct Animation {
+    /// Identifier of the animation in the registry
+    id: u64,
+    /// Flag telling the thread to exit, also set once it finishes on its own
+    stop: StopFlag,
+}
+
+impl Animation {
+    /// Stops this animation and waits for its thread to exit.
+    ///
+    /// # Arguments
+    ///
+    /// * `end` - The state to leave the LED in
+    pub fn stop(self, end: AnimationEnd) {
+        let running = {
+            let mut animations = registry().lock().unwrap();
+            animations
+                .iter()
+                .position(|running| running.id == self.id)
+                .map(|index| animations.swap_remove(index))
+        };
+        if let Some(running) = running {
+            running.finish(end);
+        }
+    }
+
+    /// Returns whether the animation thread is still running.
+    pub fn is_running(&self) -> bool {
+        !self.stop.is_set()
+    }
+}
+
+/// Returns the global animation registry.
+fn registry() -> &'static Mutex<Vec<Running>> {
+    ANIMATIONS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Spawns a new animation thread driving `led`.
+///
+/// `step` is called repeatedly to draw the next frame and returns how long to wait
+/// before the following frame, or `None` once the animation has finished.
+pub(crate) fn spawn<F>(led: LED, mut step: F) -> Animation
+where
+    F: FnMut(&mut LED) -> Option<Duration> + Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+
+    let mut thread_led = led.clone();
+    // Like `thread::spawn`, panics if the thread cannot be started
+    let worker = Worker::spawn(format!("automation-hat-animation-{}", id), move |stop| {
+        while !stop.is_set() {
+            match step(&mut thread_led) {
+                Some(delay) => stop.sleep_until(Instant::now() + delay),
+                None => break,
+            }
+        }
+        stop.set();
+    })
+    .unwrap();
+    let stop = worker.stop_flag();
+
+    let mut animations = registry().lock().unwrap();
+    // Forget animations that have already finished on their own
+    animations.retain(|running| !running.worker.is_finished());
+    animations.push(Running { id, worker, led });
+
+    Animation { id, stop }
+}
+
+/// Stops every running animation, waits for their threads, and applies the end state.
+pub(crate) fn stop_all(end: AnimationEnd) {
+    let animations: Vec<Running> = registry().lock().unwrap().drain(..).collect();
+    for running in animations {
+        running.finish(end);
+    }
+}
@@ -0,0 +1,192 @@
+//! Named-device control for Automation HAT boards.
+//!
+//! This module provides `Automation`, a thin controller that maps application-level
+//! device names (such as `"pump"` or `"tank_level"`) onto board channels. Application
+//! logic can then be written against names, with the wiring kept in one place or
+//! loaded from a configuration file.
+
+use crate::channel::Channel;
+use crate::{AutomationHAT, HatError, Result};
+
+use std::collections::HashMap;
+
+/// Controls an `AutomationHAT` through named logical devices.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{Automation, AutomationHAT, Channel, HatType};
+///
+/// # fn main() -> Result<(), automation_hat::HatError> {
+/// let hat = AutomationHAT::try_new(HatType::AutomationHAT)?;
+/// let mut plant = Automation::new(
+///     hat,
+///     [("pump", Channel::Relay(1)), ("tank_level", "analog2".parse()?)],
+/// )?;
+///
+/// if plant.read("tank_level")? < 0.2 {
+///     plant.activate("pump")?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Automation {
+    /// The board being controlled
+    hat: AutomationHAT,
+    /// Device names and the channels they are wired to
+    devices: HashMap<String, Channel>,
+}
+
+impl Automation {
+    /// Creates a controller for `hat` with the given device names.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board to control
+    /// * `devices` - Pairs of device name and the channel it is wired to
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Automation)` - If every channel exists on the board
+    /// * `Err(HatError)` - If a channel number is out of range
+    pub fn new<N: Into<String>>(
+        hat: AutomationHAT,
+        devices: impl IntoIterator<Item = (N, Channel)>,
+    ) -> Result<Self> {
+        let mut map = HashMap::new();
+        for (name, channel) in devices {
+            channel.validate()?;
+            map.insert(name.into(), channel);
+        }
+        Ok(Automation { hat, devices: map })
+    }
+
+    /// Returns the channel a device is wired to, or `None` if the name is unknown.
+    pub fn channel(&self, name: &str) -> Option<Channel> {
+        self.devices.get(name).copied()
+    }
+
+    /// Switches a relay or digital output device on.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The device name
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the device was switched on
+    /// * `Err(HatError)` - If the name is unknown, the device is not switchable, or the write failed
+    pub fn activate(&mut self, name: &str) -> Result<()> {
+        self.set(name, true)
+    }
+
+    /// Switches a relay or digital output device off.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The device name
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the device was switched off
+    /// * `Err(HatError)` - If the name is unknown, the device is not switchable, or the write failed
+    pub fn deactivate(&mut self, name: &str) -> Result<()> {
+        self.set(name, false)
+    }
+
+    /// Switches a relay or digital output device on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The device name
+    /// * `on` - The desired state
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the device was switched
+    /// * `Err(HatError)` - If the name is unknown, the device is not switchable, or the write failed
+    pub fn set(&mut self, name: &str, on: bool) -> Result<()> {
+        match self.lookup(name)? {
+            Channel::Relay(index) => self.hat.relays.try_write(index, on),
+            Channel::Output(index) => self
+                .hat
+                .outputs
+                .get_mut(index)
+                .ok_or(HatError::InvalidChannel(index))?
+                .write(on),
+            channel => Err(HatError::Config(format!(
+                "Device '{}' is wired to {}, which cannot be switched",
+                name, channel
+            ))),
+        }
+    }
+
+    /// Reads the current value of a device.
+    ///
+    /// Analog inputs return their normalized reading (0.0-1.0). Digital inputs are
+    /// read and return 1.0 when high and 0.0 when low. Relays and digital outputs
+    /// return their last written state in the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The device name
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The device value
+    /// * `Err(HatError)` - If the name is unknown or the read failed
+    pub fn read(&mut self, name: &str) -> Result<f64> {
+        let level = |on: bool| if on { 1.0 } else { 0.0 };
+        match self.lookup(name)? {
+            Channel::Analog(index) => {
+                let input = self
+                    .hat
+                    .analog_inputs
+                    .get_mut(index)
+                    .ok_or(HatError::InvalidChannel(index))?;
+                input.ensure_available()?;
+                input.read()
+            }
+            Channel::Input(index) => self
+                .hat
+                .inputs
+                .get_mut(index)
+                .ok_or(HatError::InvalidChannel(index))?
+                .read()
+                .map(level),
+            Channel::Relay(index) => self
+                .hat
+                .relays
+                .get_mut(index)
+                .map(|relay| level(relay.is_energized()))
+                .ok_or(HatError::InvalidChannel(index)),
+            Channel::Output(index) => self
+                .hat
+                .outputs
+                .get_mut(index)
+                .map(|output| level(output.value))
+                .ok_or(HatError::InvalidChannel(index)),
+        }
+    }
+
+    /// Returns the underlying board.
+    pub fn hat(&self) -> &AutomationHAT {
+        &self.hat
+    }
+
+    /// Returns the underlying board for direct channel access.
+    pub fn hat_mut(&mut self) -> &mut AutomationHAT {
+        &mut self.hat
+    }
+
+    /// Consumes the controller and returns the underlying board.
+    pub fn into_inner(self) -> AutomationHAT {
+        self.hat
+    }
+
+    /// Resolves a device name to its channel.
+    fn lookup(&self, name: &str) -> Result<Channel> {
+        self.channel(name)
+            .ok_or_else(|| HatError::UnknownDevice(name.to_string()))
+    }
+}
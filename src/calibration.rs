@@ -0,0 +1,178 @@
+//! Two-point calibration of the analog inputs.
+//!
+//! This module provides `Calibration`, a gain and offset correction applied to the
+//! terminal voltage of an analog input, and `TwoPointCalibration`, returned by
+//! `AnalogInput::calibrate_two_point`, which walks through measuring two known
+//! voltages and derives the correction from them. Correcting both gain and offset
+//! takes out the tolerance of the input resistor divider as well as any zero error of
+//! the ADC. The coefficients are plain numbers so they can be saved and restored with
+//! `AnalogInput::set_calibration` on the next run.
+
+use crate::analog_input::AnalogInput;
+use crate::error::{HatError, Result};
+
+/// Number of conversions averaged for each calibration point
+const CAPTURE_SAMPLES: usize = 16;
+
+/// A linear correction of an analog input's terminal voltage.
+///
+/// The corrected voltage is `measured * gain + offset`. The default applies no
+/// correction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    /// Factor applied to the measured voltage
+    pub gain: f64,
+    /// Volts added after the gain is applied
+    pub offset: f64,
+}
+
+impl Calibration {
+    /// Applies the correction to a measured terminal voltage.
+    ///
+    /// # Arguments
+    ///
+    /// * `volts` - The voltage as measured, before correction
+    ///
+    /// # Returns
+    ///
+    /// The corrected voltage
+    pub fn apply(&self, volts: f64) -> f64 {
+        volts * self.gain + self.offset
+    }
+
+    /// Returns whether this correction leaves voltages unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            gain: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+/// A two-point calibration in progress, created by `AnalogInput::calibrate_two_point`.
+///
+/// Call `next_point` for the voltage to apply next, apply it to the input terminal,
+/// then call `capture` to record it. Once both points are captured, `finish` computes
+/// the correction and stores it on the input. Dropping the calibration before then
+/// leaves the input's existing correction untouched.
+pub struct TwoPointCalibration<'a> {
+    /// The input being calibrated
+    input: &'a mut AnalogInput,
+    /// The voltages the user applies, in the order they are requested
+    known: [f64; 2],
+    /// Uncorrected voltages measured for each captured point
+    measured: Vec<f64>,
+}
+
+impl<'a> TwoPointCalibration<'a> {
+    /// Starts a calibration of `input`, checking that the known voltages are usable.
+    pub(crate) fn new(input: &'a mut AnalogInput, low_known: f64, high_known: f64) -> Result<Self> {
+        if !low_known.is_finite() || !high_known.is_finite() {
+            return Err(HatError::Config(
+                "Calibration voltages must be finite".to_string(),
+            ));
+        }
+        if low_known >= high_known {
+            return Err(HatError::Config(
+                "The low calibration voltage must be below the high one".to_string(),
+            ));
+        }
+        Ok(TwoPointCalibration {
+            input,
+            known: [low_known, high_known],
+            measured: Vec::with_capacity(2),
+        })
+    }
+
+    /// Returns the voltage to apply before the next `capture`, or `None` once both
+    /// points have been captured.
+    pub fn next_point(&self) -> Option<f64> {
+        self.known.get(self.measured.len()).copied()
+    }
+
+    /// Measures the input with the voltage from `next_point` applied.
+    ///
+    /// Several distinct conversions are averaged to reduce noise, taken as by
+    /// `AnalogInput::read_stats`: spaced a conversion period apart in continuous mode
+    /// and bypassing any running sampler. Any existing calibration is ignored, so an
+    /// input can be recalibrated without clearing it first; a source impedance
+    /// correction (see `AnalogInput::set_source_impedance`) still applies.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The uncorrected voltage measured for this point
+    /// * `Err(HatError)` - If both points were already captured or a read failed
+    pub fn capture(&mut self) -> Result<f64> {
+        if self.next_point().is_none() {
+            return Err(HatError::Config(
+                "Both calibration points have already been captured".to_string(),
+            ));
+        }
+        let measured = self.input.read_uncalibrated_mean(CAPTURE_SAMPLES)?;
+        self.measured.push(measured);
+        Ok(measured)
+    }
+
+    /// Computes the correction from the captured points and stores it on the input.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Calibration)` - The correction now applied to the input, to be saved for
+    ///   later runs
+    /// * `Err(HatError::Config)` - If a point has not been captured, or both points
+    ///   measured the same voltage (usually because nothing was connected)
+    pub fn finish(self) -> Result<Calibration> {
+        let [low_measured, high_measured] = self.measured[..] else {
+            return Err(HatError::Config(
+                "Both calibration points must be captured first".to_string(),
+            ));
+        };
+        if high_measured <= low_measured {
+            return Err(HatError::Config(format!(
+                "Calibration readings do not increase ({:.3}V then {:.3}V); check the wiring",
+                low_measured, high_measured
+            )));
+        }
+        let [low_known, high_known] = self.known;
+        let gain = (high_known - low_known) / (high_measured - low_measured);
+        let calibration = Calibration {
+            gain,
+            offset: low_known - low_measured * gain,
+        };
+        self.input.set_calibration(calibration)?;
+        Ok(calibration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_leaves_voltages_unchanged() {
+        let calibration = Calibration::default();
+        assert!(calibration.is_identity());
+        for volts in [-1.5, 0.0, 3.3, 25.85] {
+            assert_eq!(calibration.apply(volts), volts);
+        }
+    }
+
+    #[test]
+    fn applies_gain_before_offset() {
+        let calibration = Calibration {
+            gain: 1.25,
+            offset: -0.5,
+        };
+        assert!(!calibration.is_identity());
+        assert_eq!(calibration.apply(0.0), -0.5);
+        assert_eq!(calibration.apply(2.0), 2.0);
+        assert_eq!(calibration.apply(10.0), 12.0);
+    }
+}
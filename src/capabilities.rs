@@ -0,0 +1,104 @@
+//! Runtime description of what an Automation HAT board provides.
+//!
+//! This module provides `Capabilities`, returned by `AutomationHAT::capabilities`, so
+//! configuration tools and generic front-ends can discover the channels and
+//! peripherals of the attached board instead of hard-coding per-variant knowledge.
+
+use crate::{AutomationHAT, HatType};
+
+/// The channels and peripherals available on an initialized board.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    /// The board variant
+    pub hat_type: HatType,
+    /// Number of relays fitted (see `Relays::is_present` for which ones)
+    pub relays: usize,
+    /// Number of digital inputs
+    pub inputs: usize,
+    /// Number of digital outputs
+    pub outputs: usize,
+    /// Board labels of the analog inputs that can be read; empty if analog is disabled
+    pub analog_channels: Vec<usize>,
+    /// Full-scale voltage of each entry in `analog_channels`
+    pub analog_full_scale: Vec<f64>,
+    /// Whether the display was initialized, which needs an Automation HAT Mini, the
+    /// `display` feature and `HatConfig::display` left on
+    pub display: bool,
+    /// Whether the board has per-channel indicator LEDs (full-size HAT only)
+    pub leds: bool,
+    /// Whether channel LEDs automatically follow their relay, input, output or
+    /// analog input
+    pub auto_light: bool,
+    /// Why `auto_light` is off, e.g. because the board has no indicator LEDs
+    pub auto_light_reason: Option<&'static str>,
+}
+
+impl AutomationHAT {
+    /// Describes the channels and peripherals of this board.
+    ///
+    /// Analog channels are only listed if the ADC was initialized, so a board built
+    /// with analog disabled reports none.
+    ///
+    /// # Returns
+    ///
+    /// A `Capabilities` describing the board
+    pub fn capabilities(&self) -> Capabilities {
+        let relays = (1..=3)
+            .filter(|index| self.relays.is_present(*index))
+            .count();
+        let mut analog_channels = Vec::new();
+        let mut analog_full_scale = Vec::new();
+        let analog = &self.analog_inputs;
+        for (index, input) in [&analog.one, &analog.two, &analog.three, &analog.four]
+            .into_iter()
+            .enumerate()
+        {
+            if input.is_available() {
+                analog_channels.push(index + 1);
+                analog_full_scale.push(input.max_value);
+            }
+        }
+
+        #[cfg(feature = "display")]
+        let display = self.display.is_some();
+        #[cfg(not(feature = "display"))]
+        let display = false;
+
+        let auto_light_reason = self.auto_light_reason();
+        Capabilities {
+            hat_type: self.hat_type,
+            relays,
+            inputs: 3,
+            outputs: 3,
+            analog_channels,
+            analog_full_scale,
+            display,
+            leds: self.led_driver.is_some(),
+            auto_light: auto_light_reason.is_none(),
+            auto_light_reason,
+        }
+    }
+
+    /// Explains why channel LEDs do not follow their channels on this board.
+    ///
+    /// Automatic LED indication needs the SN3218 LED driver, which only the
+    /// full-size Automation HAT carries. On the other variants the LED methods of the
+    /// channels do nothing, and this says why.
+    ///
+    /// # Returns
+    ///
+    /// * `None` - If channel LEDs follow their channels automatically
+    /// * `Some(&str)` - The reason they do not
+    pub fn auto_light_reason(&self) -> Option<&'static str> {
+        match self.hat_type {
+            HatType::AutomationHATMini => {
+                Some("Automation HAT Mini has no per-channel indicator LEDs")
+            }
+            HatType::AutomationPHAT => Some("Automation pHAT has no indicator LEDs"),
+            HatType::AutomationHAT if self.led_driver.is_none() => {
+                Some("The SN3218 LED driver was not initialized")
+            }
+            HatType::AutomationHAT => None,
+        }
+    }
+}
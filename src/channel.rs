@@ -0,0 +1,93 @@
+//! Board-label addressing for Automation HAT channels.
+//!
+//! This module provides `Channel`, which names a single relay, input, output or analog
+//! input by the label printed on the board. Channels can be parsed from strings such
+//! as `"relay1"` or `"Analog 2"`, which makes them suitable for configuration files.
+
+use crate::error::{HatError, Result};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A single channel on the Automation HAT, numbered as printed on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// A relay (1-3)
+    Relay(usize),
+    /// A digital input (1-3)
+    Input(usize),
+    /// A digital output (1-3)
+    Output(usize),
+    /// An analog input (1-4)
+    Analog(usize),
+}
+
+impl Channel {
+    /// Returns the channel number as printed on the board.
+    pub fn index(&self) -> usize {
+        match self {
+            Channel::Relay(index)
+            | Channel::Input(index)
+            | Channel::Output(index)
+            | Channel::Analog(index) => *index,
+        }
+    }
+
+    /// Checks that the channel number exists on the board.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the channel exists
+    /// * `Err(HatError::InvalidChannel)` - If the number is out of range for its kind
+    pub fn validate(&self) -> Result<()> {
+        let count = match self {
+            Channel::Analog(_) => 4,
+            _ => 3,
+        };
+        match (1..=count).contains(&self.index()) {
+            true => Ok(()),
+            false => Err(HatError::InvalidChannel(self.index())),
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Relay(index) => write!(f, "Relay {}", index),
+            Channel::Input(index) => write!(f, "Input {}", index),
+            Channel::Output(index) => write!(f, "Output {}", index),
+            Channel::Analog(index) => write!(f, "Analog {}", index),
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = HatError;
+
+    /// Parses a channel such as `"relay1"`, `"Relay 1"`, `"output_3"` or `"analog4"`.
+    ///
+    /// Matching is case-insensitive, and spaces, underscores and dashes between the
+    /// kind and number are ignored.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let unknown = || HatError::Config(format!("Unknown channel: {}", s));
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '_' | '-'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let split = name
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(unknown)?;
+        let index: usize = name[split..].parse().map_err(|_| unknown())?;
+        let channel = match &name[..split] {
+            "relay" => Channel::Relay(index),
+            "input" => Channel::Input(index),
+            "output" => Channel::Output(index),
+            "analog" => Channel::Analog(index),
+            _ => return Err(unknown()),
+        };
+        channel.validate()?;
+        Ok(channel)
+    }
+}
@@ -0,0 +1,192 @@
+//! Complementary control of two digital outputs.
+//!
+//! This module provides `ComplementaryPair`, returned by `Outputs::complementary_pair`,
+//! which drives two digital outputs as the two sides of a half-bridge or push-pull
+//! stage. The pair borrows both outputs for as long as it exists, so nothing else can
+//! switch them, and it never has both on at once: switching sides turns the active
+//! output off, waits out a dead time, and only then turns the other on.
+
+use crate::digital_output::DigitalOutput;
+use crate::{HatError, Outputs, Result};
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Two digital outputs that are never on at the same time.
+///
+/// `set(true)` turns the first output on and `set(false)` the second, with the other
+/// output off in both cases. An output is only turned on once the dead time has passed
+/// since the other was last turned off. "On" is the logical state written to the
+/// output, so each output's `Polarity` still applies. Both outputs are turned off when
+/// the pair is dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, HatType};
+/// use std::time::Duration;
+///
+/// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+/// let mut bridge = hat
+///     .outputs
+///     .complementary_pair(1, 2, Duration::from_millis(5))?;
+/// bridge.set(true)?; // output 1 on, output 2 off
+/// bridge.set(false)?; // output 1 off, 5ms pause, output 2 on
+/// bridge.off()?;
+/// # Ok::<(), automation_hat::HatError>(())
+/// ```
+pub struct ComplementaryPair<'a> {
+    /// The output turned on by `set(true)`
+    first: &'a mut DigitalOutput,
+    /// The output turned on by `set(false)`
+    second: &'a mut DigitalOutput,
+    /// Minimum time between one output turning off and the other turning on
+    dead_time: Duration,
+    /// When an output of the pair was last turned off
+    released_at: Instant,
+}
+
+impl<'a> ComplementaryPair<'a> {
+    /// Takes control of two outputs, turning both off.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The output turned on by `set(true)`
+    /// * `second` - The output turned on by `set(false)`
+    /// * `dead_time` - Minimum time between one output turning off and the other
+    ///   turning on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ComplementaryPair)` - The pair, with both outputs off
+    /// * `Err(HatError::Gpio)` - If either output could not be turned off
+    pub fn new(
+        first: &'a mut DigitalOutput,
+        second: &'a mut DigitalOutput,
+        dead_time: Duration,
+    ) -> Result<Self> {
+        let mut pair = ComplementaryPair {
+            first,
+            second,
+            dead_time,
+            released_at: Instant::now(),
+        };
+        pair.off()?;
+        Ok(pair)
+    }
+
+    /// Turns one output of the pair on and the other off.
+    ///
+    /// If the other output is on, it is turned off first; the requested output is
+    /// then turned on once the dead time has passed since the other was last turned
+    /// off, blocking until then. Setting the side that is already on does nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - `true` to turn the first output on, `false` for the second
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the requested output is on and the other off
+    /// * `Err(HatError::Gpio)` - If an output could not be switched; the requested
+    ///   output is never turned on while the other may still be on
+    pub fn set(&mut self, state: bool) -> Result<()> {
+        let (on, off) = match state {
+            true => (&mut *self.first, &mut *self.second),
+            false => (&mut *self.second, &mut *self.first),
+        };
+        if off.value {
+            off.write(false)?;
+            self.released_at = Instant::now();
+        }
+        if on.value {
+            return Ok(());
+        }
+        let ready = self.released_at + self.dead_time;
+        let now = Instant::now();
+        if ready > now {
+            thread::sleep(ready - now);
+        }
+        on.write(true)
+    }
+
+    /// Turns both outputs off.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If both outputs are off
+    /// * `Err(HatError::Gpio)` - If an output could not be turned off
+    pub fn off(&mut self) -> Result<()> {
+        let first = self.first.write(false);
+        let second = self.second.write(false);
+        self.released_at = Instant::now();
+        first.and(second)
+    }
+
+    /// Returns which output is on: `Some(true)` for the first, `Some(false)` for the
+    /// second, or `None` if both are off.
+    pub fn state(&self) -> Option<bool> {
+        match (self.first.value, self.second.value) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Returns the minimum time between one output turning off and the other turning on.
+    pub fn dead_time(&self) -> Duration {
+        self.dead_time
+    }
+}
+
+impl Drop for ComplementaryPair<'_> {
+    fn drop(&mut self) {
+        let _ = self.off();
+    }
+}
+
+impl Outputs {
+    /// Pairs two digital outputs so they are never on at the same time.
+    ///
+    /// See `ComplementaryPair` for how switching is sequenced. The outputs are
+    /// borrowed until the pair is dropped, which turns both off.
+    ///
+    /// # Arguments
+    ///
+    /// * `first` - The output number (1-3) turned on by `set(true)`
+    /// * `second` - The output number (1-3) turned on by `set(false)`
+    /// * `dead_time` - Minimum time between one output turning off and the other
+    ///   turning on
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ComplementaryPair)` - The pair, with both outputs off
+    /// * `Err(HatError::InvalidChannel)` - If either output does not exist
+    /// * `Err(HatError::Config)` - If both numbers name the same output
+    /// * `Err(HatError::Gpio)` - If either output could not be turned off
+    pub fn complementary_pair(
+        &mut self,
+        first: usize,
+        second: usize,
+        dead_time: Duration,
+    ) -> Result<ComplementaryPair<'_>> {
+        if first == second {
+            return Err(HatError::Config(format!(
+                "A complementary pair needs two different outputs, not output {} twice",
+                first
+            )));
+        }
+        let Outputs { one, two, three } = self;
+        let mut outputs = [Some(one), Some(two), Some(three)];
+        let mut take = |index: usize| {
+            index
+                .checked_sub(1)
+                .and_then(|slot| outputs.get_mut(slot))
+                .and_then(Option::take)
+                .ok_or(HatError::InvalidChannel(index))
+        };
+        let first = take(first)?;
+        let second = take(second)?;
+        ComplementaryPair::new(first, second, dead_time)
+    }
+}
@@ -0,0 +1,526 @@
+//! Construction options for Automation HAT boards.
+//!
+//! This module provides the `HatConfig` builder, which controls how
+//! [`AutomationHAT::with_config`](crate::AutomationHAT::with_config) brings up the board.
+//! Subsystems that are disabled are never opened, so an absent or faulty peripheral
+//! does not prevent the rest of the board from being used.
+
+use crate::HatType;
+use crate::analog_input::AdcVariant;
+use crate::channel::Channel;
+use crate::error::{HatError, Result};
+
+/// I2C bus of the ADC and LED driver on the stock boards
+const DEFAULT_I2C_PATH: &str = "/dev/i2c-1";
+
+/// Builder-style configuration for initializing an `AutomationHAT`.
+///
+/// All subsystems are enabled by default, matching the behaviour of
+/// [`AutomationHAT::new`](crate::AutomationHAT::new).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, HatConfig, HatType};
+///
+/// // Only drive relays and digital I/O; leave the ADC untouched
+/// let config = HatConfig::new().enable_analog(false);
+/// let mut hat = AutomationHAT::with_config(HatType::AutomationHAT, config);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HatConfig {
+    /// Whether the ADS1015 analog-to-digital converter is initialized
+    pub analog: bool,
+    /// Whether a failure to initialize the ADC fails construction of the whole board
+    pub analog_strict: bool,
+    /// Which analog-to-digital converter is fitted
+    pub adc_variant: AdcVariant,
+    /// Whether the ST7735 display is initialized (Automation HAT Mini only)
+    pub display: bool,
+    /// Full-scale voltage of each analog input, indexed by ADC channel (0-3)
+    pub analog_max_values: [f64; 4],
+    /// GPIO line assignments for the relays, inputs and outputs
+    pub pins: PinMap,
+    /// Path of the GPIO character device, or `None` to detect it
+    pub gpio_chip: Option<String>,
+    /// Device node of the I2C bus carrying the ADC
+    pub adc_i2c_path: String,
+    /// Device node of the I2C bus carrying the SN3218 LED driver
+    pub led_i2c_path: String,
+    /// Whether every relay and digital output is switched off when the board is dropped
+    pub all_off_on_drop: bool,
+}
+
+impl HatConfig {
+    /// Creates a new configuration with every subsystem enabled.
+    ///
+    /// # Returns
+    ///
+    /// A `HatConfig` equivalent to `HatConfig::default()`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables initialization of the analog inputs.
+    ///
+    /// When disabled, the ADC is never opened and every analog input returns
+    /// an error when read.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the analog inputs should be initialized
+    pub fn enable_analog(mut self, enabled: bool) -> Self {
+        self.analog = enabled;
+        self
+    }
+
+    /// Chooses whether a failure to initialize the ADC fails construction.
+    ///
+    /// By default the board only constructs if the ADC can be opened, set to its
+    /// input range and switched to continuous conversion. With `strict` set to
+    /// `false`, a failure instead leaves the analog inputs unavailable, as if analog
+    /// were disabled, and the rest of the board comes up normally. This suits
+    /// applications that only use relays and digital I/O and should keep working with
+    /// a missing or faulty ADC. The failure is kept in
+    /// [`AutomationHAT::analog_init_error`](crate::AutomationHAT::analog_init_error).
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether an ADC failure should fail construction
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatConfig, HatType};
+    ///
+    /// let config = HatConfig::new().analog_strict(false);
+    /// let mut hat = AutomationHAT::try_with_config(HatType::AutomationHAT, config)?;
+    /// if let Some(error) = hat.analog_init_error() {
+    ///     eprintln!("Continuing without analog inputs: {}", error);
+    /// }
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn analog_strict(mut self, strict: bool) -> Self {
+        self.analog_strict = strict;
+        self
+    }
+
+    /// Selects the analog-to-digital converter fitted to the board.
+    ///
+    /// Boards ship with the 12-bit ADS1015. Select `AdcVariant::Ads1115` for a board
+    /// carrying the pin-compatible 16-bit ADS1115, which resolves 16 times finer
+    /// voltage steps but converts more slowly; see `AdcVariant` for the figures.
+    /// Selecting the wrong chip reads every voltage 16 times too high or too low.
+    ///
+    /// # Arguments
+    ///
+    /// * `variant` - The converter on the board
+    pub fn adc_variant(mut self, variant: AdcVariant) -> Self {
+        self.adc_variant = variant;
+        self
+    }
+
+    /// Sets the full-scale voltage used to normalize one analog input.
+    ///
+    /// The three buffered inputs default to 25.85V and the fourth channel to 3.3V.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The analog input number as printed on the board (1-4)
+    /// * `max_value` - The voltage that normalizes to a reading of 1.0
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HatConfig)` - The updated configuration
+    /// * `Err(HatError::InvalidChannel)` - If `index` is outside 1-4
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use automation_hat::HatConfig;
+    ///
+    /// let config = HatConfig::new().analog_max_value(1, 10.0)?;
+    /// assert!(HatConfig::new().analog_max_value(5, 10.0).is_err());
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn analog_max_value(mut self, index: usize, max_value: f64) -> Result<Self> {
+        let slot = self
+            .analog_max_values
+            .get_mut(index.wrapping_sub(1))
+            .ok_or(HatError::InvalidChannel(index))?;
+        *slot = max_value;
+        Ok(self)
+    }
+
+    /// Overrides the GPIO line assignments.
+    ///
+    /// Use this for boards with non-standard wiring, compatible third-party boards,
+    /// or future pinout revisions. The map is validated when the board is constructed,
+    /// including against the display's control lines on the Automation HAT Mini.
+    ///
+    /// # Arguments
+    ///
+    /// * `pins` - The GPIO line for each relay, input and output
+    pub fn pin_map(mut self, pins: PinMap) -> Self {
+        self.pins = pins;
+        self
+    }
+
+    /// Sets the GPIO character device to open instead of detecting it.
+    ///
+    /// By default the header GPIO controller is detected: `/dev/gpiochip0` is used if
+    /// its label identifies it as the BCM2835/BCM2711 or Raspberry Pi 5 RP1 pin
+    /// controller, otherwise every `/dev/gpiochip*` is probed for one of those labels,
+    /// and `/dev/gpiochip0` is used as a last resort. This covers Raspberry Pi 5
+    /// kernels that expose the RP1 as `/dev/gpiochip4`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The device path, e.g. `/dev/gpiochip4`
+    pub fn gpio_chip(mut self, path: impl Into<String>) -> Self {
+        self.gpio_chip = Some(path.into());
+        self
+    }
+
+    /// Sets the I2C bus the ADC is opened on, instead of `/dev/i2c-1`.
+    ///
+    /// The stock boards carry the ADC and the SN3218 LED driver on the Raspberry Pi's
+    /// `/dev/i2c-1`. Custom hardware can put them on different buses; set each path
+    /// to match. Both default to `/dev/i2c-1`, and each device is opened through its
+    /// own handle even when they share a bus, which the kernel supports.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The I2C device node, e.g. `/dev/i2c-3`
+    pub fn adc_i2c_path(mut self, path: impl Into<String>) -> Self {
+        self.adc_i2c_path = path.into();
+        self
+    }
+
+    /// Sets the I2C bus the SN3218 LED driver is opened on, instead of `/dev/i2c-1`.
+    ///
+    /// See `adc_i2c_path` for when this is needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The I2C device node, e.g. `/dev/i2c-3`
+    pub fn led_i2c_path(mut self, path: impl Into<String>) -> Self {
+        self.led_i2c_path = path.into();
+        self
+    }
+
+    /// Enables or disables initialization of the display.
+    ///
+    /// When disabled, the SPI display is never opened and the `display` field
+    /// of the `AutomationHAT` is `None`. Has no effect on boards without a display
+    /// or when the crate is built without the `display` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether the display should be initialized
+    pub fn enable_display(mut self, enabled: bool) -> Self {
+        self.display = enabled;
+        self
+    }
+
+    /// Switches every relay and digital output off when the board is dropped.
+    ///
+    /// Off by default: releasing a GPIO line does not reset it, and one-shot tools
+    /// such as those in [`ops`](crate::ops) rely on relays and outputs keeping their
+    /// level after the board is dropped. Services that must leave their loads safe
+    /// when they return or panic should enable it, together with
+    /// `AutomationHAT::install_signal_safe_shutdown` for SIGTERM and SIGINT, which end
+    /// the process without dropping the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether dropping the board switches its relays and outputs off
+    pub fn all_off_on_drop(mut self, enabled: bool) -> Self {
+        self.all_off_on_drop = enabled;
+        self
+    }
+}
+
+impl Default for HatConfig {
+    fn default() -> Self {
+        HatConfig {
+            analog: true,
+            analog_strict: true,
+            adc_variant: AdcVariant::default(),
+            display: true,
+            analog_max_values: [
+                crate::ANALOG_MAX_VALUE,
+                crate::ANALOG_MAX_VALUE,
+                crate::ANALOG_MAX_VALUE,
+                crate::ANALOG_4_MAX_VALUE,
+            ],
+            pins: PinMap::default(),
+            gpio_chip: None,
+            adc_i2c_path: DEFAULT_I2C_PATH.to_string(),
+            led_i2c_path: DEFAULT_I2C_PATH.to_string(),
+            all_off_on_drop: false,
+        }
+    }
+}
+
+/// GPIO line (BCM) assignments for every relay, digital input and digital output.
+///
+/// The default matches the Pimoroni Automation HAT family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinMap {
+    /// Line driving Relay 1
+    pub relay_1: u32,
+    /// Line driving Relay 2
+    pub relay_2: u32,
+    /// Line driving Relay 3
+    pub relay_3: u32,
+    /// Line reading Digital Input 1
+    pub input_1: u32,
+    /// Line reading Digital Input 2
+    pub input_2: u32,
+    /// Line reading Digital Input 3
+    pub input_3: u32,
+    /// Line driving Digital Output 1
+    pub output_1: u32,
+    /// Line driving Digital Output 2
+    pub output_2: u32,
+    /// Line driving Digital Output 3
+    pub output_3: u32,
+}
+
+impl PinMap {
+    /// Returns every role in the map paired with its GPIO line.
+    pub fn roles(&self) -> [(&'static str, u32); 9] {
+        [
+            ("Relay 1", self.relay_1),
+            ("Relay 2", self.relay_2),
+            ("Relay 3", self.relay_3),
+            ("Input 1", self.input_1),
+            ("Input 2", self.input_2),
+            ("Input 3", self.input_3),
+            ("Output 1", self.output_1),
+            ("Output 2", self.output_2),
+            ("Output 3", self.output_3),
+        ]
+    }
+
+    /// Returns the GPIO (BCM) line assigned to a relay, input or output.
+    ///
+    /// Useful when debugging with `gpioinfo` or `raspi-gpio`, or writing a
+    /// device-tree overlay for the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The relay, digital input or digital output
+    ///
+    /// # Returns
+    ///
+    /// The line number, or `None` for analog inputs (which are read through the
+    /// ADS1015 over I2C) and out-of-range channel numbers
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use automation_hat::{Channel, PinMap};
+    ///
+    /// let pins = PinMap::default();
+    /// assert_eq!(pins.line(Channel::Relay(1)), Some(13));
+    /// assert_eq!(pins.channel(13), Some(Channel::Relay(1)));
+    /// ```
+    pub fn line(&self, channel: Channel) -> Option<u32> {
+        let lines = match channel {
+            Channel::Relay(_) => [self.relay_1, self.relay_2, self.relay_3],
+            Channel::Input(_) => [self.input_1, self.input_2, self.input_3],
+            Channel::Output(_) => [self.output_1, self.output_2, self.output_3],
+            Channel::Analog(_) => return None,
+        };
+        lines.get(channel.index().wrapping_sub(1)).copied()
+    }
+
+    /// Returns the relay, input or output assigned to a GPIO (BCM) line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The GPIO line number
+    ///
+    /// # Returns
+    ///
+    /// The channel using the line, or `None` if no role is assigned to it
+    pub fn channel(&self, line: u32) -> Option<Channel> {
+        (1..=3)
+            .flat_map(|index| {
+                [
+                    Channel::Relay(index),
+                    Channel::Input(index),
+                    Channel::Output(index),
+                ]
+            })
+            .find(|channel| self.line(*channel) == Some(line))
+    }
+
+    /// Checks that no two roles are assigned the same GPIO line.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every role has its own line
+    /// * `Err(HatError::PinConflict)` - Naming the first shared line and every role
+    ///   assigned to it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use automation_hat::{HatError, PinMap};
+    ///
+    /// let pins = PinMap { input_1: 13, ..PinMap::default() };
+    /// assert_eq!(
+    ///     pins.validate(),
+    ///     Err(HatError::PinConflict {
+    ///         line: 13,
+    ///         roles: vec!["Relay 1".to_string(), "Input 1".to_string()],
+    ///     })
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<()> {
+        check_lines(&self.roles())
+    }
+
+    /// Checks that no two roles share a GPIO line, nor use a line the board variant
+    /// claims for itself, such as the display control lines of the HAT Mini.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat_type` - The board variant the map is used with
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every role, and the variant's own lines, have a line each
+    /// * `Err(HatError::PinConflict)` - Naming the first shared line and every role
+    ///   assigned to it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use automation_hat::{HatType, PinMap};
+    ///
+    /// // GPIO 22 resets the HAT Mini's display
+    /// let pins = PinMap { relay_1: 22, ..PinMap::default() };
+    /// assert!(pins.validate().is_ok());
+    /// # #[cfg(feature = "display")]
+    /// assert!(pins.validate_for(HatType::AutomationHATMini).is_err());
+    /// ```
+    pub fn validate_for(&self, hat_type: HatType) -> Result<()> {
+        let board_lines: &[(&str, u32)] = match hat_type {
+            #[cfg(feature = "display")]
+            HatType::AutomationHATMini => &crate::display::CONTROL_LINES,
+            _ => &[],
+        };
+        check_lines(&[self.roles().as_slice(), board_lines].concat())
+    }
+}
+
+/// Checks that no GPIO line is claimed for more than one role.
+///
+/// The kernel refuses a second request for a line that is already held with a bare
+/// `EBUSY`, after earlier lines have been requested; checking every line the board
+/// will request up front fails before any of them is taken, and names the roles.
+///
+/// # Arguments
+///
+/// * `roles` - Every line the board will request, paired with its role
+///
+/// # Returns
+///
+/// * `Ok(())` - If every line is claimed once
+/// * `Err(HatError::PinConflict)` - For the first line claimed more than once
+fn check_lines(roles: &[(&'static str, u32)]) -> Result<()> {
+    for (_, line) in roles {
+        let claims: Vec<String> = roles
+            .iter()
+            .filter(|(_, other)| other == line)
+            .map(|(role, _)| role.to_string())
+            .collect();
+        if claims.len() > 1 {
+            return Err(HatError::PinConflict {
+                line: *line,
+                roles: claims,
+            });
+        }
+    }
+    Ok(())
+}
+
+impl Default for PinMap {
+    fn default() -> Self {
+        PinMap {
+            relay_1: crate::RELAY_1,
+            relay_2: crate::RELAY_2,
+            relay_3: crate::RELAY_3,
+            input_1: crate::INPUT_1,
+            input_2: crate::INPUT_2,
+            input_3: crate::INPUT_3,
+            output_1: crate::OUTPUT_1,
+            output_2: crate::OUTPUT_2,
+            output_3: crate::OUTPUT_3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_map_has_no_conflicts() {
+        assert_eq!(check_lines(&PinMap::default().roles()), Ok(()));
+    }
+
+    #[test]
+    fn shared_line_names_every_role() {
+        let pins = PinMap {
+            input_1: 13,
+            output_2: 13,
+            ..PinMap::default()
+        };
+        assert_eq!(
+            check_lines(&pins.roles()),
+            Err(HatError::PinConflict {
+                line: 13,
+                roles: vec![
+                    "Relay 1".to_string(),
+                    "Input 1".to_string(),
+                    "Output 2".to_string(),
+                ],
+            })
+        );
+    }
+
+    #[cfg(feature = "display")]
+    #[test]
+    fn role_on_mini_display_line_conflicts() {
+        let pins = PinMap {
+            relay_2: 22,
+            ..PinMap::default()
+        };
+        assert_eq!(pins.validate(), Ok(()));
+        assert_eq!(
+            pins.validate_for(HatType::AutomationHATMini),
+            Err(HatError::PinConflict {
+                line: 22,
+                roles: vec!["Relay 2".to_string(), "Display RST".to_string()],
+            })
+        );
+
+        let pins = PinMap {
+            output_1: 9,
+            ..PinMap::default()
+        };
+        assert_eq!(
+            pins.validate_for(HatType::AutomationHATMini),
+            Err(HatError::PinConflict {
+                line: 9,
+                roles: vec!["Output 1".to_string(), "Display DC".to_string()],
+            })
+        );
+        assert_eq!(pins.validate_for(HatType::AutomationHAT), Ok(()));
+    }
+}
@@ -0,0 +1,304 @@
+//! A live status panel on the Automation HAT Mini display.
+//!
+//! This module provides `DisplayDashboard`, which reads the whole board with
+//! `AutomationHAT::read_all` on a background thread and draws it on the display as
+//! colored tiles and bars: one row of tiles each for the relays, digital inputs and
+//! digital outputs, and a bar per analog input filled to its share of full scale. The
+//! status bar at the top shows whether the last read succeeded. It needs no drawing
+//! code from the application, which makes it a ready-made demo and a field
+//! diagnostic screen.
+
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, Status};
+use crate::layout::ScreenLayout;
+use crate::readings::HatReadings;
+use crate::worker::{StopFlag, Worker, lock};
+use crate::{AutomationHAT, HatError, Result};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Screen background, and the color of channels not fitted to the board
+const BACKGROUND: u16 = 0x0000;
+/// A tile or bar track that is off or empty
+const IDLE: u16 = 0x2104;
+/// An energized relay
+const RELAY_ON: u16 = 0x07E0;
+/// A high digital input
+const INPUT_ON: u16 = 0x07FF;
+/// An active digital output
+const OUTPUT_ON: u16 = 0xFDE0;
+/// The filled part of an analog bar
+const BAR_FILL: u16 = 0xFFFF;
+
+/// Width of a tile, three to a row with 2-pixel margins and gaps
+const TILE_WIDTH: u16 = 24;
+/// Width of an analog bar, the full screen less the margins
+const BAR_WIDTH: u16 = 76;
+
+/// What the dashboard shows, compared between refreshes to skip redundant redraws.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frame {
+    /// Whether the last read succeeded
+    healthy: bool,
+    /// Color of the relay, input and output tiles, in that order
+    tiles: [u16; 9],
+    /// Filled width of each analog bar, `None` for inputs without an ADC
+    bars: [Option<u16>; 4],
+}
+
+impl Frame {
+    /// Builds the frame showing `readings`.
+    fn new(readings: &HatReadings) -> Self {
+        let tile = |on: bool, color: u16| if on { color } else { IDLE };
+        let mut tiles = [BACKGROUND; 9];
+        for index in 0..3 {
+            tiles[index] = readings.relays[index].map_or(BACKGROUND, |on| tile(on, RELAY_ON));
+            tiles[3 + index] = tile(readings.inputs[index], INPUT_ON);
+            tiles[6 + index] = tile(readings.outputs[index], OUTPUT_ON);
+        }
+        let bars = std::array::from_fn(|channel| {
+            readings.analog.channels[channel]
+                .map(|reading| (reading.value.clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as u16)
+        });
+        Frame {
+            healthy: true,
+            tiles,
+            bars,
+        }
+    }
+}
+
+/// A running status panel on the display, stopped with `stop` or when dropped.
+///
+/// While it runs, the dashboard owns the screen: anything else drawn is overwritten
+/// on the next change. Stopping leaves the last frame on the display.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, DisplayDashboard, HatType};
+/// use std::sync::{Arc, Mutex};
+/// use std::time::Duration;
+///
+/// let hat = Arc::new(Mutex::new(AutomationHAT::new(HatType::AutomationHATMini)));
+/// let dashboard = DisplayDashboard::start(hat.clone(), Duration::from_millis(200))?;
+/// // ... the application keeps using `hat` ...
+/// dashboard.stop();
+/// # Ok::<(), automation_hat::HatError>(())
+/// ```
+pub struct DisplayDashboard {
+    /// Time between refreshes in microseconds, shared with the drawing thread
+    refresh: Arc<AtomicU64>,
+    /// Number of refreshes whose read or drawing failed
+    errors: Arc<AtomicU64>,
+    /// The most recent read or drawing error
+    last_error: Arc<Mutex<Option<HatError>>>,
+    /// The drawing thread
+    worker: Worker,
+}
+
+impl DisplayDashboard {
+    /// Starts drawing the state of `hat` on its display every `refresh`.
+    ///
+    /// Each refresh locks the board, reads it with `read_all`, and redraws the panel
+    /// if anything shown changed. Relays not fitted to the board and analog inputs
+    /// without an ADC are drawn in the background color. If a read fails, the status
+    /// bar turns red (`Status::Fault`) until a read succeeds; read and drawing
+    /// failures are counted rather than stopping the dashboard.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board to show, shared with the rest of the application
+    /// * `refresh` - Time between the starts of consecutive refreshes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DisplayDashboard)` - A handle to the running dashboard
+    /// * `Err(HatError::Config)` - If `refresh` is shorter than a microsecond
+    /// * `Err(HatError::NoDisplay)` - If the board has no initialized display
+    /// * `Err(HatError::Io)` - If the drawing thread could not be started
+    pub fn start(hat: Arc<Mutex<AutomationHAT>>, refresh: Duration) -> Result<Self> {
+        let refresh = micros(refresh);
+        if refresh == 0 {
+            return Err(HatError::Config(
+                "Dashboard refresh must be at least one microsecond".to_string(),
+            ));
+        }
+        lock(&hat).display_mut()?;
+
+        let refresh = Arc::new(AtomicU64::new(refresh));
+        let errors = Arc::new(AtomicU64::new(0));
+        let last_error = Arc::new(Mutex::new(None));
+
+        let worker = {
+            let (refresh, errors, last_error) =
+                (refresh.clone(), errors.clone(), last_error.clone());
+            Worker::spawn("automation-hat-dashboard", move |stop| {
+                draw_loop(&hat, &stop, &refresh, &errors, &last_error)
+            })?
+        };
+
+        Ok(DisplayDashboard {
+            refresh,
+            errors,
+            last_error,
+            worker,
+        })
+    }
+
+    /// Changes the time between refreshes, from the next refresh on.
+    ///
+    /// A `refresh` shorter than a microsecond is ignored, as `start` would reject it,
+    /// and the dashboard keeps its current refresh.
+    pub fn set_refresh(&self, refresh: Duration) {
+        let refresh = micros(refresh);
+        if refresh == 0 {
+            return;
+        }
+        self.refresh.store(refresh, Ordering::Relaxed);
+        self.worker.unpark();
+    }
+
+    /// Returns the time between refreshes.
+    pub fn refresh(&self) -> Duration {
+        Duration::from_micros(self.refresh.load(Ordering::Relaxed))
+    }
+
+    /// Returns how many refreshes failed to read the board or draw the panel.
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most recent read or drawing error, if any refresh failed.
+    pub fn last_error(&self) -> Option<HatError> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Stops the dashboard and waits for the drawing thread to exit.
+    pub fn stop(mut self) {
+        self.worker.join();
+    }
+}
+
+/// Converts a refresh interval to the microseconds stored in the handle.
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros().try_into().unwrap_or(u64::MAX)
+}
+
+/// Reads the board and redraws the panel every refresh until stopped.
+fn draw_loop(
+    hat: &Mutex<AutomationHAT>,
+    stop: &StopFlag,
+    refresh: &AtomicU64,
+    errors: &AtomicU64,
+    last_error: &Mutex<Option<HatError>>,
+) {
+    let layout = panel_layout();
+    let mut shown: Option<Frame> = None;
+    while !stop.is_set() {
+        let started = Instant::now();
+        {
+            let mut hat = lock(hat);
+            // A failed read keeps the last state on screen, flagged as a fault
+            let (frame, read_error) = match hat.read_all() {
+                Ok(readings) => (Some(Frame::new(&readings)), None),
+                Err(error) => (
+                    shown.map(|frame| Frame {
+                        healthy: false,
+                        ..frame
+                    }),
+                    Some(error),
+                ),
+            };
+            let drawn = match frame {
+                Some(frame) => draw(&mut hat, &layout, frame, shown),
+                None => hat.show_status(Status::Fault),
+            };
+            // After a failed draw the screen is unknown, so the next frame is drawn in full
+            shown = if drawn.is_ok() { frame } else { None };
+            if let Some(error) = read_error.or(drawn.err()) {
+                errors.fetch_add(1, Ordering::Relaxed);
+                *last_error
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(error);
+            }
+        }
+
+        // The interval is re-read on every wake, so `set_refresh` applies at once
+        while !stop.is_set() {
+            let deadline = started + Duration::from_micros(refresh.load(Ordering::Relaxed));
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+/// Returns the layout of the panel: tile rows for the relays, inputs and outputs
+/// below the status bar, then one bar per analog input.
+fn panel_layout() -> ScreenLayout {
+    let mut layout = ScreenLayout::new();
+    for (row, y) in ["relay", "input", "output"].into_iter().zip([16, 36, 56]) {
+        for (column, x) in [2, 28, 54].into_iter().enumerate() {
+            layout = layout.region(format!("{}{}", row, column), x, y, TILE_WIDTH, 16);
+        }
+    }
+    for (bar, y) in [80, 98, 116, 134].into_iter().enumerate() {
+        layout = layout.region(format!("analog{}", bar), 2, y, BAR_WIDTH, 14);
+    }
+    layout
+}
+
+/// Draws the parts of `frame` that differ from `shown`, or all of it the first time.
+fn draw(
+    hat: &mut AutomationHAT,
+    layout: &ScreenLayout,
+    frame: Frame,
+    shown: Option<Frame>,
+) -> Result<()> {
+    if shown.is_none() {
+        let pixels = DISPLAY_WIDTH as usize * DISPLAY_HEIGHT as usize;
+        hat.display_mut()?
+            .set_pixels(
+                0,
+                0,
+                DISPLAY_WIDTH - 1,
+                DISPLAY_HEIGHT - 1,
+                std::iter::repeat_n(BACKGROUND, pixels),
+            )
+            .map_err(|error| HatError::Display(format!("Failed to clear display: {:?}", error)))?;
+    }
+    if shown.is_none_or(|shown| shown.healthy != frame.healthy) {
+        hat.show_status(match frame.healthy {
+            true => Status::Ok,
+            false => Status::Fault,
+        })?;
+    }
+
+    let display = hat.display_mut()?;
+    let names = ["relay", "input", "output"];
+    for (index, color) in frame.tiles.iter().enumerate() {
+        if shown.is_none_or(|shown| shown.tiles[index] != *color) {
+            let name = format!("{}{}", names[index / 3], index % 3);
+            layout.fill(display, &name, *color)?;
+        }
+    }
+    for (index, bar) in frame.bars.iter().enumerate() {
+        if shown.is_some_and(|shown| shown.bars[index] == *bar) {
+            continue;
+        }
+        let name = format!("analog{}", index);
+        match bar {
+            Some(filled) => {
+                let row = (0..BAR_WIDTH).map(|x| if x < *filled { BAR_FILL } else { IDLE });
+                layout.draw(display, &name, row.cycle())?;
+            }
+            None => layout.fill(display, &name, BACKGROUND)?,
+        }
+    }
+    Ok(())
+}
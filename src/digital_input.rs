@@ -2,27 +2,169 @@
 //!
 //! This module provides control for the digital input pins on Automation HAT boards.
 //! Digital inputs can read 5V signals and have indicator LEDs to show their current state.
+//!
+//! Inputs connected to mechanical contacts can be debounced with
+//! [`DigitalInput::set_debounce`].
+//!
+//! With the `async` feature, an input can also be consumed as a stream of edge events
+//! (see [`DigitalInput::event_stream`]).
 
+use crate::error::{HatError, Result};
 use crate::lights::LED;
+use crate::worker::{StopFlag, Worker};
 
 use embedded_hal::digital::InputPin;
 use linux_embedded_hal::{
     CdevPin,
-    gpio_cdev::{Line, LineRequestFlags},
+    gpio_cdev::{EventRequestFlags, EventType, Line, LineEventHandle, LineRequestFlags},
 };
 
+#[cfg(feature = "async")]
+use futures_core::Stream;
+#[cfg(feature = "async")]
+use linux_embedded_hal::gpio_cdev::AsyncLineEventHandle;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// How often a `PulseCounter` samples its input
+static COUNTER_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A transition of a digital input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    /// The input went from low to high
+    Rising,
+    /// The input went from high to low
+    Falling,
+}
+
+/// An edge captured by the kernel, with the time it was detected.
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeEvent {
+    /// The direction of the transition
+    pub edge: Edge,
+    /// Kernel timestamp of the edge, taken in the GPIO interrupt handler.
+    ///
+    /// This is measured from the kernel's `CLOCK_MONOTONIC` epoch on Linux 5.7 and
+    /// later (`CLOCK_REALTIME` on older kernels), so it is only comparable with other
+    /// kernel event timestamps, not with `Instant`.
+    pub timestamp: Duration,
+}
+
+/// The changes seen across all digital inputs between two polls.
+///
+/// Each mask holds one bit per input, with bit 0 for Input 1, bit 1 for Input 2 and
+/// bit 2 for Input 3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InputDelta {
+    /// Inputs that went from low to high since the previous poll
+    pub rising: u8,
+    /// Inputs that went from high to low since the previous poll
+    pub falling: u8,
+    /// Inputs that are currently high
+    pub current: u8,
+}
+
+impl InputDelta {
+    /// Returns whether any input changed since the previous poll.
+    pub fn changed(&self) -> bool {
+        self.rising != 0 || self.falling != 0
+    }
+
+    /// Returns whether the input with the given board label went high.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The input number as printed on the board (1-3)
+    pub fn rose(&self, index: usize) -> bool {
+        Self::bit(self.rising, index)
+    }
+
+    /// Returns whether the input with the given board label went low.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The input number as printed on the board (1-3)
+    pub fn fell(&self, index: usize) -> bool {
+        Self::bit(self.falling, index)
+    }
+
+    /// Returns whether the input with the given board label is currently high.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The input number as printed on the board (1-3)
+    pub fn is_high(&self, index: usize) -> bool {
+        Self::bit(self.current, index)
+    }
+
+    fn bit(mask: u8, index: usize) -> bool {
+        (1..=3).contains(&index) && mask & (1 << (index - 1)) != 0
+    }
+}
+
+/// Software debouncing of a sampled input level.
+#[derive(Debug, Clone, Copy)]
+struct Debouncer {
+    /// How long a new level must hold before it is reported; zero disables debouncing
+    period: Duration,
+    /// The last level reported while debouncing
+    stable: Option<bool>,
+    /// When the line was first seen at a level other than `stable`
+    pending_since: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(period: Duration) -> Self {
+        Debouncer {
+            period,
+            stable: None,
+            pending_since: None,
+        }
+    }
+
+    /// Filters a raw sample taken at `now` through the debounce period.
+    fn filter(&mut self, raw: bool, now: Instant) -> bool {
+        if self.period.is_zero() {
+            return raw;
+        }
+        let stable = *self.stable.get_or_insert(raw);
+        if raw == stable {
+            self.pending_since = None;
+            return stable;
+        }
+        let since = *self.pending_since.get_or_insert(now);
+        if now.duration_since(since) >= self.period {
+            self.stable = Some(raw);
+            self.pending_since = None;
+            return raw;
+        }
+        stable
+    }
+}
+
 /// Controls a digital input on the Automation HAT.
 ///
 /// Digital inputs can read 5V signals from external devices. When a 5V signal
 /// is detected, the input reads as high (true). Each input can have an associated
 /// LED that automatically indicates the input state.
 pub struct DigitalInput {
-    /// GPIO pin for the digital input
-    pin: CdevPin,
+    /// GPIO pin for the digital input, released while an event stream owns the line
+    pin: Option<CdevPin>,
+    /// GPIO line offset of the input pin
+    offset: u32,
     /// Optional LED indicator for this input
     led: Option<LED>,
     /// Whether the LED should automatically reflect input state
     _auto_light: bool,
+    /// Filters samples through the debounce period
+    debouncer: Debouncer,
 }
 
 impl DigitalInput {
@@ -37,14 +179,17 @@ impl DigitalInput {
     ///
     /// A new `DigitalInput` instance with automatic LED indication enabled
     pub fn new(line: Line, led: Option<LED>) -> Self {
+        let offset = line.offset();
         let line = line
             .request(LineRequestFlags::INPUT, 0, "AutomationHAT Rust SDK")
             .unwrap();
         let pin = CdevPin::new(line).unwrap();
         DigitalInput {
-            pin,
+            pin: Some(pin),
+            offset,
             led,
             _auto_light: true,
+            debouncer: Debouncer::new(Duration::ZERO),
         }
     }
 
@@ -60,37 +205,586 @@ impl DigitalInput {
     ///
     /// A new `DigitalInput` instance with the specified LED behavior
     pub fn new_with_auto_light(line: Line, led: Option<LED>, auto_light: bool) -> Self {
+        Self::try_new_with_auto_light(line, led, auto_light).unwrap()
+    }
+
+    /// Creates a new digital input with configurable LED indication, returning an error
+    /// instead of panicking if the GPIO line cannot be requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - GPIO line connected to the digital input
+    /// * `led` - Optional LED indicator for this input
+    /// * `auto_light` - Whether the LED should automatically reflect the input state
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DigitalInput)` - If the line was requested successfully
+    /// * `Err(HatError)` - If the line is unavailable or already in use
+    pub fn try_new_with_auto_light(line: Line, led: Option<LED>, auto_light: bool) -> Result<Self> {
+        let offset = line.offset();
         let line = line
             .request(LineRequestFlags::INPUT, 0, "AutomationHAT Rust SDK")
-            .unwrap();
-        let pin = CdevPin::new(line).unwrap();
-        DigitalInput {
-            pin,
+            .map_err(|error| {
+                HatError::Gpio(format!("Failed to request line {}: {}", offset, error))
+            })?;
+        let pin = CdevPin::new(line).map_err(|error| HatError::Gpio(error.to_string()))?;
+        Ok(DigitalInput {
+            pin: Some(pin),
+            offset,
             led,
             _auto_light: auto_light,
-        }
+            debouncer: Debouncer::new(Duration::ZERO),
+        })
     }
 
     /// Reads the current state of the digital input.
     ///
     /// When auto_light is enabled and an LED is attached, this method will
-    /// also update the LED to reflect the current input state.
+    /// also update the LED to reflect the current input state. If a debounce period
+    /// is set, the debounced state is returned (see `set_debounce`).
     ///
     /// # Returns
     ///
     /// * `Ok(true)` - If the input is high (5V signal detected)
     /// * `Ok(false)` - If the input is low (no signal)
-    /// * `Err(String)` - If reading the input failed
-    pub fn read(&mut self) -> Result<bool, String> {
-        let value = self.pin.is_high().map_err(|e| e.to_string())?;
-        if self._auto_light && self.led.is_some() {
-            if let Err(e) = self.led.as_mut().unwrap().set_brightness(match value {
+    /// * `Err(HatError::Gpio)` - If reading the input failed
+    pub fn read(&mut self) -> Result<bool> {
+        let value = self.sample()?;
+        let value = self.debouncer.filter(value, Instant::now());
+        self.update_led(value);
+        Ok(value)
+    }
+
+    /// Reads the current state of the digital input without updating its LED.
+    ///
+    /// Identical to `read`, including debouncing, except that the LED is left alone
+    /// whatever the auto-light setting. For high-rate polling loops that do not need
+    /// the indication, this avoids an SN3218 write on every change of state. The LED
+    /// catches up on the next `read`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - The input state
+    /// * `Err(HatError::Gpio)` - If reading the input failed
+    pub fn read_quiet(&mut self) -> Result<bool> {
+        let value = self.sample()?;
+        Ok(self.debouncer.filter(value, Instant::now()))
+    }
+
+    /// Reads the current state of the digital input along with when it was sampled.
+    ///
+    /// The timestamp is taken immediately after the line is read and before the LED
+    /// is updated, so LED I2C traffic does not skew it. Debouncing applies as in
+    /// `read`. For edges captured by the kernel, use `timestamped_event_stream`, whose
+    /// timestamps are more precise.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok((bool, Instant))` - The input state and the monotonic time it was read
+    /// * `Err(HatError)` - If reading the input failed
+    pub fn read_timestamped(&mut self) -> Result<(bool, Instant)> {
+        let value = self.sample()?;
+        let sampled = Instant::now();
+        let value = self.debouncer.filter(value, sampled);
+        self.update_led(value);
+        Ok((value, sampled))
+    }
+
+    /// Sets how long the input must hold a new level before `read` reports it.
+    ///
+    /// Linux can debounce input lines in the kernel through the v2 GPIO character
+    /// device interface, but the `gpio_cdev` crate used here only speaks the v1
+    /// interface, which has no debounce attribute. The period is therefore applied in
+    /// software: each `read` samples the line, and a change is only reported once
+    /// every read over the period has agreed with it. Unlike kernel debouncing, this
+    /// depends on `read` being called regularly, and a bounce shorter than the polling
+    /// interval can go unseen rather than filtered. Edge event streams are not
+    /// debounced.
+    ///
+    /// # Arguments
+    ///
+    /// * `period` - How long a new level must be stable; `Duration::ZERO` disables debouncing
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::time::Duration;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.inputs.one.set_debounce(Duration::from_millis(20));
+    /// let pressed = hat.inputs.one.read().unwrap();
+    /// ```
+    pub fn set_debounce(&mut self, period: Duration) {
+        self.debouncer = Debouncer::new(period);
+    }
+
+    /// Returns the debounce period, `Duration::ZERO` if debouncing is disabled.
+    pub fn debounce(&self) -> Duration {
+        self.debouncer.period
+    }
+
+    /// Returns whether the indicator LED automatically reflects the input state.
+    pub fn auto_light(&self) -> bool {
+        self._auto_light
+    }
+
+    /// Returns the GPIO line offset of this input on its gpiochip.
+    pub fn line_offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Temporarily hands the input's GPIO line to caller code.
+    ///
+    /// The kernel only lets one handle hold a line at a time, so the crate's own
+    /// handle is released before `f` runs and re-requested as a plain input after it
+    /// returns. Inside `f`, the line can be requested with any flags the crate does
+    /// not cover, such as a custom edge-event watcher. Every handle requested from it
+    /// must be dropped before `f` returns, or the input cannot be re-acquired.
+    ///
+    /// This is an advanced escape hatch; prefer `read` and `event_stream` where they
+    /// are sufficient.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Code to run while the caller owns the line
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(R)` - The value returned by `f`, once the input has been re-acquired
+    /// * `Err(HatError)` - If the line had already been lost or could not be re-acquired;
+    ///   the input then reports errors from `read` until the HAT is re-created
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use linux_embedded_hal::gpio_cdev::{EventRequestFlags, LineRequestFlags};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let first_edge = hat.inputs.one.with_line(|line| {
+    ///     let mut events = line
+    ///         .events(LineRequestFlags::INPUT, EventRequestFlags::FALLING_EDGE, "my-app")
+    ///         .ok()?;
+    ///     events.next()
+    /// });
+    /// ```
+    pub fn with_line<R>(&mut self, f: impl FnOnce(&Line) -> R) -> Result<R> {
+        let pin = self
+            .pin
+            .take()
+            .ok_or_else(|| HatError::Gpio("Input line has been released".to_string()))?;
+        let line = pin.line().clone();
+        drop(pin);
+
+        let result = f(&line);
+
+        let handle = line
+            .request(LineRequestFlags::INPUT, 0, "AutomationHAT Rust SDK")
+            .map_err(|error| {
+                HatError::Gpio(format!(
+                    "Failed to re-acquire line {}: {}",
+                    self.offset, error
+                ))
+            })?;
+        self.pin = Some(CdevPin::new(handle).map_err(|error| HatError::Gpio(error.to_string()))?);
+        Ok(result)
+    }
+
+    /// Counts pulses on this input on a background thread, debouncing each one.
+    ///
+    /// Mechanical contacts such as the reed switch of a flow meter bounce as they
+    /// open and close, and an undebounced counter counts every bounce. Here a change
+    /// only counts once the new level has held for `debounce`, so each closure counts
+    /// once. Linux can debounce lines in the kernel, but only through the v2 GPIO
+    /// interface, which the `gpio_cdev` crate used here does not speak, so the
+    /// counting thread samples the line every millisecond and debounces in software.
+    /// A pulse must therefore last longer than `debounce` plus about a millisecond to
+    /// be counted, and likewise the gap between pulses. Samples that fail to read are
+    /// skipped.
+    ///
+    /// The counter holds the input's line while it runs, so `read` is unavailable
+    /// until it is stopped or dropped. When auto_light is enabled the LED follows the
+    /// debounced level.
+    ///
+    /// # Arguments
+    ///
+    /// * `debounce` - How long a new level must hold to count; `Duration::ZERO` counts
+    ///   every sampled change
+    /// * `edge` - Which transition is counted
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PulseCounter)` - The running counter, starting from zero
+    /// * `Err(HatError)` - If the line has been released or the thread could not be
+    ///   started; in the latter case the line is lost and `read` reports errors until
+    ///   the HAT is re-created
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, Edge, HatType};
+    /// use std::{thread, time::Duration};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // A reed switch flow meter giving 450 pulses per litre
+    /// let counter = hat
+    ///     .inputs
+    ///     .one
+    ///     .counter_with_debounce(Duration::from_millis(5), Edge::Falling)?;
+    /// thread::sleep(Duration::from_secs(60));
+    /// println!("{:.2} L/min", counter.reset() as f64 / 450.0);
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn counter_with_debounce(
+        &mut self,
+        debounce: Duration,
+        edge: Edge,
+    ) -> Result<PulseCounter<'_>> {
+        let pin = self
+            .pin
+            .take()
+            .ok_or_else(|| HatError::Gpio("Input line has been released".to_string()))?;
+        let count = Arc::new(AtomicU64::new(0));
+        let led = self.led.clone().filter(|_| self._auto_light);
+
+        let thread_count = count.clone();
+        let worker = Worker::spawn(
+            format!("automation-hat-counter-{}", self.offset),
+            move |stop| count_loop(pin, led, debounce, edge, &thread_count, &stop),
+        )?;
+        Ok(PulseCounter {
+            input: self,
+            count,
+            worker,
+        })
+    }
+
+    /// Swaps the indicator LED, e.g. when the board is reconfigured for another
+    /// variant. The new LED catches up on the next `read`.
+    pub(crate) fn replace_led(&mut self, led: Option<LED>, auto_light: bool) {
+        self.led = led;
+        self._auto_light = auto_light;
+    }
+
+    /// Reads the line without touching the LED or debouncing.
+    pub(crate) fn sample(&mut self) -> Result<bool> {
+        let pin = self
+            .pin
+            .as_mut()
+            .ok_or_else(|| HatError::Gpio("Input line has been released".to_string()))?;
+        pin.is_high().map_err(|e| HatError::Gpio(e.to_string()))
+    }
+
+    /// Swaps the input's line for a kernel request of events on both edges, as used
+    /// by `AutomationHAT::run`. `read` fails until the line is handed back with
+    /// `unwatch_edges`.
+    pub(crate) fn watch_edges(&mut self) -> Result<LineEventHandle> {
+        let pin = self
+            .pin
+            .take()
+            .ok_or_else(|| HatError::Gpio("Input line has been released".to_string()))?;
+        let line = pin.line().clone();
+        drop(pin);
+        line.events(
+            LineRequestFlags::INPUT,
+            EventRequestFlags::BOTH_EDGES,
+            "AutomationHAT Rust SDK",
+        )
+        .map_err(|error| {
+            self.restore_line(&line);
+            HatError::Gpio(format!(
+                "Failed to request events on line {}: {}",
+                self.offset, error
+            ))
+        })
+    }
+
+    /// Releases an event request made by `watch_edges` and hands the line back to
+    /// the input.
+    pub(crate) fn unwatch_edges(&mut self, events: LineEventHandle) {
+        let line = events.line().clone();
+        drop(events);
+        self.restore_line(&line);
+    }
+
+    /// Reads the next edge from an event request made by `watch_edges`, blocking
+    /// until one occurs, and mirrors it on the LED.
+    pub(crate) fn next_edge(&mut self, events: &mut LineEventHandle) -> Result<Edge> {
+        let event = events
+            .get_event()
+            .map_err(|error| HatError::Gpio(error.to_string()))?;
+        let edge = edge_of(event.event_type());
+        self.update_led(edge == Edge::Rising);
+        Ok(edge)
+    }
+
+    /// Requests `line` as an input again, leaving the input released on failure.
+    fn restore_line(&mut self, line: &Line) {
+        self.pin = line
+            .request(LineRequestFlags::INPUT, 0, "AutomationHAT Rust SDK")
+            .ok()
+            .and_then(|handle| CdevPin::new(handle).ok());
+    }
+
+    /// Mirrors `value` on the LED when auto_light is enabled and an LED is attached.
+    fn update_led(&mut self, value: bool) {
+        if self._auto_light
+            && let Some(led) = &mut self.led
+            && let Err(e) = led.set_brightness_clamped(match value {
                 true => 1.0,
                 false => 0.0,
-            }) {
-                println!("Failed to update LED: {}", e);
+            })
+        {
+            println!("Failed to update LED: {}", e);
+        }
+    }
+
+    /// Returns a stream of edge events for this input.
+    ///
+    /// The stream is driven by the kernel's GPIO event interface and must be polled
+    /// from within a tokio runtime. While it exists, the input's line is requested for
+    /// events instead of plain reads; dropping the stream cancels it and restores the
+    /// input for `read`. When auto_light is enabled the LED follows each edge.
+    ///
+    /// # Backpressure
+    ///
+    /// Edges are timestamped and queued by the kernel, not by this crate. The kernel
+    /// queue holds only a small number of events (16 on current kernels); if edges
+    /// arrive faster than the stream is polled, the newest events are discarded until
+    /// there is room again, so consumers should not assume strict alternation of
+    /// `Rising` and `Falling`.
+    ///
+    /// # Returns
+    ///
+    /// A stream yielding `Ok(Edge)` for each transition, or `Err(HatError)` if the
+    /// line could not be requested for events or an event could not be read. A
+    /// setup failure is reported as the first item, after which the stream ends.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use futures::StreamExt;
+    ///
+    /// # async fn run() {
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let mut edges = hat.inputs.one.event_stream();
+    /// while let Some(edge) = edges.next().await {
+    ///     println!("Input 1: {:?}", edge);
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn event_stream(&mut self) -> impl Stream<Item = Result<Edge>> + '_ {
+        EdgeStream::open(self, |event| event.edge)
+    }
+
+    /// Returns a stream of edge events carrying the kernel's capture timestamps.
+    ///
+    /// Behaves exactly like `event_stream`, but each item also includes the time the
+    /// kernel detected the edge, which is unaffected by how late the stream is polled.
+    ///
+    /// # Returns
+    ///
+    /// A stream yielding `Ok(EdgeEvent)` for each transition, or `Err(HatError)` as
+    /// described for `event_stream`
+    #[cfg(feature = "async")]
+    pub fn timestamped_event_stream(&mut self) -> impl Stream<Item = Result<EdgeEvent>> + '_ {
+        EdgeStream::open(self, |event| event)
+    }
+}
+
+/// Counts debounced pulses on a digital input, returned by
+/// `DigitalInput::counter_with_debounce`.
+///
+/// Dropping the counter stops its thread and hands the line back to the input.
+pub struct PulseCounter<'a> {
+    /// The input whose line is being counted
+    input: &'a mut DigitalInput,
+    /// Pulses counted since the counter started or was last reset
+    count: Arc<AtomicU64>,
+    /// The counting thread, returning the pin when it exits
+    worker: Worker<CdevPin>,
+}
+
+impl PulseCounter<'_> {
+    /// Returns the number of pulses counted since the counter started or was last reset.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Sets the count back to zero.
+    ///
+    /// # Returns
+    ///
+    /// The count before the reset, so that no pulse is lost between reading and
+    /// resetting it
+    pub fn reset(&self) -> u64 {
+        self.count.swap(0, Ordering::SeqCst)
+    }
+
+    /// Stops counting and hands the line back to the input.
+    ///
+    /// # Returns
+    ///
+    /// The final count
+    pub fn stop(mut self) -> u64 {
+        self.shutdown();
+        self.count()
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(pin) = self.worker.join() {
+            self.input.pin = Some(pin);
+        }
+    }
+}
+
+impl Drop for PulseCounter<'_> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Converts a kernel event type into the edge it reports.
+fn edge_of(event_type: EventType) -> Edge {
+    match event_type {
+        EventType::RisingEdge => Edge::Rising,
+        EventType::FallingEdge => Edge::Falling,
+    }
+}
+
+/// Body of the counting thread, returning the pin once stopped.
+fn count_loop(
+    mut pin: CdevPin,
+    mut led: Option<LED>,
+    debounce: Duration,
+    edge: Edge,
+    count: &AtomicU64,
+    stop: &StopFlag,
+) -> CdevPin {
+    let mut debouncer = Debouncer::new(debounce);
+    let mut level: Option<bool> = None;
+    let counted = edge == Edge::Rising;
+    while !stop.is_set() {
+        let started = Instant::now();
+        if let Ok(raw) = pin.is_high() {
+            let filtered = debouncer.filter(raw, started);
+            if level
+                .replace(filtered)
+                .is_some_and(|previous| previous != filtered)
+            {
+                if filtered == counted {
+                    count.fetch_add(1, Ordering::SeqCst);
+                }
+                if let Some(led) = led.as_mut() {
+                    let _ = led.set_brightness_clamped(match filtered {
+                        true => 1.0,
+                        false => 0.0,
+                    });
+                }
             }
         }
-        Ok(value)
+        stop.sleep_until(started + COUNTER_INTERVAL);
+    }
+    pin
+}
+
+/// Stream of edge events that owns an input's line until dropped.
+#[cfg(feature = "async")]
+struct EdgeStream<'a, T> {
+    /// The input whose line is being watched
+    input: &'a mut DigitalInput,
+    /// GPIO line, kept so the input can be restored on drop
+    line: Option<Line>,
+    /// Kernel event handle, `None` once the stream has ended
+    events: Option<AsyncLineEventHandle>,
+    /// Setup error to report as the first item
+    error: Option<HatError>,
+    /// Converts each captured event into the stream's item type
+    map: fn(EdgeEvent) -> T,
+}
+
+#[cfg(feature = "async")]
+impl<'a, T> EdgeStream<'a, T> {
+    fn open(input: &'a mut DigitalInput, map: fn(EdgeEvent) -> T) -> Self {
+        let mut stream = EdgeStream {
+            input,
+            line: None,
+            events: None,
+            error: None,
+            map,
+        };
+        let Some(pin) = stream.input.pin.take() else {
+            stream.error = Some(HatError::Gpio("Input line has been released".to_string()));
+            return stream;
+        };
+        let line = pin.line().clone();
+        drop(pin);
+        let offset = line.offset();
+        match line
+            .events(
+                LineRequestFlags::INPUT,
+                EventRequestFlags::BOTH_EDGES,
+                "AutomationHAT Rust SDK",
+            )
+            .and_then(AsyncLineEventHandle::new)
+        {
+            Ok(events) => stream.events = Some(events),
+            Err(error) => {
+                stream.error = Some(HatError::Gpio(format!(
+                    "Failed to request events on line {}: {}",
+                    offset, error
+                )))
+            }
+        }
+        stream.line = Some(line);
+        stream
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Stream for EdgeStream<'_, T> {
+    type Item = Result<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(error) = self.error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+        let Some(events) = self.events.as_mut() else {
+            return Poll::Ready(None);
+        };
+        match Pin::new(events).poll_next(cx) {
+            Poll::Ready(Some(Ok(event))) => {
+                let edge = edge_of(event.event_type());
+                self.input.update_led(edge == Edge::Rising);
+                let event = EdgeEvent {
+                    edge,
+                    timestamp: Duration::from_nanos(event.timestamp()),
+                };
+                Poll::Ready(Some(Ok((self.map)(event))))
+            }
+            Poll::Ready(Some(Err(error))) => {
+                Poll::Ready(Some(Err(HatError::Gpio(error.to_string()))))
+            }
+            Poll::Ready(None) => {
+                self.events = None;
+                Poll::Ready(None)
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T> Drop for EdgeStream<'_, T> {
+    /// Releases the event request and hands the line back to the input.
+    fn drop(&mut self) {
+        self.events = None;
+        if let Some(line) = self.line.take() {
+            self.input.restore_line(&line);
+        }
     }
 }
@@ -3,7 +3,13 @@
 //! This module provides control for the digital output pins on Automation HAT boards.
 //! Digital outputs provide 5V signals for controlling external devices and have indicator
 //! LEDs to show their current state.
+//!
+//! On the Automation HAT family the outputs are driven through a ULN2003A Darlington array,
+//! so they *sink* current: driving the GPIO high switches the output terminal to ground
+//! rather than sourcing 5V. Use [`Polarity`] to choose which electrical level a logical
+//! `write(true)` produces.
 
+use crate::error::{HatError, Result};
 use crate::lights::LED;
 
 use embedded_hal::digital::{OutputPin, PinState};
@@ -11,6 +17,21 @@ use linux_embedded_hal::{
     CdevPin,
     gpio_cdev::{Line, LineRequestFlags},
 };
+use std::thread;
+use std::time::Duration;
+
+/// Period of the software PWM used by `DigitalOutput::ramp_to` (100 Hz)
+static RAMP_PERIOD: Duration = Duration::from_millis(10);
+
+/// Maps the logical state of a digital output onto the GPIO level that drives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Polarity {
+    /// `write(true)` drives the GPIO high (the sinking driver switches on)
+    #[default]
+    ActiveHigh,
+    /// `write(true)` drives the GPIO low (the sinking driver switches off)
+    ActiveLow,
+}
 
 /// Controls a digital output on the Automation HAT.
 ///
@@ -18,12 +39,16 @@ use linux_embedded_hal::{
 /// When an output is set high, it outputs 5V. Each output can have an associated
 /// LED that automatically indicates the output state.
 pub struct DigitalOutput {
-    /// GPIO pin for the digital output
-    pin: CdevPin,
+    /// The GPIO line behind the output, kept so it can be requested again
+    line: Line,
+    /// GPIO pin for the digital output, `None` while the line is released
+    pin: Option<CdevPin>,
     /// Optional LED indicator for this output
     led: Option<LED>,
     /// Whether the LED should automatically reflect output state
     _auto_light: bool,
+    /// How the logical state maps onto the GPIO level
+    polarity: Polarity,
     /// Current state of the output (true = high/on, false = low/off)
     pub value: bool,
 }
@@ -40,14 +65,16 @@ impl DigitalOutput {
     ///
     /// A new `DigitalOutput` instance with automatic LED indication enabled
     pub fn new(line: Line, led: Option<LED>) -> Self {
-        let line = line
+        let handle = line
             .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
             .unwrap();
-        let pin = CdevPin::new(line).unwrap();
+        let pin = CdevPin::new(handle).unwrap();
         DigitalOutput {
-            pin,
+            line,
+            pin: Some(pin),
             led,
             _auto_light: true,
+            polarity: Polarity::ActiveHigh,
             value: false,
         }
     }
@@ -64,22 +91,72 @@ impl DigitalOutput {
     ///
     /// A new `DigitalOutput` instance with the specified LED behavior
     pub fn new_with_auto_light(line: Line, led: Option<LED>, auto_light: bool) -> Self {
-        let line = line
-            .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
-            .unwrap();
-        let pin = CdevPin::new(line).unwrap();
-        DigitalOutput {
-            pin,
+        Self::try_new_with_auto_light(line, led, auto_light).unwrap()
+    }
+
+    /// Creates a new digital output with configurable LED indication, returning an error
+    /// instead of panicking if the GPIO line cannot be requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - GPIO line connected to the digital output
+    /// * `led` - Optional LED indicator for this output
+    /// * `auto_light` - Whether the LED should automatically reflect the output state
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(DigitalOutput)` - If the line was requested successfully
+    /// * `Err(HatError)` - If the line is unavailable or already in use
+    pub fn try_new_with_auto_light(line: Line, led: Option<LED>, auto_light: bool) -> Result<Self> {
+        let pin = request_output(&line, 0)?;
+        Ok(DigitalOutput {
+            line,
+            pin: Some(pin),
             led,
             _auto_light: auto_light,
+            polarity: Polarity::ActiveHigh,
             value: false,
-        }
+        })
+    }
+
+    /// Sets the polarity used to translate logical states into GPIO levels.
+    ///
+    /// The new polarity is applied immediately, so the pin is re-driven to keep
+    /// the current logical `value` unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `polarity` - The mapping between logical state and GPIO level
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the polarity was applied
+    /// * `Err(HatError::Gpio)` - If re-driving the pin failed
+    pub fn set_polarity(&mut self, polarity: Polarity) -> Result<()> {
+        self.polarity = polarity;
+        self.write(self.value)
+    }
+
+    /// Returns whether the indicator LED automatically reflects the output state.
+    pub fn auto_light(&self) -> bool {
+        self._auto_light
+    }
+
+    /// Returns the GPIO line offset of this output on its gpiochip.
+    pub fn line_offset(&self) -> u32 {
+        self.line.offset()
+    }
+
+    /// Returns the polarity currently used by this output.
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
     }
 
     /// Sets the state of the digital output.
     ///
-    /// When `on` is true, the output is set high (5V).
-    /// When `on` is false, the output is set low (0V).
+    /// When `on` is true, the output is set active; with the default
+    /// [`Polarity::ActiveHigh`] this drives the GPIO high and the output sinks to ground.
+    /// When `on` is false, the output is set inactive.
     /// If auto_light is enabled and an LED is attached, this method will
     /// also update the LED to reflect the current output state.
     ///
@@ -90,28 +167,210 @@ impl DigitalOutput {
     /// # Returns
     ///
     /// * `Ok(())` - If the output was successfully set
-    /// * `Err(String)` - If setting the output or LED failed, with an error message
-    pub fn write(&mut self, on: bool) -> Result<(), String> {
-        if self._auto_light {
-            if let Some(led) = &mut self.led {
-                match led.set(match on {
-                    true => 1.0,
-                    false => 0.0,
-                }) {
-                    Ok(_) => {}
-                    Err(e) => return Err(format!("Unable to set LED state: {}", e)),
-                }
+    /// * `Err(HatError)` - If setting the output or LED failed
+    pub fn write(&mut self, on: bool) -> Result<()> {
+        if self._auto_light
+            && let Some(led) = &mut self.led
+        {
+            led.set_brightness_clamped(match on {
+                true => 1.0,
+                false => 0.0,
+            })?;
+        }
+        self.drive(on)?;
+        self.value = on;
+        Ok(())
+    }
+
+    /// Ramps the output to `target` over `duration` using software PWM.
+    ///
+    /// The output is switched at a fixed 100 Hz (a 10 ms period) while its
+    /// duty cycle moves linearly from the current state to `target`, after which it
+    /// settles to a static level with `write(target)`. This gives a simple soft-start
+    /// or soft-stop for loads such as LED strips or motors driven through an external
+    /// MOSFET. If the output is already at `target`, it is simply re-written.
+    ///
+    /// The PWM is timed by the calling thread, which is blocked for the whole ramp.
+    /// Timing relies on the Linux scheduler, so expect jitter of a few hundred
+    /// microseconds per edge, and the ULN2003A is not intended for fast switching.
+    /// The indicator LED is only updated once the ramp has finished.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The state to ramp to
+    /// * `duration` - How long the ramp takes; ramps shorter than one PWM period switch directly
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the ramp completed and the output is at `target`
+    /// * `Err(HatError::Gpio)` - If setting the pin failed
+    pub fn ramp_to(&mut self, target: bool, duration: Duration) -> Result<()> {
+        let period = RAMP_PERIOD;
+        let periods = u32::try_from(duration.as_micros() / period.as_micros()).unwrap_or(u32::MAX);
+        if self.value != target {
+            for step in 0..periods {
+                let progress = (step as f64 + 0.5) / periods as f64;
+                let duty = match target {
+                    true => progress,
+                    false => 1.0 - progress,
+                };
+                let on_time = period.mul_f64(duty);
+                self.drive(true)?;
+                thread::sleep(on_time);
+                self.drive(false)?;
+                thread::sleep(period - on_time);
             }
         }
-        return match self.pin.set_state(match on {
+        self.write(target)
+    }
+
+    /// Sets the output without touching its LED, for callers that batch LED updates.
+    pub(crate) fn write_pin(&mut self, on: bool) -> Result<()> {
+        self.drive(on)?;
+        self.value = on;
+        Ok(())
+    }
+
+    /// Swaps the indicator LED, e.g. when the board is reconfigured for another
+    /// variant, and shows the output's state on the new one.
+    pub(crate) fn replace_led(&mut self, led: Option<LED>, auto_light: bool) {
+        self.led = led;
+        self._auto_light = auto_light;
+        let value = self.value;
+        if let Some(led) = self.auto_led_mut() {
+            let _ = led.set_brightness_clamped(match value {
+                true => 1.0,
+                false => 0.0,
+            });
+        }
+    }
+
+    /// Returns the LED that follows this output, if auto-lighting is enabled.
+    pub(crate) fn auto_led_mut(&mut self) -> Option<&mut LED> {
+        match self._auto_light {
+            true => self.led.as_mut(),
+            false => None,
+        }
+    }
+
+    /// Consumes the output and hands back its GPIO line, ungated.
+    ///
+    /// The line is released to the kernel, so another part of the application (or
+    /// another process) can request it, e.g. to bit-bang a one-off protocol. The
+    /// kernel usually leaves a released line at its last level, but makes no promise
+    /// to; drive it explicitly once you have requested it. The indicator LED is left
+    /// as it was. To get the output back, pass the line to
+    /// `try_new_with_auto_light` once the other user has dropped its request.
+    ///
+    /// Outputs owned by an `AutomationHAT` cannot be moved out of it; use
+    /// `release_line` and `reacquire` for those.
+    ///
+    /// # Returns
+    ///
+    /// The `Line` this output drove
+    pub fn release(self) -> Line {
+        self.line
+    }
+
+    /// Releases the GPIO line for use elsewhere, keeping this output so it can be
+    /// re-acquired later.
+    ///
+    /// While released, `write` and the other methods that drive the pin fail, and
+    /// `value` keeps the last state written. As with `release`, the line's level is
+    /// up to the kernel and the new user until it is re-acquired.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Line)` - The line, free to be requested by its new user
+    /// * `Err(HatError::Gpio)` - If the line is already released
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use linux_embedded_hal::gpio_cdev::LineRequestFlags;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let line = hat.outputs.one.release_line()?;
+    /// {
+    ///     let handle = line.request(LineRequestFlags::OUTPUT, 0, "bit-bang").unwrap();
+    ///     handle.set_value(1).unwrap();
+    ///     // The handle is dropped here, releasing the line again
+    /// }
+    /// hat.outputs.one.reacquire()?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn release_line(&mut self) -> Result<Line> {
+        match self.pin.take() {
+            Some(_) => Ok(self.line.clone()),
+            None => Err(HatError::Gpio(format!(
+                "Line {} is already released",
+                self.line_offset()
+            ))),
+        }
+    }
+
+    /// Requests the GPIO line again after `release_line`.
+    ///
+    /// Every request made of the line since it was released must have been dropped
+    /// first, or the kernel reports it as busy. The line is requested already driven
+    /// to the level of the current `value` and polarity, so the output resumes its
+    /// last state without a glitch. Calling this on an output that holds its line
+    /// does nothing.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the output holds its line again
+    /// * `Err(HatError::Gpio)` - If the line could not be requested, e.g. because it is
+    ///   still in use
+    pub fn reacquire(&mut self) -> Result<()> {
+        if self.pin.is_none() {
+            let level = self.level(self.value) as u8;
+            self.pin = Some(request_output(&self.line, level)?);
+        }
+        Ok(())
+    }
+
+    /// Returns whether the GPIO line has been released with `release_line`.
+    pub fn is_released(&self) -> bool {
+        self.pin.is_none()
+    }
+
+    /// Returns whether the pin is driven high to represent `on`.
+    fn level(&self, on: bool) -> bool {
+        match self.polarity {
+            Polarity::ActiveHigh => on,
+            Polarity::ActiveLow => !on,
+        }
+    }
+
+    /// Drives the pin to the level representing `on` without touching the LED.
+    fn drive(&mut self, on: bool) -> Result<()> {
+        let high = self.level(on);
+        let Some(pin) = &mut self.pin else {
+            return Err(HatError::Gpio(format!(
+                "Line {} has been released",
+                self.line.offset()
+            )));
+        };
+        pin.set_state(match high {
             true => PinState::High,
             false => PinState::Low,
-        }) {
-            Ok(_) => {
-                self.value = on;
-                Ok(())
-            }
-            Err(e) => Err(format!("Unable to set pin state: {}", e)),
-        };
+        })
+        .map_err(|e| HatError::Gpio(format!("Unable to set pin state: {}", e)))
     }
 }
+
+/// Requests `line` as an output driven to `level`.
+fn request_output(line: &Line, level: u8) -> Result<CdevPin> {
+    let handle = line
+        .request(LineRequestFlags::OUTPUT, level, "AutomationHAT Rust SDK")
+        .map_err(|error| {
+            HatError::Gpio(format!(
+                "Failed to request line {}: {}",
+                line.offset(),
+                error
+            ))
+        })?;
+    CdevPin::new(handle).map_err(|error| HatError::Gpio(error.to_string()))
+}
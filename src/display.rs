@@ -0,0 +1,167 @@
+//! Display support for the Automation HAT Mini.
+//!
+//! This module provides initialization for the 0.96" 160x80 ST7735 color LCD on the
+//! Automation HAT Mini. It is only compiled with the `display` cargo feature, which is
+//! enabled by default.
+//!
+//! None of the Automation HAT variants have color indicator LEDs: the SN3218 drives
+//! single-color LEDs on the full-size HAT, and the pHAT and Mini have no indicator
+//! LEDs at all. The display is therefore the only way to show color, and
+//! [`AutomationHAT::show_status`] uses it to draw a colored status bar.
+//!
+//! The Mini has no user buttons, so the crate exposes none. Menu-driven interfaces on
+//! the display need external buttons, wired to the digital inputs or to spare GPIO
+//! lines.
+
+use crate::AutomationHAT;
+use crate::error::{HatError, Result};
+
+use linux_embedded_hal::{
+    CdevPin, SpidevDevice,
+    gpio_cdev::{Chip, LineRequestFlags},
+};
+use st7735_lcd::ST7735;
+
+/// The ST7735 display driver type used by the Automation HAT Mini.
+pub type Display = ST7735<SpidevDevice, CdevPin, CdevPin>;
+
+const DISPLAY_DC: u32 = 9;
+const DISPLAY_RST: u32 = 22;
+
+/// The GPIO lines requested by `init`, with their roles
+pub(crate) const CONTROL_LINES: [(&str, u32); 2] =
+    [("Display DC", DISPLAY_DC), ("Display RST", DISPLAY_RST)];
+
+// Panel geometry as passed to the ST7735 driver, and the height of the status bar
+pub(crate) const DISPLAY_WIDTH: u16 = 80;
+pub(crate) const DISPLAY_HEIGHT: u16 = 160;
+const STATUS_BAR_HEIGHT: u16 = 12;
+
+/// The meaning shown by the status bar on the Automation HAT Mini display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Everything is working (green)
+    Ok,
+    /// Attention needed, but still operating (amber)
+    Warning,
+    /// A fault has occurred (red)
+    Fault,
+}
+
+impl Status {
+    /// Returns the RGB565 color used to draw this status.
+    pub fn rgb565(&self) -> u16 {
+        match self {
+            Status::Ok => 0x07E0,
+            Status::Warning => 0xFDE0,
+            Status::Fault => 0xF800,
+        }
+    }
+}
+
+impl AutomationHAT {
+    /// Returns whether the board has an initialized display.
+    ///
+    /// Only the Automation HAT Mini has a display, and it is skipped when disabled
+    /// with `HatConfig::enable_display(false)`.
+    pub fn has_display(&self) -> bool {
+        self.display.is_some()
+    }
+
+    /// Returns the display, so drawing code can use `?` instead of unwrapping.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&mut Display)` - The initialized display
+    /// * `Err(HatError::NoDisplay)` - If the board has no display or it was disabled
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHATMini);
+    /// let display = hat.display_mut()?;
+    /// display.set_pixels(0, 0, 79, 159, std::iter::repeat_n(0x0000, 80 * 160)).ok();
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn display_mut(&mut self) -> Result<&mut Display> {
+        self.display.as_mut().ok_or(HatError::NoDisplay)
+    }
+
+    /// Draws a colored status bar across the top edge of the display.
+    ///
+    /// Only the bar is redrawn, so the rest of the screen can be used for other
+    /// content as long as it leaves the top rows free.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The status to show: green for ok, amber for warning, red for fault
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the bar was drawn
+    /// * `Err(HatError::NoDisplay)` - If the board has no initialized display
+    /// * `Err(HatError::Display)` - If the SPI write failed
+    pub fn show_status(&mut self, status: Status) -> Result<()> {
+        let display = self.display_mut()?;
+        let pixels = (DISPLAY_WIDTH as usize) * (STATUS_BAR_HEIGHT as usize);
+        display
+            .set_pixels(
+                0,
+                0,
+                DISPLAY_WIDTH - 1,
+                STATUS_BAR_HEIGHT - 1,
+                std::iter::repeat_n(status.rgb565(), pixels),
+            )
+            .map_err(|error| HatError::Display(format!("Failed to draw status bar: {:?}", error)))
+    }
+}
+
+/// Opens and initializes the Automation HAT Mini display.
+///
+/// # Arguments
+///
+/// * `gpio_chip` - The GPIO chip providing the DC and RST lines
+///
+/// # Returns
+///
+/// * `Ok(Display)` - An initialized display ready for drawing
+/// * `Err(HatError)` - If the control lines, SPI device or controller could not be set up
+pub(crate) fn init(gpio_chip: &mut Chip) -> Result<Display> {
+    let dc = request_output(gpio_chip, DISPLAY_DC, "DC")?;
+    let rst = request_output(gpio_chip, DISPLAY_RST, "RST")?;
+
+    let spi = SpidevDevice::open("/dev/spidev0.1").map_err(|error| {
+        HatError::Display(format!(
+            "Failed to open /dev/spidev0.1: {} (is SPI enabled?)",
+            error
+        ))
+    })?;
+    let mut display = ST7735::new(spi, dc, rst, false, true, 80, 160);
+
+    let mut delay = linux_embedded_hal::Delay {};
+    display.init(&mut delay).map_err(|error| {
+        HatError::Display(format!(
+            "SPI display init failed: {:?} (is SPI enabled?)",
+            error
+        ))
+    })?;
+    display.set_offset(26, 2);
+
+    Ok(display)
+}
+
+/// Requests one of the display control lines as an output.
+fn request_output(gpio_chip: &mut Chip, offset: u32, name: &str) -> Result<CdevPin> {
+    let line = gpio_chip
+        .get_line(offset)
+        .and_then(|line| line.request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK"))
+        .map_err(|error| {
+            HatError::Display(format!(
+                "Failed to request display {} line {}: {}",
+                name, offset, error
+            ))
+        })?;
+    CdevPin::new(line).map_err(|error| HatError::Display(error.to_string()))
+}
@@ -0,0 +1,92 @@
+//! Error types for Automation HAT boards.
+//!
+//! This module provides the `HatError` enum, which describes the ways an
+//! operation on the Automation HAT can fail, and the `Result` alias used by
+//! operations that fail with it.
+
+use std::fmt;
+
+/// Result of an operation that can fail with a [`HatError`].
+///
+/// Importing this alias (directly or through [`prelude`](crate::prelude)) shadows
+/// `std::result::Result` in that scope; spell out `std::result::Result` for results
+/// with other error types.
+pub type Result<T> = std::result::Result<T, HatError>;
+
+/// Errors that can occur while operating an Automation HAT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HatError {
+    /// Reading from the ADS1015 analog-to-digital converter failed
+    Analog(String),
+    /// Updating an LED through the SN3218 LED driver failed
+    Led(String),
+    /// Reading or writing a GPIO line failed
+    Gpio(String),
+    /// A channel index does not exist on the board
+    InvalidChannel(usize),
+    /// Initializing or drawing to the Automation HAT Mini display failed
+    Display(String),
+    /// An operating system facility (such as signal handling) failed
+    Io(String),
+    /// The operation was refused because running it could be unsafe
+    Refused(String),
+    /// The supplied configuration is invalid
+    Config(String),
+    /// No device with this name is configured
+    UnknownDevice(String),
+    /// The component is not fitted on this board variant
+    NotPresent(String),
+    /// No display is initialized, because the board is not an Automation HAT Mini or
+    /// the display was disabled
+    NoDisplay,
+    /// An LED brightness was NaN, infinite, or outside 0.0-1.0
+    InvalidBrightness(f64),
+    /// The board has no indicator LEDs to drive, with the reason
+    NoLeds(String),
+    /// The analog input with this board label has no ADC behind it, because analog
+    /// support was disabled or failed to initialize
+    AnalogUnavailable(usize),
+    /// More than one role was assigned the same GPIO line, which the kernel would
+    /// refuse to hand out twice
+    PinConflict {
+        /// The GPIO (BCM) line
+        line: u32,
+        /// Every role assigned to the line
+        roles: Vec<String>,
+    },
+}
+
+impl fmt::Display for HatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HatError::Analog(message) => write!(f, "Analog input error: {}", message),
+            HatError::Led(message) => write!(f, "LED error: {}", message),
+            HatError::Gpio(message) => write!(f, "GPIO error: {}", message),
+            HatError::InvalidChannel(index) => write!(f, "Invalid channel: {}", index),
+            HatError::Display(message) => write!(f, "Display error: {}", message),
+            HatError::Io(message) => write!(f, "I/O error: {}", message),
+            HatError::Refused(message) => write!(f, "Refused: {}", message),
+            HatError::Config(message) => write!(f, "Invalid configuration: {}", message),
+            HatError::UnknownDevice(name) => write!(f, "Unknown device: {}", name),
+            HatError::NotPresent(name) => write!(f, "{} is not present on this board", name),
+            HatError::NoDisplay => write!(f, "No display is initialized"),
+            HatError::InvalidBrightness(brightness) => write!(
+                f,
+                "Invalid brightness {}: must be between 0.0 and 1.0",
+                brightness
+            ),
+            HatError::NoLeds(reason) => write!(f, "No LEDs: {}", reason),
+            HatError::AnalogUnavailable(index) => {
+                write!(f, "Analog input {} is not available", index)
+            }
+            HatError::PinConflict { line, roles } => write!(
+                f,
+                "GPIO line {} is assigned to more than one role: {}",
+                line,
+                roles.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HatError {}
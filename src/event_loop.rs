@@ -0,0 +1,512 @@
+//! A single event loop over the inputs, analog thresholds and a timer.
+//!
+//! This module provides `EventLoop`, the configuration for `AutomationHAT::run`, which
+//! watches the digital inputs, analog threshold crossings and a periodic tick, and
+//! hands each occurrence to one handler as an `Event`. Applications that react to
+//! several sources then need neither a watcher thread per source nor a loop selecting
+//! between them.
+//!
+//! # Threading model
+//!
+//! `run` blocks the calling thread and does all of its work there: it sleeps until
+//! the kernel reports an edge on a digital input, the analog thresholds are due to be
+//! polled or a tick is due, then calls the handler for each event found, in order,
+//! before waiting again. The handler therefore needs to be neither `Send` nor
+//! `'static`, and can borrow local state. While it runs nothing is read, so it should
+//! return promptly. Input edges queue in the kernel meanwhile and are delivered
+//! afterwards, but threshold crossings that revert while it runs, or within one poll
+//! interval, are not seen. Work that takes longer belongs on another thread.
+//!
+//! The loop holds the lines of the digital inputs while it runs, so `read` on them
+//! fails from the handler; their changes arrive as `Event::Input` instead. Edges are
+//! reported as the kernel sees them, without the input's debounce period.
+//!
+//! A source that fails is reported as a single `Event::Fault` when it starts failing,
+//! and again only once it has read successfully in between. An input whose line is
+//! lost is requested again every poll interval.
+//!
+//! The loop runs until its `StopHandle` is triggered or the handler returns an
+//! error. The handle is `Send` and `Sync`, so it can be triggered from the handler
+//! itself, another thread, or a signal-handling thread. Either way, every relay and
+//! digital output is switched off before `run` returns.
+
+use crate::channel::Channel;
+use crate::digital_input::Edge;
+use crate::{AutomationHAT, HatError, Result};
+
+use linux_embedded_hal::gpio_cdev::LineEventHandle;
+use nix::errno::Errno;
+use nix::libc::c_int;
+use nix::poll::{PollFd, PollFlags, poll};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default time between polls of the analog thresholds
+static DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Something that happened on the board, passed to the handler of `AutomationHAT::run`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A digital input changed level
+    Input {
+        /// The input number as printed on the board (1-3)
+        index: usize,
+        /// The direction of the change
+        edge: Edge,
+    },
+    /// A watched analog input crossed one of its thresholds
+    Threshold {
+        /// The analog input number as printed on the board (1-4)
+        index: usize,
+        /// Whether the input rose above its on threshold (`true`) or fell below its
+        /// off threshold (`false`)
+        above: bool,
+        /// The terminal voltage that crossed the threshold
+        volts: f64,
+    },
+    /// The tick interval elapsed
+    Tick,
+    /// A source started failing; the loop carries on unless the handler returns an
+    /// error, and does not report the source again until it has recovered
+    Fault {
+        /// The channel that failed
+        channel: Channel,
+        /// Why it failed
+        error: HatError,
+    },
+}
+
+/// Stops a running `AutomationHAT::run` loop.
+///
+/// Handles are cheap to clone and can be sent to other threads.
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle {
+    /// Set once the loop should stop
+    stop: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    /// Asks the loop to stop.
+    ///
+    /// The loop finishes the poll in progress, switches every relay and output off
+    /// and returns, within about one poll interval.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the loop has been asked to stop.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+}
+
+/// An analog input watched for crossings of a pair of thresholds.
+#[derive(Debug, Clone, Copy)]
+struct Threshold {
+    /// The analog input number as printed on the board (1-4)
+    index: usize,
+    /// Terminal voltage above which the input is reported as above
+    on: f64,
+    /// Terminal voltage below which the input is reported as below again
+    off: f64,
+}
+
+/// Builder-style configuration of the sources watched by `AutomationHAT::run`.
+///
+/// All three digital inputs are always watched. Analog thresholds and the tick are
+/// added as needed.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, Edge, Event, EventLoop, HatType};
+/// use std::time::Duration;
+///
+/// let mut hat = AutomationHAT::try_new(HatType::AutomationHAT)?;
+/// let event_loop = EventLoop::new()
+///     .threshold(1, 12.0, 11.0)
+///     .tick(Duration::from_secs(1));
+/// let stop = event_loop.stop_handle();
+///
+/// hat.run(event_loop, |hat, event| {
+///     match event {
+///         Event::Input { index: 1, edge: Edge::Rising } => hat.relays.try_write(1, true)?,
+///         Event::Threshold { index: 1, above: false, .. } => hat.relays.try_write(1, false)?,
+///         Event::Input { index: 3, .. } => stop.stop(),
+///         Event::Fault { error, .. } => return Err(error),
+///         _ => {}
+///     }
+///     Ok(())
+/// })?;
+/// # Ok::<(), automation_hat::HatError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventLoop {
+    /// Time between polls of the analog thresholds
+    poll_interval: Duration,
+    /// Interval of `Event::Tick`, `None` for no ticks
+    tick: Option<Duration>,
+    /// Analog inputs watched for threshold crossings
+    thresholds: Vec<Threshold>,
+    /// Stops the loop
+    stop: StopHandle,
+}
+
+impl EventLoop {
+    /// Creates a configuration watching only the digital inputs, checking its stop
+    /// handle every 10ms.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how often the analog thresholds are polled and the stop handle checked.
+    ///
+    /// Digital input edges are reported by the kernel as they happen and do not
+    /// depend on this. Threshold crossings shorter than this can be missed, and each
+    /// poll of an analog threshold costs an ADC conversion.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between the starts of consecutive polls
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Emits `Event::Tick` every `interval`, measured from when the loop starts.
+    ///
+    /// Ticks missed while the handler was busy are not caught up.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between ticks
+    pub fn tick(mut self, interval: Duration) -> Self {
+        self.tick = Some(interval);
+        self
+    }
+
+    /// Watches an analog input for threshold crossings.
+    ///
+    /// The input is reported above once its terminal voltage rises past `on_volts`,
+    /// and below only once it falls past `off_volts`, so noise around a single
+    /// threshold does not produce a stream of events. The first reading sets the
+    /// starting state without an event. Watching the same input again replaces its
+    /// thresholds.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The analog input number as printed on the board (1-4)
+    /// * `on_volts` - The voltage above which the input is reported as above
+    /// * `off_volts` - The voltage below which it is reported as below; at most `on_volts`
+    pub fn threshold(mut self, index: usize, on_volts: f64, off_volts: f64) -> Self {
+        self.thresholds.retain(|threshold| threshold.index != index);
+        self.thresholds.push(Threshold {
+            index,
+            on: on_volts,
+            off: off_volts,
+        });
+        self
+    }
+
+    /// Returns a handle that stops the loop once it is running.
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    /// Checks the configuration before the loop starts.
+    fn validate(&self) -> Result<()> {
+        if self.poll_interval.is_zero() {
+            return Err(HatError::Config(
+                "Poll interval must be greater than zero".to_string(),
+            ));
+        }
+        if self.tick.is_some_and(|tick| tick.is_zero()) {
+            return Err(HatError::Config(
+                "Tick interval must be greater than zero".to_string(),
+            ));
+        }
+        for threshold in &self.thresholds {
+            Channel::Analog(threshold.index).validate()?;
+            if !threshold.on.is_finite() || !threshold.off.is_finite() {
+                return Err(HatError::Config(format!(
+                    "Thresholds of analog input {} must be finite",
+                    threshold.index
+                )));
+            }
+            if threshold.off > threshold.on {
+                return Err(HatError::Config(format!(
+                    "Off threshold {}V of analog input {} is above its on threshold {}V",
+                    threshold.off, threshold.index, threshold.on
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for EventLoop {
+    fn default() -> Self {
+        EventLoop {
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            tick: None,
+            thresholds: Vec::new(),
+            stop: StopHandle::default(),
+        }
+    }
+}
+
+/// What the loop last saw of each source, to detect changes between polls.
+struct Observed {
+    /// Kernel edge events of each digital input, `None` while its line is unavailable
+    edges: [Option<LineEventHandle>; 3],
+    /// Whether each digital input has failed since it last read successfully
+    inputs_failing: [bool; 3],
+    /// State of each watched analog input, in `EventLoop::thresholds` order
+    thresholds: Vec<ThresholdState>,
+    /// When the analog thresholds are next polled
+    next_poll: Instant,
+    /// When the next tick is due, `None` for no ticks
+    next_tick: Option<Instant>,
+}
+
+/// What the loop last saw of a watched analog input.
+#[derive(Debug, Clone, Copy, Default)]
+struct ThresholdState {
+    /// Whether the input is above, `None` until first read
+    above: Option<bool>,
+    /// Whether the input has failed since it last read successfully
+    failing: bool,
+}
+
+impl AutomationHAT {
+    /// Runs an event loop, calling `handler` for each input edge, analog threshold
+    /// crossing and timer tick until the loop is stopped.
+    ///
+    /// `EventLoop` describes the sources. Everything runs on the calling thread: the
+    /// loop waits for the next event, then calls the handler for each event found, so
+    /// it needs to be neither `Send` nor `'static`. It gets the board and may switch
+    /// relays and outputs, but should return promptly, since nothing is read while it
+    /// runs. The digital inputs are held by the loop, so reading them from the handler
+    /// fails.
+    ///
+    /// The loop ends when the `StopHandle` of `event_loop` is triggered or the
+    /// handler returns an error. A source that fails is reported once as
+    /// `Event::Fault` rather than ending the loop; return the error from the handler
+    /// to stop on faults. However the loop ends, the inputs get their lines back and
+    /// every relay and digital output is switched off before returning.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_loop` - The sources to watch
+    /// * `handler` - Called with the board and each event, in the order detected
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the loop was stopped and the board made safe
+    /// * `Err(HatError::Config)` - If `event_loop` is invalid; the loop does not start
+    /// * `Err(HatError::Io)` - If waiting for input edges failed
+    /// * `Err(HatError)` - The error returned by the handler, or else the failure to
+    ///   switch a relay or output off
+    pub fn run(
+        &mut self,
+        event_loop: EventLoop,
+        mut handler: impl FnMut(&mut AutomationHAT, Event) -> Result<()>,
+    ) -> Result<()> {
+        event_loop.validate()?;
+        let now = Instant::now();
+        let mut observed = Observed {
+            edges: [None, None, None],
+            inputs_failing: [false; 3],
+            thresholds: vec![ThresholdState::default(); event_loop.thresholds.len()],
+            next_poll: now,
+            next_tick: event_loop.tick.map(|tick| now + tick),
+        };
+
+        let mut events = Vec::new();
+        let result = loop {
+            if event_loop.stop.is_stopped() {
+                break Ok(());
+            }
+            if let Err(error) = self.wait_events(&event_loop, &mut observed, &mut events) {
+                break Err(error);
+            }
+            if let Err(error) = events.drain(..).try_for_each(|event| handler(self, event)) {
+                break Err(error);
+            }
+        };
+
+        for (watch, index) in observed.edges.iter_mut().zip(1..) {
+            if let (Some(edges), Some(input)) = (watch.take(), self.inputs.get_mut(index)) {
+                input.unwatch_edges(edges);
+            }
+        }
+        let safe = self.all_off();
+        result.and(safe)
+    }
+
+    /// Waits until at least one event is due or the next poll, appending the events
+    /// found to `events`.
+    fn wait_events(
+        &mut self,
+        event_loop: &EventLoop,
+        observed: &mut Observed,
+        events: &mut Vec<Event>,
+    ) -> Result<()> {
+        let now = Instant::now();
+        if now >= observed.next_poll {
+            self.poll_sources(event_loop, observed, events);
+            observed.next_poll = now + event_loop.poll_interval;
+        }
+
+        if let (Some(due), Some(interval)) = (observed.next_tick, event_loop.tick)
+            && now >= due
+        {
+            events.push(Event::Tick);
+            // Skip ticks missed while the handler was busy rather than bunching them
+            let next = due + interval;
+            observed.next_tick = Some(match next > now {
+                true => next,
+                false => now + interval,
+            });
+        }
+        if !events.is_empty() {
+            return Ok(());
+        }
+
+        let deadline = observed
+            .next_tick
+            .map_or(observed.next_poll, |tick| tick.min(observed.next_poll));
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let ready = wait_readable(&observed.edges, timeout)?;
+
+        for (slot, index) in (0..3).zip(1..) {
+            let (true, Some(input)) = (ready[slot], self.inputs.get_mut(index)) else {
+                continue;
+            };
+            let Some(edges) = observed.edges[slot].as_mut() else {
+                continue;
+            };
+            match input.next_edge(edges) {
+                Ok(edge) => {
+                    observed.inputs_failing[slot] = false;
+                    events.push(Event::Input { index, edge });
+                }
+                Err(error) => {
+                    // Give the line back so a broken request does not keep the wait
+                    // busy; it is requested again at the next poll
+                    if let Some(edges) = observed.edges[slot].take() {
+                        input.unwatch_edges(edges);
+                    }
+                    report_fault(
+                        &mut observed.inputs_failing[slot],
+                        Channel::Input(index),
+                        error,
+                        events,
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Requests edge events on any input without them and reads every watched analog
+    /// input once, appending the crossings and new failures to `events`.
+    fn poll_sources(
+        &mut self,
+        event_loop: &EventLoop,
+        observed: &mut Observed,
+        events: &mut Vec<Event>,
+    ) {
+        for (slot, index) in (0..3).zip(1..) {
+            if observed.edges[slot].is_some() {
+                continue;
+            }
+            let Some(input) = self.inputs.get_mut(index) else {
+                continue;
+            };
+            match input.watch_edges() {
+                Ok(edges) => observed.edges[slot] = Some(edges),
+                Err(error) => report_fault(
+                    &mut observed.inputs_failing[slot],
+                    Channel::Input(index),
+                    error,
+                    events,
+                ),
+            }
+        }
+
+        for (state, threshold) in observed.thresholds.iter_mut().zip(&event_loop.thresholds) {
+            let Some(input) = self.analog_inputs.get_mut(threshold.index) else {
+                continue;
+            };
+            match input.read_voltage() {
+                Ok(volts) => {
+                    state.failing = false;
+                    let above = match state.above {
+                        Some(true) => volts >= threshold.off,
+                        _ => volts > threshold.on,
+                    };
+                    if state
+                        .above
+                        .replace(above)
+                        .is_some_and(|previous| previous != above)
+                    {
+                        events.push(Event::Threshold {
+                            index: threshold.index,
+                            above,
+                            volts,
+                        });
+                    }
+                }
+                Err(error) => report_fault(
+                    &mut state.failing,
+                    Channel::Analog(threshold.index),
+                    error,
+                    events,
+                ),
+            }
+        }
+    }
+}
+
+/// Appends a fault for `channel` unless it is already failing, then marks it failing.
+fn report_fault(failing: &mut bool, channel: Channel, error: HatError, events: &mut Vec<Event>) {
+    if !mem::replace(failing, true) {
+        events.push(Event::Fault { channel, error });
+    }
+}
+
+/// Sleeps until one of the watched inputs has an edge pending or `timeout` elapses,
+/// returning which inputs are ready.
+fn wait_readable(edges: &[Option<LineEventHandle>; 3], timeout: Duration) -> Result<[bool; 3]> {
+    let (slots, mut fds): (Vec<usize>, Vec<PollFd>) = edges
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, watch)| {
+            watch
+                .as_ref()
+                .map(|edges| (slot, PollFd::new(edges, PollFlags::POLLIN)))
+        })
+        .unzip();
+    // Round up so a wait that is not quite due does not spin with a zero timeout
+    let millis = c_int::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(c_int::MAX);
+
+    let mut ready = [false; 3];
+    match poll(&mut fds, millis) {
+        Ok(_) => {}
+        Err(Errno::EINTR) => return Ok(ready),
+        Err(error) => {
+            return Err(HatError::Io(format!(
+                "Failed to wait for input edges: {}",
+                error
+            )));
+        }
+    }
+    for (slot, fd) in slots.into_iter().zip(&fds) {
+        // Errors and hang-ups count as ready so the failed read reports them
+        ready[slot] = fd.revents().is_some_and(|flags| !flags.is_empty());
+    }
+    Ok(ready)
+}
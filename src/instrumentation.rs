@@ -0,0 +1,86 @@
+//! Optional metrics instrumentation for Automation HAT boards.
+//!
+//! When the `metrics` feature is enabled, these helpers emit counters, gauges and
+//! histograms through the [`metrics`](https://docs.rs/metrics) facade so that any
+//! installed recorder (e.g. a Prometheus exporter) can scrape board activity.
+//! When the feature is disabled every helper compiles down to nothing.
+
+use std::time::Instant;
+
+/// Records that a relay changed state.
+///
+/// # Arguments
+///
+/// * `line` - GPIO line offset of the relay that switched
+#[inline]
+pub(crate) fn relay_switched(line: u32) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("automation_hat_relay_switches_total", "line" => line.to_string())
+        .increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = line;
+}
+
+/// Starts timing an analog read.
+///
+/// Returns `None` when the `metrics` feature is disabled so no clock is read.
+#[inline]
+pub(crate) fn analog_read_started() -> Option<Instant> {
+    #[cfg(feature = "metrics")]
+    return Some(Instant::now());
+    #[cfg(not(feature = "metrics"))]
+    None
+}
+
+/// Records the latency of an analog read started with [`analog_read_started`].
+///
+/// # Arguments
+///
+/// * `channel` - The ADC channel that was read
+/// * `started` - The value returned by [`analog_read_started`]
+#[inline]
+pub(crate) fn analog_read_finished(channel: u8, started: Option<Instant>) {
+    #[cfg(feature = "metrics")]
+    if let Some(started) = started {
+        metrics::histogram!("automation_hat_analog_read_seconds", "channel" => channel.to_string())
+            .record(started.elapsed().as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (channel, started);
+}
+
+/// Records that the board could not be made safe when a shutdown signal arrived.
+#[cfg(feature = "signal-hook")]
+#[inline]
+pub(crate) fn safe_shutdown_failed() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("automation_hat_safe_shutdown_failures_total").increment(1);
+}
+
+/// Records a failed I2C transaction.
+///
+/// # Arguments
+///
+/// * `device` - Name of the device on the bus (`"ads1015"` or `"sn3218"`)
+#[inline]
+pub(crate) fn i2c_error(device: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("automation_hat_i2c_errors_total", "device" => device).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = device;
+}
+
+/// Records the current brightness of an LED channel.
+///
+/// # Arguments
+///
+/// * `channel` - The SN3218 channel number (0-17)
+/// * `brightness` - The brightness value (0.0-1.0)
+#[inline]
+pub(crate) fn led_brightness(channel: u8, brightness: f64) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("automation_hat_led_brightness", "channel" => channel.to_string())
+        .set(brightness);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (channel, brightness);
+}
@@ -0,0 +1,204 @@
+//! Region-based screen layouts for the Automation HAT Mini display.
+//!
+//! This module provides `ScreenLayout`, which names rectangular regions of the display
+//! once so that dashboards can redraw a single region at a time. Each update is sent
+//! to the ST7735 as one windowed write covering only that region, which keeps SPI
+//! traffic and flicker down for frequently-updating values.
+
+use crate::display::{DISPLAY_HEIGHT, DISPLAY_WIDTH, Display};
+use crate::error::{HatError, Result};
+
+/// A rectangle of the display, in pixels from the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    /// Left edge
+    pub x: u16,
+    /// Top edge
+    pub y: u16,
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+}
+
+impl Region {
+    /// Returns the number of pixels in the region.
+    pub fn pixels(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Returns whether the region shares any pixel with `other`.
+    fn overlaps(&self, other: &Region) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// A set of named display regions that can be redrawn independently.
+///
+/// Coordinates use the display's default orientation, 80 pixels wide by 160 tall.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, HatType, ScreenLayout};
+///
+/// let mut hat = AutomationHAT::new(HatType::AutomationHATMini);
+/// let layout = ScreenLayout::new()
+///     .region("header", 0, 0, 80, 16)
+///     .region("row1", 0, 20, 80, 40)
+///     .region("row2", 0, 64, 80, 40)
+///     .region("footer", 0, 144, 80, 16);
+/// layout.validate().unwrap();
+///
+/// layout.fill(hat.display_mut().unwrap(), "header", 0x001F).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScreenLayout {
+    /// Regions in the order they were added
+    regions: Vec<(String, Region)>,
+}
+
+impl ScreenLayout {
+    /// Creates a layout with no regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a named region to the layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name used to update the region
+    /// * `x` - Left edge in pixels
+    /// * `y` - Top edge in pixels
+    /// * `width` - Width in pixels
+    /// * `height` - Height in pixels
+    pub fn region(
+        mut self,
+        name: impl Into<String>,
+        x: u16,
+        y: u16,
+        width: u16,
+        height: u16,
+    ) -> Self {
+        self.regions.push((
+            name.into(),
+            Region {
+                x,
+                y,
+                width,
+                height,
+            },
+        ));
+        self
+    }
+
+    /// Returns the region with the given name, if it is part of the layout.
+    pub fn get(&self, name: &str) -> Option<Region> {
+        self.regions
+            .iter()
+            .find(|(region_name, _)| region_name == name)
+            .map(|(_, region)| *region)
+    }
+
+    /// Checks that every region is non-empty, on screen, uniquely named and does not
+    /// overlap another region.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the layout is valid
+    /// * `Err(HatError)` - A `Config` error describing the first problem found
+    pub fn validate(&self) -> Result<()> {
+        for (index, (name, region)) in self.regions.iter().enumerate() {
+            check_bounds(name, region)?;
+            for (other_name, other) in &self.regions[..index] {
+                if other_name == name {
+                    return Err(HatError::Config(format!(
+                        "Region \"{}\" is defined twice",
+                        name
+                    )));
+                }
+                if region.overlaps(other) {
+                    return Err(HatError::Config(format!(
+                        "Region \"{}\" overlaps region \"{}\"",
+                        name, other_name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Redraws one region from a row-major sequence of RGB565 pixels.
+    ///
+    /// Only the region's window is written. `pixels` should yield `width * height`
+    /// values; extra values are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on
+    /// * `name` - The region to redraw
+    /// * `pixels` - The new contents of the region, left to right and top to bottom
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the region was written
+    /// * `Err(HatError)` - If the region is unknown or off screen, or the SPI write failed
+    pub fn draw(
+        &self,
+        display: &mut Display,
+        name: &str,
+        pixels: impl IntoIterator<Item = u16>,
+    ) -> Result<()> {
+        let region = self
+            .get(name)
+            .ok_or_else(|| HatError::Config(format!("Unknown region \"{}\"", name)))?;
+        check_bounds(name, &region)?;
+        display
+            .set_pixels(
+                region.x,
+                region.y,
+                region.x + region.width - 1,
+                region.y + region.height - 1,
+                pixels.into_iter().take(region.pixels()),
+            )
+            .map_err(|error| {
+                HatError::Display(format!("Failed to draw region \"{}\": {:?}", name, error))
+            })
+    }
+
+    /// Fills one region with a single RGB565 color.
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - The display to draw on
+    /// * `name` - The region to fill
+    /// * `color` - The RGB565 color
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the region was written
+    /// * `Err(HatError)` - If the region is unknown or off screen, or the SPI write failed
+    pub fn fill(&self, display: &mut Display, name: &str, color: u16) -> Result<()> {
+        self.draw(display, name, std::iter::repeat(color))
+    }
+}
+
+/// Checks that a region is non-empty and lies entirely on the display.
+fn check_bounds(name: &str, region: &Region) -> Result<()> {
+    if region.width == 0 || region.height == 0 {
+        return Err(HatError::Config(format!("Region \"{}\" is empty", name)));
+    }
+    if region.x as u32 + region.width as u32 > DISPLAY_WIDTH as u32
+        || region.y as u32 + region.height as u32 > DISPLAY_HEIGHT as u32
+    {
+        return Err(HatError::Config(format!(
+            "Region \"{}\" extends past the {}x{} display",
+            name, DISPLAY_WIDTH, DISPLAY_HEIGHT
+        )));
+    }
+    Ok(())
+}
@@ -13,7 +13,8 @@
 //! - Control relays, digital outputs, and read digital/analog inputs
 //! - Full LED control with automatic status indication
 //! - Support for all Automation HAT variants (HAT, pHAT, Mini)
-//! - Display support for Automation HAT Mini
+//! - Display support for Automation HAT Mini (behind the default `display` feature)
+//! - Optional [`metrics`](https://docs.rs/metrics) instrumentation behind the `metrics` feature
 //!
 //! ## Example
 //!
@@ -42,26 +43,91 @@
 //! }
 //! ```
 
+#[cfg(feature = "embedded-hal-02")]
+pub mod adc_hal;
 mod analog_input;
+mod animation;
+mod automation;
+mod calibration;
+mod capabilities;
+mod channel;
+mod complementary;
+mod config;
+#[cfg(feature = "display")]
+mod dashboard;
 mod digital_input;
 mod digital_output;
+#[cfg(feature = "display")]
+mod display;
+mod error;
+mod event_loop;
+mod instrumentation;
+#[cfg(feature = "display")]
+mod layout;
 mod lights;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+pub mod ops;
+pub mod prelude;
+mod readings;
 mod relay;
+mod sampler;
+mod schedule;
+mod self_test;
+#[cfg(feature = "signal-hook")]
+mod shutdown;
+mod supply;
+mod telemetry;
+mod thermal;
+mod thermostat;
+mod worker;
 
-pub use analog_input::AnalogInput;
-pub use digital_input::DigitalInput;
-pub use digital_output::DigitalOutput;
-pub use lights::LED;
-pub use relay::Relay;
+pub use analog_input::{
+    ADC_INPUT_IMPEDANCE_OHMS, Adc, AdcVariant, AnalogInput, AnalogReading, AnalogSnapshot,
+    AnalogStats, LedCurve, OutOfRange,
+};
+pub use animation::{Animation, AnimationEnd};
+pub use automation::Automation;
+pub use calibration::{Calibration, TwoPointCalibration};
+pub use capabilities::Capabilities;
+pub use channel::Channel;
+pub use complementary::ComplementaryPair;
+pub use config::{HatConfig, PinMap};
+#[cfg(feature = "display")]
+pub use dashboard::DisplayDashboard;
+#[cfg(feature = "async")]
+pub use digital_input::EdgeEvent;
+pub use digital_input::{DigitalInput, Edge, InputDelta, PulseCounter};
+pub use digital_output::{DigitalOutput, Polarity};
+#[cfg(feature = "display")]
+pub use display::{Display, Status};
+pub use error::{HatError, Result};
+pub use event_loop::{Event, EventLoop, StopHandle};
+#[cfg(feature = "display")]
+pub use layout::{Region, ScreenLayout};
+pub use lights::{LED, LedFailurePolicy, LedLabel, LedState};
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttBridge, MqttConfig};
+pub use readings::HatReadings;
+pub use relay::{LedConvention, Relay, RelayConfirmation, RelayMode};
+pub use sampler::{AnalogSampler, AnalogWatcher};
+pub use schedule::BrightnessSchedule;
+pub use self_test::{CheckResult, SelfTestReport};
+pub use telemetry::{ChannelMeta, TelemetryConfig, TelemetryFormat, TelemetryLogger};
+pub use thermostat::{Thermostat, ThermostatMode};
 
 use ads1x1x::{Ads1x1x, FullScaleRange, TargetAddr};
+use analog_input::SharedAdc;
+use lights::LedBank;
 use linux_embedded_hal::{
-    CdevPin, I2cdev, SpidevDevice,
-    gpio_cdev::{Chip, LineRequestFlags},
+    I2cdev,
+    gpio_cdev::{self, Chip, Line},
 };
+use relay::{Interlock, SharedInterlock};
 use sn3218_hal::SN3218;
-use st7735_lcd::ST7735;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use worker::Worker;
 
 static RELAY_1: u32 = 13;
 static RELAY_2: u32 = 19;
@@ -75,12 +141,169 @@ static OUTPUT_1: u32 = 5;
 static OUTPUT_2: u32 = 12;
 static OUTPUT_3: u32 = 6;
 
+// Full-scale terminal voltages of the analog inputs. The HAT, pHAT and HAT Mini
+// share the same divider network: inputs 1-3 are buffered 0-25.85V inputs and
+// input 4 is an unbuffered 0-3.3V input.
+static ANALOG_MAX_VALUE: f64 =
+    analog_input::ADC_INPUT_SPAN_VOLTS * analog_input::INPUT_DIVIDER_RATIO;
+static ANALOG_4_MAX_VALUE: f64 = analog_input::ADC_INPUT_SPAN_VOLTS;
+
+// Labels of the GPIO controllers that drive the 40-pin header. On the Raspberry Pi 5
+// the RP1 numbers its header lines 0-27 like the BCM GPIOs, so the same offsets apply.
+static HEADER_GPIO_LABELS: [&str; 3] = ["pinctrl-bcm2835", "pinctrl-bcm2711", "pinctrl-rp1"];
+
+/// Opens the GPIO chip that drives the 40-pin header.
+///
+/// An explicit `path` is opened as-is. Otherwise `/dev/gpiochip0` is used if its label
+/// is a known header controller, then every other chip is probed for such a label,
+/// and `/dev/gpiochip0` is used as a last resort.
+fn open_gpio_chip(path: Option<&str>) -> Result<Chip> {
+    let open = |path: &str| {
+        Chip::new(path)
+            .map_err(|error| HatError::Gpio(format!("Failed to open {}: {}", path, error)))
+    };
+    if let Some(path) = path {
+        return open(path);
+    }
+
+    let default = open("/dev/gpiochip0");
+    if let Ok(chip) = &default
+        && HEADER_GPIO_LABELS.contains(&chip.label())
+    {
+        return default;
+    }
+    if let Ok(chips) = gpio_cdev::chips()
+        && let Some(chip) = chips
+            .flatten()
+            .find(|chip| HEADER_GPIO_LABELS.contains(&chip.label()))
+    {
+        return Ok(chip);
+    }
+    default
+}
+
+/// Looks up a GPIO line on the chip, mapping failures to a `HatError`.
+fn get_line(gpio_chip: &mut Chip, offset: u32) -> Result<Line> {
+    gpio_chip
+        .get_line(offset)
+        .map_err(|error| HatError::Gpio(format!("Failed to get line {}: {}", offset, error)))
+}
+
+// The ADC and the LED driver each get their own `I2cdev`, even when both are on the
+// same bus. That is intentional: every handle is an independent open of the device
+// node, the kernel serializes transfers from all of them, and the two drivers can then
+// be locked separately.
+
+/// Opens the ADC on the I2C bus at `path` and configures it for the board's input
+/// range in continuous mode.
+fn open_adc(variant: AdcVariant, path: &str) -> Result<Adc> {
+    let i2c_analog = I2cdev::new(path).map_err(|error| {
+        HatError::Analog(format!(
+            "Failed to open {}: {} (is I2C enabled?)",
+            path, error
+        ))
+    })?;
+    let range_error = |error| HatError::Analog(format!("Failed to set ADC range: {:?}", error));
+    let mode_error =
+        || HatError::Analog("Failed to convert analog driver into continuous mode".to_string());
+
+    // The range must match analog_input::ADC_FULL_SCALE_VOLTS on either chip
+    match variant {
+        AdcVariant::Ads1015 => {
+            let mut analog_driver = Ads1x1x::new_ads1015(i2c_analog, TargetAddr::default());
+            analog_driver
+                .set_full_scale_range(FullScaleRange::Within4_096V)
+                .map_err(range_error)?;
+            analog_driver
+                .into_continuous()
+                .map(Adc::from)
+                .map_err(|_| mode_error())
+        }
+        AdcVariant::Ads1115 => {
+            let mut analog_driver = Ads1x1x::new_ads1115(i2c_analog, TargetAddr::default());
+            analog_driver
+                .set_full_scale_range(FullScaleRange::Within4_096V)
+                .map_err(range_error)?;
+            analog_driver
+                .into_continuous()
+                .map(Adc::from)
+                .map_err(|_| mode_error())
+        }
+    }
+}
+
+/// Opens the SN3218 LED driver on the I2C bus at `path`.
+fn open_led_driver(path: &str) -> Result<SN3218<I2cdev>> {
+    let i2c_led = I2cdev::new(path).map_err(|error| {
+        HatError::Led(format!(
+            "Failed to open {}: {} (is I2C enabled?)",
+            path, error
+        ))
+    })?;
+    Ok(SN3218::new(i2c_led))
+}
+
+/// Returns which relays are fitted to a board variant; only the full-size HAT fits
+/// relays 1 and 2.
+fn fitted_relays(hat_type: HatType) -> [bool; 3] {
+    match hat_type {
+        HatType::AutomationHAT => [true; 3],
+        HatType::AutomationPHAT | HatType::AutomationHATMini => [false, false, true],
+    }
+}
+
+/// The indicator LED of every channel, indexed by board label minus one.
+#[derive(Default)]
+struct ChannelLeds {
+    /// Normally open and normally closed LEDs of each relay
+    relays: [(Option<LED>, Option<LED>); 3],
+    /// LED of each digital input
+    inputs: [Option<LED>; 3],
+    /// LED of each digital output
+    outputs: [Option<LED>; 3],
+    /// LED of analog inputs 1-3; input 4 has none
+    analog: [Option<LED>; 3],
+}
+
+impl ChannelLeds {
+    /// Creates the channel LEDs driven by `bank`, or none for boards without one.
+    fn new(bank: Option<&Arc<LedBank>>) -> Self {
+        let Some(bank) = bank else {
+            return Self::default();
+        };
+        let led = |label: LedLabel| Some(LED::new(bank.driver().clone(), label.channel()));
+        ChannelLeds {
+            relays: [
+                (led(LedLabel::Relay1No), led(LedLabel::Relay1Nc)),
+                (led(LedLabel::Relay2No), led(LedLabel::Relay2Nc)),
+                (led(LedLabel::Relay3No), led(LedLabel::Relay3Nc)),
+            ],
+            inputs: [
+                led(LedLabel::Input1),
+                led(LedLabel::Input2),
+                led(LedLabel::Input3),
+            ],
+            outputs: [
+                led(LedLabel::Output1),
+                led(LedLabel::Output2),
+                led(LedLabel::Output3),
+            ],
+            analog: [
+                led(LedLabel::Analog1),
+                led(LedLabel::Analog2),
+                led(LedLabel::Analog3),
+            ],
+        }
+    }
+}
+
 /// Represents the type of Automation HAT hardware being used.
 ///
 /// Different HAT types have different capabilities:
 /// - `AutomationHAT`: Full-size HAT with 3 relays, LEDs for all I/O
 /// - `AutomationPHAT`: Smaller pHAT form factor with fewer features
 /// - `AutomationHATMini`: Mini form factor with LCD display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HatType {
     /// Full-sized Automation HAT with 3 relays and status LEDs for all I/O
     AutomationHAT,
@@ -93,8 +316,12 @@ pub enum HatType {
 /// Container for relay controls on the Automation HAT.
 ///
 /// Provides access to the relays on the Automation HAT:
-/// - `one` and `two` are optional as they're not present on all HAT variants
+/// - `one` and `two` are only fitted on the full-size HAT
 /// - `three` is available on all HAT variants
+///
+/// Use `try_write` in code that must work across variants; it reports relays that
+/// are not fitted instead of silently driving an unconnected line. Relays that must
+/// never be on together can be interlocked with `set_interlock`.
 pub struct Relays {
     /// Relay 1 - Only present on full HAT
     pub one: Relay,
@@ -102,6 +329,14 @@ pub struct Relays {
     pub two: Relay,
     /// Relay 3 - Present on all HAT variants
     pub three: Relay,
+    /// Whether each relay is fitted on this board variant
+    present: [bool; 3],
+    /// Board labels of the interlocked relays, empty if there is no interlock
+    interlock: Vec<usize>,
+    /// The interlock group shared with its member relays
+    interlock_group: Option<SharedInterlock>,
+    /// Dead time applied to the interlock group
+    interlock_dead_time: Duration,
 }
 
 impl Relays {
@@ -113,7 +348,190 @@ impl Relays {
     /// * `two` - Relay 2 instance (present on HAT)
     /// * `three` - Relay 3 instance (present on all variants)
     pub fn new(one: Relay, two: Relay, three: Relay) -> Self {
-        Relays { one, two, three }
+        Relays {
+            one,
+            two,
+            three,
+            present: [true; 3],
+            interlock: Vec::new(),
+            interlock_group: None,
+            interlock_dead_time: Duration::ZERO,
+        }
+    }
+
+    /// Returns a mutable reference to the relay with the given board label.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The relay number as printed on the board (1-3)
+    ///
+    /// # Returns
+    ///
+    /// The matching `Relay`, or `None` if `index` is out of range
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Relay> {
+        match index {
+            1 => Some(&mut self.one),
+            2 => Some(&mut self.two),
+            3 => Some(&mut self.three),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the relay with the given board label is fitted on this board.
+    ///
+    /// Relays 1 and 2 are only fitted on the full-size Automation HAT.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The relay number as printed on the board (1-3)
+    pub fn is_present(&self, index: usize) -> bool {
+        index
+            .checked_sub(1)
+            .and_then(|slot| self.present.get(slot))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    /// Switches the relay with the given board label, checking it exists on this board.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The relay number as printed on the board (1-3)
+    /// * `state` - The desired relay state (true = activated, false = deactivated)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the relay was switched
+    /// * `Err(HatError::InvalidChannel)` - If `index` is out of range
+    /// * `Err(HatError::NotPresent)` - If the relay is not fitted on this board variant
+    /// * `Err(HatError::Gpio)` - If the relay could not be switched
+    pub fn try_write(&mut self, index: usize, state: bool) -> Result<()> {
+        if !(1..=3).contains(&index) {
+            return Err(HatError::InvalidChannel(index));
+        }
+        if !self.is_present(index) {
+            return Err(HatError::NotPresent(format!("Relay {}", index)));
+        }
+        let relay = self.get_mut(index).ok_or(HatError::InvalidChannel(index))?;
+        relay.write(state)
+    }
+
+    /// Interlocks a group of relays so that no two of them are ever energized together.
+    ///
+    /// Once interlocked, activating any relay in the group first switches off every
+    /// other energized member, waits out the dead time (see
+    /// `set_interlock_dead_time`), and only then energizes the requested relay. This
+    /// is enforced inside `Relay::write`, so it also holds for writes made directly on
+    /// `one`, `two` or `three`. Use it for forward/reverse motor contactors and similar
+    /// loads where overlap would short the supply.
+    ///
+    /// A board has at most one group; setting a new one replaces it. The interlock is
+    /// a software guard; wire the contactors' auxiliary contacts as a hardware
+    /// interlock too where overlap is dangerous.
+    ///
+    /// # Arguments
+    ///
+    /// * `relays` - The board labels (1-3) of at least two relays
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the group was set up
+    /// * `Err(HatError::Config)` - If fewer than two distinct relays are given
+    /// * `Err(HatError::InvalidChannel)` - If a label is out of range
+    /// * `Err(HatError::NotPresent)` - If a relay is not fitted on this board variant
+    /// * `Err(HatError::Refused)` - If more than one of the relays is currently
+    ///   energized, so the interlock could not be satisfied
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::time::Duration;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.relays.set_interlock(&[1, 2])?;
+    /// hat.relays.set_interlock_dead_time(Duration::from_millis(50));
+    /// hat.relays.one.write(true).unwrap(); // forward
+    /// hat.relays.two.write(true).unwrap(); // relay 1 drops out first, then reverse
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn set_interlock(&mut self, relays: &[usize]) -> Result<()> {
+        let mut group: Vec<usize> = Vec::with_capacity(relays.len());
+        for &index in relays {
+            Channel::Relay(index).validate()?;
+            if !self.is_present(index) {
+                return Err(HatError::NotPresent(format!("Relay {}", index)));
+            }
+            if !group.contains(&index) {
+                group.push(index);
+            }
+        }
+        if group.len() < 2 {
+            return Err(HatError::Config(
+                "An interlock needs at least two different relays".to_string(),
+            ));
+        }
+        let mut energized = group.iter().filter(|index| {
+            self.get_mut(**index)
+                .is_some_and(|relay| relay.is_energized())
+        });
+        if let (Some(first), Some(second)) = (energized.next(), energized.next()) {
+            return Err(HatError::Refused(format!(
+                "Relays {} and {} are both energized; switch one off before interlocking them",
+                first, second
+            )));
+        }
+
+        self.clear_interlock();
+        let members = group
+            .iter()
+            .filter_map(|index| self.get_mut(*index).map(|relay| relay.off_switch()))
+            .collect();
+        let interlock = Interlock::new(members, self.interlock_dead_time);
+        for &index in &group {
+            if let Some(relay) = self.get_mut(index) {
+                relay.set_interlock(Some(interlock.clone()));
+            }
+        }
+        self.interlock = group;
+        self.interlock_group = Some(interlock);
+        Ok(())
+    }
+
+    /// Sets how long an interlocked relay waits after switching another member of its
+    /// group off before energizing.
+    ///
+    /// Contactors take tens of milliseconds to drop out and quench their arc, so a
+    /// dead time of 50-100ms is typical for motor reversing. The default is zero.
+    /// Applies to the current group and any group set later.
+    ///
+    /// # Arguments
+    ///
+    /// * `dead_time` - The pause between one relay switching off and the next energizing
+    pub fn set_interlock_dead_time(&mut self, dead_time: Duration) {
+        self.interlock_dead_time = dead_time;
+        if let Some(interlock) = &self.interlock_group {
+            interlock
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .set_dead_time(dead_time);
+        }
+    }
+
+    /// Removes the interlock, leaving every relay in its current state.
+    pub fn clear_interlock(&mut self) {
+        for index in std::mem::take(&mut self.interlock) {
+            if let Some(relay) = self.get_mut(index) {
+                relay.set_interlock(None);
+            }
+        }
+        self.interlock_group = None;
+    }
+
+    /// Returns the board labels of the interlocked relays, or an empty slice if there
+    /// is no interlock.
+    pub fn interlock(&self) -> &[usize] {
+        &self.interlock
     }
 }
 
@@ -127,6 +545,8 @@ pub struct Inputs {
     pub two: DigitalInput,
     /// Digital Input 3
     pub three: DigitalInput,
+    /// Input mask seen by the previous `poll_changes`, `None` before the first poll
+    previous: Option<u8>,
 }
 
 impl Inputs {
@@ -138,7 +558,58 @@ impl Inputs {
     /// * `two` - Digital Input 2 instance
     /// * `three` - Digital Input 3 instance
     pub fn new(one: DigitalInput, two: DigitalInput, three: DigitalInput) -> Self {
-        Inputs { one, two, three }
+        Inputs {
+            one,
+            two,
+            three,
+            previous: None,
+        }
+    }
+
+    /// Returns a mutable reference to the digital input with the given board label.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The input number as printed on the board (1-3)
+    ///
+    /// # Returns
+    ///
+    /// The matching `DigitalInput`, or `None` if `index` is out of range
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut DigitalInput> {
+        match index {
+            1 => Some(&mut self.one),
+            2 => Some(&mut self.two),
+            3 => Some(&mut self.three),
+            _ => None,
+        }
+    }
+
+    /// Reads all three inputs and reports what changed since the previous call.
+    ///
+    /// The first call establishes the baseline and reports no rising or falling
+    /// inputs. Changes that revert between two polls are not seen, so poll at least
+    /// as often as the shortest pulse you need to catch.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(InputDelta)` - The inputs that rose, fell, and are currently high
+    /// * `Err(HatError)` - If any input could not be read; the baseline is left unchanged
+    pub fn poll_changes(&mut self) -> Result<InputDelta> {
+        let mut current = 0u8;
+        for (bit, input) in [&mut self.one, &mut self.two, &mut self.three]
+            .into_iter()
+            .enumerate()
+        {
+            if input.read()? {
+                current |= 1 << bit;
+            }
+        }
+        let previous = self.previous.replace(current).unwrap_or(current);
+        Ok(InputDelta {
+            rising: current & !previous,
+            falling: previous & !current,
+            current,
+        })
     }
 }
 
@@ -165,11 +636,101 @@ impl Outputs {
     pub fn new(one: DigitalOutput, two: DigitalOutput, three: DigitalOutput) -> Self {
         Outputs { one, two, three }
     }
+
+    /// Returns a mutable reference to the digital output with the given board label.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The output number as printed on the board (1-3)
+    ///
+    /// # Returns
+    ///
+    /// The matching `DigitalOutput`, or `None` if `index` is out of range
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut DigitalOutput> {
+        match index {
+            1 => Some(&mut self.one),
+            2 => Some(&mut self.two),
+            3 => Some(&mut self.three),
+            _ => None,
+        }
+    }
+
+    /// Sets all three outputs in one call.
+    ///
+    /// The GPIO lines are driven back to back in board order, then the indicator
+    /// LEDs of all three outputs are flushed to the SN3218 in a single write, so the
+    /// LEDs change together after all pins have changed. Useful for parallel output
+    /// such as a 3-bit code.
+    ///
+    /// # Arguments
+    ///
+    /// * `states` - The desired state of outputs 1, 2 and 3
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every output and LED was updated
+    /// * `Err(HatError::Gpio)` - If a line could not be driven; earlier outputs keep
+    ///   their new state and no LEDs are updated
+    /// * `Err(HatError::Led)` - If the LED driver could not be updated
+    pub fn write_all(&mut self, states: [bool; 3]) -> Result<()> {
+        let mut leds = Vec::new();
+        for (output, on) in [&mut self.one, &mut self.two, &mut self.three]
+            .into_iter()
+            .zip(states)
+        {
+            output.write_pin(on)?;
+            if let Some(led) = output.auto_led_mut() {
+                leds.push((led, on));
+            }
+        }
+
+        let Some(driver) = leds.first().map(|(led, _)| led.bank().clone()) else {
+            return Ok(());
+        };
+        let updates: Vec<(u8, u8)> = leds
+            .into_iter()
+            .map(|(led, on)| led.stage(if on { 1.0 } else { 0.0 }))
+            .collect();
+        lights::write_frame(&driver, &updates)
+    }
+
+    /// Sets all three outputs from a bitmask, as `write_all` does.
+    ///
+    /// # Arguments
+    ///
+    /// * `mask` - Bit 0 sets output 1, bit 1 output 2 and bit 2 output 3
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every output and LED was updated
+    /// * `Err(HatError::Config)` - If a bit above bit 2 is set; no output is changed
+    /// * `Err(HatError)` - If an output or LED could not be updated, as for `write_all`
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // Outputs 1 and 3 on, output 2 off
+    /// hat.outputs.from_mask(0b101)?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn from_mask(&mut self, mask: u8) -> Result<()> {
+        if mask > 0b111 {
+            return Err(HatError::Config(format!(
+                "Output mask {:#010b} sets bits beyond output 3",
+                mask
+            )));
+        }
+        self.write_all([mask & 0b001 != 0, mask & 0b010 != 0, mask & 0b100 != 0])
+    }
 }
 
 /// Container for analog input controls on the Automation HAT.
 ///
-/// Provides access to the three analog inputs available on all HAT variants.
+/// Provides access to the three buffered 0-25.85V analog inputs available on all
+/// HAT variants, plus the fourth ADC channel which measures 0-3.3V.
 pub struct AnalogInputs {
     /// Analog Input 1
     pub one: AnalogInput,
@@ -177,6 +738,8 @@ pub struct AnalogInputs {
     pub two: AnalogInput,
     /// Analog Input 3
     pub three: AnalogInput,
+    /// Analog Input 4 (0-3.3V range)
+    pub four: AnalogInput,
 }
 
 impl AnalogInputs {
@@ -187,8 +750,158 @@ impl AnalogInputs {
     /// * `one` - Analog Input 1 instance
     /// * `two` - Analog Input 2 instance
     /// * `three` - Analog Input 3 instance
-    pub fn new(one: AnalogInput, two: AnalogInput, three: AnalogInput) -> Self {
-        AnalogInputs { one, two, three }
+    /// * `four` - Analog Input 4 instance
+    pub fn new(one: AnalogInput, two: AnalogInput, three: AnalogInput, four: AnalogInput) -> Self {
+        AnalogInputs {
+            one,
+            two,
+            three,
+            four,
+        }
+    }
+
+    /// Returns a mutable reference to the analog input with the given board label.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The analog input number as printed on the board (1-4)
+    ///
+    /// # Returns
+    ///
+    /// The matching `AnalogInput`, or `None` if `index` is out of range
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut AnalogInput> {
+        match index {
+            1 => Some(&mut self.one),
+            2 => Some(&mut self.two),
+            3 => Some(&mut self.three),
+            4 => Some(&mut self.four),
+            _ => None,
+        }
+    }
+
+    /// Programs the ADS1015's hardware comparator to watch one channel.
+    ///
+    /// The comparator asserts the ADC's ALERT/RDY pin when the channel's terminal
+    /// voltage rises above `high_volts`, and releases it once the voltage falls below
+    /// `low_volts`, without any polling by the CPU.
+    ///
+    /// # Routing caveats
+    ///
+    /// The ADS1015 has a single comparator, which always follows the channel the
+    /// input multiplexer currently points at. Reading any other analog input (or
+    /// running an `AnalogSampler`) moves the multiplexer, so the comparator only
+    /// watches `channel` while no other channel is read.
+    ///
+    /// The published pinouts of the Automation HAT, pHAT and HAT Mini do not route
+    /// ALERT/RDY to a Raspberry Pi GPIO, so this crate does not watch it. The
+    /// comparator is only useful on a board modified to wire ALERT/RDY to a spare
+    /// GPIO, which can then be watched with `gpio_cdev` edge events.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The analog input number as printed on the board (1-4)
+    /// * `low_volts` - Terminal voltage below which the alert is released
+    /// * `high_volts` - Terminal voltage above which the alert is asserted
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the comparator was configured
+    /// * `Err(HatError)` - If the channel is invalid, the thresholds are reversed, or the
+    ///   ADC is unavailable or could not be configured
+    pub fn set_comparator(
+        &mut self,
+        channel: usize,
+        low_volts: f64,
+        high_volts: f64,
+    ) -> Result<()> {
+        if low_volts > high_volts {
+            return Err(HatError::Config(format!(
+                "Comparator low threshold {}V is above high threshold {}V",
+                low_volts, high_volts
+            )));
+        }
+        self.get_mut(channel)
+            .ok_or(HatError::InvalidChannel(channel))?
+            .set_comparator(low_volts, high_volts)
+    }
+
+    /// Reads every available analog input under a single lock of the ADC.
+    ///
+    /// All channels are converted in turn with no other reader able to interleave, and
+    /// the result carries one capture timestamp, taken as the first conversion starts,
+    /// making it suitable for data logging. In continuous mode each channel waits for
+    /// the ADC to settle on it, so four channels take about 6 ms on the ADS1015 and
+    /// 70 ms on the ADS1115. Each input's `value` is updated; LEDs are left untouched.
+    /// A running `AnalogSampler` is bypassed so the readings are fresh.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AnalogSnapshot)` - The readings, with `None` for inputs without an ADC
+    /// * `Err(HatError)` - If any conversion failed
+    pub fn snapshot(&mut self) -> Result<AnalogSnapshot> {
+        let mut channels = [None; 4];
+        let Some(driver) = self.one.driver().cloned() else {
+            return Ok(AnalogSnapshot {
+                timestamp: SystemTime::now(),
+                channels,
+            });
+        };
+
+        let mut driver = driver.lock().unwrap();
+        let timestamp = SystemTime::now();
+        for (index, slot) in channels.iter_mut().enumerate() {
+            if let Some(input) = self.get_mut(index + 1)
+                && input.is_available()
+            {
+                *slot = Some(input.sample_locked(&mut driver)?);
+            }
+        }
+
+        Ok(AnalogSnapshot {
+            timestamp,
+            channels,
+        })
+    }
+
+    /// Switches the ADS1015 between continuous and one-shot conversion.
+    ///
+    /// The ADC starts in continuous mode, where it converts all the time and each
+    /// read simply fetches the latest result. Reading a different channel from the
+    /// last read first moves the multiplexer and waits two conversion periods for a
+    /// result of the new channel (about 1.4 ms on the ADS1015, 17 ms on the ADS1115),
+    /// so alternating between channels is slower than reading one. In one-shot mode it
+    /// powers down between conversions and each read starts a conversion and waits for
+    /// it (under 1 ms at the default data rate), trading a little latency and extra
+    /// I2C traffic for lower idle power. All four inputs share the ADC, so the mode
+    /// applies to every channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `continuous` - `true` for continuous conversion, `false` for one-shot
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the ADC is now in the requested mode
+    /// * `Err(HatError)` - If analog support is disabled or the mode change failed, in
+    ///   which case the previous mode is kept
+    pub fn set_continuous(&mut self, continuous: bool) -> Result<()> {
+        self.one.set_continuous(continuous)
+    }
+
+    /// Returns whether the ADS1015 is converting continuously, or `None` if analog
+    /// support is disabled.
+    pub fn is_continuous(&self) -> Option<bool> {
+        self.one.is_continuous()
+    }
+
+    /// Disables the ADS1015's hardware comparator.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the comparator was disabled or there is no ADC
+    /// * `Err(HatError)` - If the ADC could not be configured
+    pub fn clear_comparator(&mut self) -> Result<()> {
+        self.one.clear_comparator()
     }
 }
 
@@ -212,7 +925,19 @@ pub struct AutomationHAT {
     /// Access to analog input controls
     pub analog_inputs: AnalogInputs,
     /// Access to the ST7735 display (only available on Automation HAT Mini)
-    pub display: Option<ST7735<SpidevDevice, CdevPin, CdevPin>>,
+    #[cfg(feature = "display")]
+    pub display: Option<Display>,
+    /// Shared SN3218 LED driver (only present on boards with LEDs)
+    led_driver: Option<Arc<LedBank>>,
+    /// Shared ADS1015 driver (absent when analog inputs are disabled)
+    analog_driver: Option<SharedAdc>,
+    /// Configuration the board was built with, for re-opening its peripherals
+    config: HatConfig,
+    /// Why the ADC could not be initialized, when `analog_strict` allowed construction
+    /// to continue without it
+    analog_init_error: Option<HatError>,
+    /// Background task applying the LED brightness schedule, if one is set
+    brightness_schedule: Option<Worker>,
 }
 
 impl AutomationHAT {
@@ -231,169 +956,819 @@ impl AutomationHAT {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```rust,no_run
     /// use automation_hat::{AutomationHAT, HatType};
     ///
     /// // Create a new AutomationHAT instance
     /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
     /// ```
     pub fn new(hat_type: HatType) -> Self {
-        let i2c_analog = I2cdev::new("/dev/i2c-1").unwrap();
-        let mut analog_driver = Ads1x1x::new_ads1015(i2c_analog, TargetAddr::default());
+        Self::with_config(hat_type, HatConfig::default())
+    }
 
-        analog_driver
-            .set_full_scale_range(FullScaleRange::Within2_048V)
-            .unwrap();
+    /// Creates a new AutomationHAT instance, returning an error instead of panicking
+    /// if any part of the board cannot be initialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat_type` - The type of Automation HAT to initialize
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AutomationHAT)` - A fully configured instance ready for use
+    /// * `Err(HatError)` - If a bus, GPIO line or the display could not be set up
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// match AutomationHAT::try_new(HatType::AutomationHATMini) {
+    ///     Ok(hat) => println!("Board ready"),
+    ///     Err(error) => eprintln!("{}", error),
+    /// }
+    /// ```
+    pub fn try_new(hat_type: HatType) -> Result<Self> {
+        Self::try_with_config(hat_type, HatConfig::default())
+    }
+
+    /// Creates a new AutomationHAT instance, initializing only the subsystems
+    /// enabled in `config`.
+    ///
+    /// Disabled subsystems are never opened: with analog disabled every analog
+    /// input returns an error when read, and with the display disabled the
+    /// `display` field is `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat_type` - The type of Automation HAT to initialize
+    /// * `config` - Which subsystems to initialize
+    ///
+    /// # Returns
+    ///
+    /// An `AutomationHAT` instance with the requested subsystems configured
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatConfig, HatType};
+    ///
+    /// let config = HatConfig::new().enable_analog(false).enable_display(false);
+    /// let mut hat = AutomationHAT::with_config(HatType::AutomationHATMini, config);
+    /// ```
+    pub fn with_config(hat_type: HatType, config: HatConfig) -> Self {
+        Self::try_with_config(hat_type, config).unwrap()
+    }
 
-        let analog_driver = match analog_driver.into_continuous() {
-            Ok(driver) => Arc::new(Mutex::new(driver)),
-            Err(_) => panic!("Failed to convert analog driver into continuous mode"),
+    /// Creates a new AutomationHAT instance with the given configuration, returning
+    /// an error instead of panicking if any enabled subsystem cannot be initialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat_type` - The type of Automation HAT to initialize
+    /// * `config` - Which subsystems to initialize
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AutomationHAT)` - An instance with the requested subsystems configured
+    /// * `Err(HatError::PinConflict)` - If two roles in the pin map share a GPIO line,
+    ///   or one is used by the Mini's display
+    /// * `Err(HatError)` - If a bus, GPIO line or the display could not be set up
+    pub fn try_with_config(hat_type: HatType, config: HatConfig) -> Result<Self> {
+        let pins = config.pins;
+        match config.display {
+            true => pins.validate_for(hat_type)?,
+            false => pins.validate()?,
+        }
+
+        let mut analog_init_error = None;
+        let analog_driver = match config.analog {
+            true => match open_adc(config.adc_variant, &config.adc_i2c_path) {
+                Ok(adc) => Some(Arc::new(Mutex::new(adc))),
+                Err(error) if !config.analog_strict => {
+                    analog_init_error = Some(error);
+                    None
+                }
+                Err(error) => return Err(error),
+            },
+            false => None,
         };
 
-        let mut gpio_chip = Chip::new("/dev/gpiochip0").unwrap();
+        let mut gpio_chip = open_gpio_chip(config.gpio_chip.as_deref())?;
 
         // For AutomationHATMini, disable auto-lighting since there are no LEDs
         let auto_light = !matches!(hat_type, HatType::AutomationHATMini);
 
-        let mut relay_1_no_led = None;
-        let mut relay_1_nc_led = None;
-        let mut relay_2_no_led = None;
-        let mut relay_2_nc_led = None;
-        let mut relay_3_no_led = None;
-        let mut relay_3_nc_led = None;
-        let mut input_1_led = None;
-        let mut input_2_led = None;
-        let mut input_3_led = None;
-        let mut output_1_led = None;
-        let mut output_2_led = None;
-        let mut output_3_led = None;
-        let mut analog_input_1_led = None;
-        let mut analog_input_2_led = None;
-        let mut analog_input_3_led = None;
-        let mut display = None;
-
-        match hat_type {
-            HatType::AutomationHAT => {
-                let i2c_led = I2cdev::new("/dev/i2c-1").unwrap();
-                let driver = Arc::new(Mutex::new(SN3218::new(i2c_led)));
-
-                analog_input_1_led = Some(LED::new(driver.clone(), 0));
-                analog_input_2_led = Some(LED::new(driver.clone(), 1));
-                analog_input_3_led = Some(LED::new(driver.clone(), 2));
-
-                output_1_led = Some(LED::new(driver.clone(), 3));
-                output_2_led = Some(LED::new(driver.clone(), 4));
-                output_3_led = Some(LED::new(driver.clone(), 5));
-
-                input_1_led = Some(LED::new(driver.clone(), 14));
-                input_2_led = Some(LED::new(driver.clone(), 13));
-                input_3_led = Some(LED::new(driver.clone(), 12));
-
-                relay_1_no_led = Some(LED::new(driver.clone(), 6));
-                relay_1_nc_led = Some(LED::new(driver.clone(), 7));
-                relay_2_no_led = Some(LED::new(driver.clone(), 8));
-                relay_2_nc_led = Some(LED::new(driver.clone(), 9));
-                relay_3_no_led = Some(LED::new(driver.clone(), 10));
-                relay_3_nc_led = Some(LED::new(driver.clone(), 11));
-            }
-            HatType::AutomationPHAT => {}
-            HatType::AutomationHATMini => {
-                let dc = gpio_chip.get_line(9).unwrap();
-                let dc = dc
-                    .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
-                    .unwrap();
-                let dc = CdevPin::new(dc).unwrap();
-
-                let rst = gpio_chip.get_line(22).unwrap();
-                let rst = rst
-                    .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
-                    .unwrap();
-                let rst = CdevPin::new(rst).unwrap();
-                display = Some(ST7735::new(
-                    SpidevDevice::open("/dev/spidev0.1").unwrap(),
-                    dc,
-                    rst,
-                    false,
-                    true,
-                    80,
-                    160,
-                ));
-
-                if let Some(ref mut disp) = display {
-                    let mut delay = linux_embedded_hal::Delay {};
-                    disp.init(&mut delay).unwrap();
-                    disp.set_offset(26, 2);
-                }
-            }
-        }
+        let led_driver = match hat_type {
+            HatType::AutomationHAT => Some(LedBank::of(Arc::new(Mutex::new(open_led_driver(
+                &config.led_i2c_path,
+            )?)))),
+            HatType::AutomationPHAT | HatType::AutomationHATMini => None,
+        };
+        let ChannelLeds {
+            relays:
+                [
+                    (relay_1_no_led, relay_1_nc_led),
+                    (relay_2_no_led, relay_2_nc_led),
+                    (relay_3_no_led, relay_3_nc_led),
+                ],
+            inputs: [input_1_led, input_2_led, input_3_led],
+            outputs: [output_1_led, output_2_led, output_3_led],
+            analog: [analog_input_1_led, analog_input_2_led, analog_input_3_led],
+        } = ChannelLeds::new(led_driver.as_ref());
 
-        let relay_1 = Relay::new_with_auto_light(
-            gpio_chip.get_line(RELAY_1).unwrap(),
+        #[cfg(feature = "display")]
+        let display = match hat_type {
+            HatType::AutomationHATMini if config.display => Some(display::init(&mut gpio_chip)?),
+            _ => None,
+        };
+
+        let relay_1 = Relay::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.relay_1)?,
             relay_1_no_led,
             relay_1_nc_led,
             auto_light,
-        );
+        )?;
 
-        let relay_2 = Relay::new_with_auto_light(
-            gpio_chip.get_line(RELAY_2).unwrap(),
+        let relay_2 = Relay::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.relay_2)?,
             relay_2_no_led,
             relay_2_nc_led,
             auto_light,
-        );
+        )?;
 
-        let relay_3 = Relay::new_with_auto_light(
-            gpio_chip.get_line(RELAY_3).unwrap(),
+        let relay_3 = Relay::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.relay_3)?,
             relay_3_no_led,
             relay_3_nc_led,
             auto_light,
-        );
+        )?;
 
-        let input_1 = DigitalInput::new_with_auto_light(
-            gpio_chip.get_line(INPUT_1).unwrap(),
+        let input_1 = DigitalInput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.input_1)?,
             input_1_led,
             auto_light,
-        );
-        let input_2 = DigitalInput::new_with_auto_light(
-            gpio_chip.get_line(INPUT_2).unwrap(),
+        )?;
+        let input_2 = DigitalInput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.input_2)?,
             input_2_led,
             auto_light,
-        );
-        let input_3 = DigitalInput::new_with_auto_light(
-            gpio_chip.get_line(INPUT_3).unwrap(),
+        )?;
+        let input_3 = DigitalInput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.input_3)?,
             input_3_led,
             auto_light,
-        );
-        let output_1 = DigitalOutput::new_with_auto_light(
-            gpio_chip.get_line(OUTPUT_1).unwrap(),
+        )?;
+        let output_1 = DigitalOutput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.output_1)?,
             output_1_led,
             auto_light,
-        );
-        let output_2 = DigitalOutput::new_with_auto_light(
-            gpio_chip.get_line(OUTPUT_2).unwrap(),
+        )?;
+        let output_2 = DigitalOutput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.output_2)?,
             output_2_led,
             auto_light,
-        );
-        let output_3 = DigitalOutput::new_with_auto_light(
-            gpio_chip.get_line(OUTPUT_3).unwrap(),
+        )?;
+        let output_3 = DigitalOutput::try_new_with_auto_light(
+            get_line(&mut gpio_chip, pins.output_3)?,
             output_3_led,
             auto_light,
-        );
-        let analog_input_1 = AnalogInput::new(analog_driver.clone(), analog_input_1_led, 0);
-        let analog_input_2 = AnalogInput::new(analog_driver.clone(), analog_input_2_led, 1);
-        let analog_input_3 = AnalogInput::new(analog_driver.clone(), analog_input_3_led, 2);
+        )?;
+        let analog_input = |led, channel: u8| {
+            let max_value = config.analog_max_values[channel as usize];
+            match &analog_driver {
+                Some(driver) => {
+                    AnalogInput::new_with_max_value(driver.clone(), led, channel, max_value)
+                }
+                None => {
+                    let mut input = AnalogInput::disabled(led, channel);
+                    input.max_value = max_value;
+                    input
+                }
+            }
+        };
+        let analog_input_1 = analog_input(analog_input_1_led, 0);
+        let analog_input_2 = analog_input(analog_input_2_led, 1);
+        let analog_input_3 = analog_input(analog_input_3_led, 2);
+        let analog_input_4 = analog_input(None, 3);
 
-        let analog_inputs = AnalogInputs::new(analog_input_1, analog_input_2, analog_input_3);
+        let analog_inputs = AnalogInputs::new(
+            analog_input_1,
+            analog_input_2,
+            analog_input_3,
+            analog_input_4,
+        );
         let inputs = Inputs::new(input_1, input_2, input_3);
         let outputs = Outputs::new(output_1, output_2, output_3);
-        let relays = Relays::new(relay_1, relay_2, relay_3);
+        let mut relays = Relays::new(relay_1, relay_2, relay_3);
+        relays.present = fitted_relays(hat_type);
 
-        Self {
+        Ok(Self {
+            analog_driver,
+            analog_init_error,
             analog_inputs,
+            brightness_schedule: None,
+            #[cfg(feature = "display")]
             display,
             hat_type,
             inputs,
+            config,
+            led_driver,
             outputs,
             relays,
+        })
+    }
+
+    /// Returns why the ADC could not be initialized, if the board was built with
+    /// `HatConfig::analog_strict(false)` and carried on without it.
+    ///
+    /// The analog inputs of such a board are unavailable and fail every read;
+    /// `reinitialize_i2c` does not retry the ADC. Construct the board again to retry.
+    ///
+    /// # Returns
+    ///
+    /// * `None` - If the ADC was initialized, or analog was disabled
+    /// * `Some(&HatError)` - The failure that left the analog inputs unavailable
+    pub fn analog_init_error(&self) -> Option<&HatError> {
+        self.analog_init_error.as_ref()
+    }
+
+    /// Switches every relay and digital output off.
+    ///
+    /// This is the safe state for the board: all loads are de-energized. Every
+    /// relay and output is attempted even if an earlier one fails.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every relay and output was switched off
+    /// * `Err(HatError)` - The first failure encountered
+    pub fn all_off(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for relay in [
+            &mut self.relays.one,
+            &mut self.relays.two,
+            &mut self.relays.three,
+        ] {
+            if let Err(error) = relay.write(false) {
+                result = result.and(Err(error));
+            }
+        }
+        for output in [
+            &mut self.outputs.one,
+            &mut self.outputs.two,
+            &mut self.outputs.three,
+        ] {
+            if let Err(error) = output.write(false) {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
+
+    /// Compares a relay's commanded state with its contact feedback input.
+    ///
+    /// The relay must have been associated with a digital input using
+    /// `Relay::with_feedback`; see there for the wiring. A relay takes around 10ms to
+    /// actuate, so allow it to settle after `write` before confirming, either by
+    /// waiting or with `Relay::set_settle_time`.
+    ///
+    /// # Arguments
+    ///
+    /// * `relay` - The relay number as printed on the board (1-3)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(RelayConfirmation)` - The commanded and sensed states; check `is_confirmed()`
+    /// * `Err(HatError)` - If the relay does not exist, has no feedback input, or the
+    ///   input could not be read
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::{thread, time::Duration};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.relays.one.with_feedback(1)?;
+    /// hat.relays.one.write(true).unwrap();
+    /// thread::sleep(Duration::from_millis(20));
+    /// if !hat.confirmed_state(1)?.is_confirmed() {
+    ///     eprintln!("Relay 1 did not switch");
+    /// }
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn confirmed_state(&mut self, relay: usize) -> Result<RelayConfirmation> {
+        let relay_ref = self
+            .relays
+            .get_mut(relay)
+            .ok_or(HatError::InvalidChannel(relay))?;
+        let commanded = relay_ref.is_energized();
+        let input = relay_ref
+            .feedback_input()
+            .ok_or_else(|| HatError::Config(format!("Relay {} has no feedback input", relay)))?;
+        let sensed = self
+            .inputs
+            .get_mut(input)
+            .ok_or(HatError::InvalidChannel(input))?
+            .read()?;
+        Ok(RelayConfirmation { commanded, sensed })
+    }
+
+    /// Returns the SN3218 LED driver, for the board-level LED methods.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(&Arc<LedBank>)` - The driver and its state
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs, with the reason given by
+    ///   `auto_light_reason`
+    pub(crate) fn leds(&self) -> Result<&Arc<LedBank>> {
+        self.led_driver.as_ref().ok_or_else(|| {
+            HatError::NoLeds(
+                self.auto_light_reason()
+                    .unwrap_or("The SN3218 LED driver was not initialized")
+                    .to_string(),
+            )
+        })
+    }
+
+    /// Lights a group of LEDs as a bar graph showing `level`.
+    ///
+    /// The LEDs are treated as equal segments of a bar, in the order given. Segments
+    /// fully below the level are lit at full brightness, the segment containing the
+    /// level is dimmed proportionally, and the rest are turned off. All LEDs are
+    /// updated with a single write to the LED driver to avoid flicker.
+    ///
+    /// # Arguments
+    ///
+    /// * `leds` - The LEDs making up the bar, from lowest to highest
+    /// * `level` - The level to display, from 0.0 (empty) to 1.0 (full); values outside
+    ///   this range are clamped
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the bar was displayed
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError::Led)` - If the LED driver could not be updated
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType, LedLabel};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let level = hat.analog_inputs.one.read()?;
+    /// hat.set_bar(&[LedLabel::Analog1, LedLabel::Analog2, LedLabel::Analog3], level)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_bar(&mut self, leds: &[LedLabel], level: f64) -> Result<()> {
+        let driver = self.leds()?;
+
+        let fill = level.clamp(0.0, 1.0) * leds.len() as f64;
+        let updates: Vec<(u8, u8)> = leds
+            .iter()
+            .enumerate()
+            .map(|(index, led)| {
+                let brightness = (fill - index as f64).clamp(0.0, 1.0);
+                (led.channel(), (brightness * 255.0) as u8)
+            })
+            .collect();
+
+        lights::write_frame(driver, &updates)
+    }
+
+    /// Sets every LED on the board to the same brightness with a single write.
+    ///
+    /// Useful as a quick lamp test (`1.0`) or to blank the board at shutdown (`0.0`).
+    /// Auto-lit LEDs are overwritten until their channel next updates them.
+    ///
+    /// # Arguments
+    ///
+    /// * `brightness` - A value between 0.0 (off) and 1.0 (full brightness)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every LED was updated
+    /// * `Err(HatError::InvalidBrightness)` - If the brightness is NaN or out of range
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError::Led)` - If the LED driver could not be updated
+    pub fn set_all_leds(&mut self, brightness: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&brightness) {
+            return Err(HatError::InvalidBrightness(brightness));
+        }
+        let driver = self.leds()?;
+
+        let value = (brightness * 255.0) as u8;
+        let updates: Vec<(u8, u8)> = LedLabel::ALL
+            .iter()
+            .map(|led| (led.channel(), value))
+            .collect();
+
+        lights::write_frame(driver, &updates)
+    }
+
+    /// Sets a global scale factor applied to the brightness of every LED.
+    ///
+    /// The scale is applied when the LED frame is written to the SN3218, on top of
+    /// each LED's own brightness, which is left unchanged: `LED::brightness` still
+    /// reports the unscaled value, and restoring a scale of 1.0 brings every LED back
+    /// to it. This is the dimming control used by `set_brightness_schedule`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - The factor from 0.0 (all dark) to 1.0 (unscaled)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the scaled frame was written
+    /// * `Err(HatError::Config)` - If the scale is NaN or outside 0.0-1.0
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError)` - If the LED driver could not be updated
+    pub fn set_led_scale(&mut self, scale: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&scale) {
+            return Err(HatError::Config(
+                "LED scale must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        let driver = self.leds()?;
+        lights::set_master_scale(scale);
+        lights::write_frame(driver, &[])
+    }
+
+    /// Returns the global scale factor applied to the brightness of every LED.
+    pub fn led_scale(&self) -> f64 {
+        lights::master_scale()
+    }
+
+    /// Sets what the crate's LED state does when a write to the SN3218 fails.
+    ///
+    /// The SN3218 is write-only, so after a failed write the crate cannot tell which
+    /// channels the chip took. Either way, the next write re-asserts every channel
+    /// instead of skipping an apparently unchanged frame. With the default
+    /// `LedFailurePolicy::Reassert` the failed values stay recorded and appear on the
+    /// next successful write; with `LedFailurePolicy::Rollback` they are discarded,
+    /// so `LED::brightness` and the tracked levels only reflect writes that succeeded.
+    /// The policy applies to every board in the process.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The recovery behavior for failed writes
+    pub fn set_led_failure_policy(&mut self, policy: LedFailurePolicy) {
+        lights::set_failure_policy(policy);
+    }
+
+    /// Returns what the crate's LED state does when a write to the SN3218 fails.
+    pub fn led_failure_policy(&self) -> LedFailurePolicy {
+        lights::failure_policy()
+    }
+
+    /// Limits how often LED changes are written to the SN3218.
+    ///
+    /// Animations and auto-lit channels can change LEDs far faster than anyone can
+    /// see, and every change is an I2C write on the bus shared with the ADC. With a
+    /// limit set, LED changes only update the crate's record of each channel, and a
+    /// background thread writes the latest frame at most `fps` times per second, so
+    /// rapid changes are coalesced and the last value of each channel wins. LED
+    /// methods then report success without waiting for the bus, and write failures
+    /// are only counted by the instrumentation. The limit applies to every board in
+    /// the process.
+    ///
+    /// A limit of 0, the default, writes every change immediately; setting it flushes
+    /// any waiting frame first. `refresh_leds` always writes immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `fps` - Most frames written per second, or 0 for immediate writes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the limit is in effect
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError)` - If the flusher thread could not be started, or the waiting
+    ///   frame could not be written when switching back to immediate writes
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// // Write the LEDs at most 30 times per second
+    /// hat.set_led_max_fps(30).unwrap();
+    /// ```
+    pub fn set_led_max_fps(&mut self, fps: u32) -> Result<()> {
+        self.leds()?;
+        lights::set_max_fps(fps)
+    }
+
+    /// Returns the frame rate limit of LED writes, 0 if every change is written
+    /// immediately.
+    pub fn led_max_fps(&self) -> u32 {
+        lights::max_fps()
+    }
+
+    /// Stops every running LED animation.
+    ///
+    /// Signals all background animation threads (such as those started by
+    /// [`LED::blink`]) to stop, waits for each of them to exit, and then leaves
+    /// the animated LEDs in the requested state.
+    ///
+    /// # Arguments
+    ///
+    /// * `end` - The state to leave each animated LED in
+    pub fn stop_all_animations(&mut self, end: AnimationEnd) {
+        animation::stop_all(end);
+    }
+
+    /// Re-asserts the tracked state of every LED on the SN3218.
+    ///
+    /// The SN3218 is write-only, so the crate cannot read back what the chip is
+    /// showing; it keeps its own record of every channel instead. If the chip is reset
+    /// by a supply glitch or another process, or misses a write on a noisy bus, its
+    /// registers no longer match that record, and LEDs go dark or show stale values.
+    /// This takes the chip out of shutdown and writes every channel from the record,
+    /// with the global LED scale applied, whether or not it appears to have changed.
+    /// Re-asserting is the only way to recover; existing `LED`s keep working and do
+    /// not need to be recreated. `reinitialize_i2c` calls this after re-opening the
+    /// driver.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every channel was written
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError::Led)` - If the driver did not respond
+    pub fn refresh_leds(&mut self) -> Result<()> {
+        let driver = self.leds()?;
+        driver
+            .driver()
+            .lock()
+            .unwrap()
+            .enable()
+            .map_err(|error| HatError::Led(format!("Failed to enable LEDs: {:?}", error)))?;
+        lights::rewrite_frame(driver)
+    }
+
+    /// Captures the crate's logical LED state, for restoring after a restart.
+    ///
+    /// The levels are this board's; the LED scale is shared by every board in the
+    /// process.
+    ///
+    /// # Returns
+    ///
+    /// The tracked value of every channel, all 0 on boards without LEDs, and the
+    /// global LED scale
+    pub fn led_state(&self) -> LedState {
+        LedState {
+            levels: self
+                .led_driver
+                .as_ref()
+                .map_or([0; 18], |bank| bank.levels()),
+            scale: lights::master_scale(),
+        }
+    }
+
+    /// Returns the tracked 0-255 value of every SN3218 channel, keyed by channel (0-17).
+    ///
+    /// This is the crate's logical LED state, before the global LED scale is applied,
+    /// so it can be inspected without reading from the write-only chip. It lets tests
+    /// check the indication logic, e.g. that writing Relay 1 on set channel 6 (its NO
+    /// LED) to 255 and channel 7 (its NC LED) to 0. Only available with the `testing`
+    /// feature.
+    ///
+    /// # Returns
+    ///
+    /// The value of every channel, or an empty map on boards without LEDs
+    #[cfg(any(test, feature = "testing"))]
+    pub fn dump_led_state(&self) -> std::collections::HashMap<u8, u8> {
+        self.led_driver
+            .iter()
+            .flat_map(|bank| bank.levels().into_iter().enumerate())
+            .map(|(channel, level)| (channel as u8, level))
+            .collect()
+    }
+
+    /// Loads a saved LED state and writes it to the SN3218.
+    ///
+    /// A restarted process starts with every LED tracked as off, while the chip still
+    /// shows what the previous process left on it. Restoring the state saved by that
+    /// process with `led_state` brings the crate's record back in line, and writes it
+    /// to the chip with `refresh_leds`, so both agree again.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state to load, as returned by `led_state`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the state was loaded and written
+    /// * `Err(HatError::Config)` - If the saved scale is NaN or outside 0.0-1.0
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError)` - If the LED driver could not be updated
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let saved = hat.led_state();
+    /// // ... persist `saved` (it serializes with the `serde` feature) and restart ...
+    /// hat.restore_led_state(&saved).unwrap();
+    /// ```
+    pub fn restore_led_state(&mut self, state: &LedState) -> Result<()> {
+        if !(0.0..=1.0).contains(&state.scale) {
+            return Err(HatError::Config(
+                "LED scale must be between 0.0 and 1.0".to_string(),
+            ));
+        }
+        self.leds()?.set_levels(&state.levels);
+        lights::set_master_scale(state.scale);
+        self.refresh_leds()
+    }
+
+    /// Checks whether the SN3218 LED driver responds on the I2C bus.
+    ///
+    /// LED writes that succeed only show that the bus accepted them when a frame rate
+    /// limit is set, and an LED at zero brightness looks the same as a dead chip. This
+    /// performs a write the chip must acknowledge and that leaves the LEDs as they
+    /// are, so diagnostics can tell a missing or unpowered SN3218 from LEDs that are
+    /// simply off.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the chip acknowledged; `false` if it did not, or the board has no
+    /// LED driver
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// if !hat.led_driver_present() {
+    ///     eprintln!("SN3218 is not responding; check the HAT is seated");
+    /// }
+    /// ```
+    pub fn led_driver_present(&mut self) -> bool {
+        self.led_driver
+            .as_ref()
+            .is_some_and(|driver| lights::probe(driver).is_ok())
+    }
+
+    /// Re-opens the I2C peripherals after a bus fault.
+    ///
+    /// The ADC is re-opened as the same chip and configured for the board's range and
+    /// its current conversion mode, and the SN3218 is re-opened and its LEDs restored
+    /// with `refresh_leds`.
+    /// GPIO lines are left untouched, so relays and outputs keep their state.
+    /// Peripherals that were disabled, or that failed under `analog_strict(false)`, at
+    /// construction stay disabled.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every enabled I2C peripheral was brought back up
+    /// * `Err(HatError)` - If the bus could not be opened or a device did not respond
+    pub fn reinitialize_i2c(&mut self) -> Result<()> {
+        if let Some(adc) = &self.analog_driver {
+            // A reader may have panicked mid-fault; the fresh driver makes the lock usable again
+            let mut adc_guard = adc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut fresh = open_adc(adc_guard.variant(), &self.config.adc_i2c_path)?;
+            if !adc_guard.is_continuous() {
+                fresh.set_continuous(false)?;
+            }
+            *adc_guard = fresh;
+            drop(adc_guard);
+            adc.clear_poison();
+        }
+        if let Some(bank) = &self.led_driver {
+            let fresh = open_led_driver(&self.config.led_i2c_path)?;
+            let driver = bank.driver();
+            *driver
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = fresh;
+            driver.clear_poison();
+            self.refresh_leds()?;
+        }
+        Ok(())
+    }
+
+    /// Switches the board to another HAT variant without re-opening its GPIO lines.
+    ///
+    /// Use this when the fitted board is only known after start-up, for example when
+    /// it is probed or read from a settings file, rather than dropping the
+    /// `AutomationHAT` and building a new one. The LED driver is opened when moving
+    /// to the full-size HAT, and the display (if enabled in the `HatConfig`) when
+    /// moving to the Mini; both are opened before anything else changes, so a failure
+    /// leaves the board as it was. When moving away from the full-size HAT, every LED
+    /// is switched off and LED animations and the brightness schedule are stopped.
+    ///
+    /// Relays, outputs and inputs keep their lines and state, and their LEDs are
+    /// attached or detached to match the new variant. Relays 1 and 2 are reported as
+    /// absent on the pHAT and Mini; an interlock including either of them is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_type` - The variant to switch to
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the board now matches `new_type`
+    /// * `Err(HatError::PinConflict)` - If moving to the Mini and the pin map uses one
+    ///   of the display's lines
+    /// * `Err(HatError)` - If the LED driver or the display could not be set up
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::try_new(HatType::AutomationPHAT).unwrap();
+    /// hat.reconfigure(HatType::AutomationHAT).unwrap();
+    /// hat.relays.one.write(true).unwrap();
+    /// ```
+    pub fn reconfigure(&mut self, new_type: HatType) -> Result<()> {
+        if new_type == self.hat_type {
+            return Ok(());
+        }
+
+        let led_driver = match (new_type, &self.led_driver) {
+            (HatType::AutomationHAT, Some(driver)) => Some(driver.clone()),
+            (HatType::AutomationHAT, None) => Some(LedBank::of(Arc::new(Mutex::new(
+                open_led_driver(&self.config.led_i2c_path)?,
+            )))),
+            (HatType::AutomationPHAT | HatType::AutomationHATMini, _) => None,
+        };
+        #[cfg(feature = "display")]
+        let display = match new_type {
+            HatType::AutomationHATMini if self.config.display => {
+                self.config.pins.validate_for(new_type)?;
+                let mut gpio_chip = open_gpio_chip(self.config.gpio_chip.as_deref())?;
+                Some(display::init(&mut gpio_chip)?)
+            }
+            _ => None,
+        };
+
+        if led_driver.is_none()
+            && let Some(driver) = self.led_driver.take()
+        {
+            self.brightness_schedule = None;
+            animation::stop_all(AnimationEnd::Off);
+            let blank: Vec<(u8, u8)> = (0..18).map(|channel| (channel, 0)).collect();
+            // The LEDs are being detached either way; a dark board is best effort
+            let _ = lights::write_frame(&driver, &blank);
+        }
+
+        let auto_light = !matches!(new_type, HatType::AutomationHATMini);
+        let leds = ChannelLeds::new(led_driver.as_ref());
+        let [(one_no, one_nc), (two_no, two_nc), (three_no, three_nc)] = leds.relays;
+        self.relays.one.replace_leds(one_no, one_nc, auto_light);
+        self.relays.two.replace_leds(two_no, two_nc, auto_light);
+        self.relays
+            .three
+            .replace_leds(three_no, three_nc, auto_light);
+        let [one, two, three] = leds.inputs;
+        self.inputs.one.replace_led(one, auto_light);
+        self.inputs.two.replace_led(two, auto_light);
+        self.inputs.three.replace_led(three, auto_light);
+        let [one, two, three] = leds.outputs;
+        self.outputs.one.replace_led(one, auto_light);
+        self.outputs.two.replace_led(two, auto_light);
+        self.outputs.three.replace_led(three, auto_light);
+        let [one, two, three] = leds.analog;
+        self.analog_inputs.one.replace_led(one);
+        self.analog_inputs.two.replace_led(two);
+        self.analog_inputs.three.replace_led(three);
+
+        let present = fitted_relays(new_type);
+        if self
+            .relays
+            .interlock()
+            .iter()
+            .any(|&index| !present[index - 1])
+        {
+            self.relays.clear_interlock();
+        }
+        self.relays.present = present;
+        self.led_driver = led_driver;
+        #[cfg(feature = "display")]
+        {
+            self.display = display;
+        }
+        self.hat_type = new_type;
+
+        if self.led_driver.is_some() {
+            self.refresh_leds()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AutomationHAT {
+    /// Switches every relay and digital output off if the board was configured with
+    /// `HatConfig::all_off_on_drop`; otherwise they keep their last level.
+    fn drop(&mut self) {
+        if self.config.all_off_on_drop {
+            // Nothing can report a failure here; `all_off` still tries every channel
+            let _ = self.all_off();
         }
     }
 }
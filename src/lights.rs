@@ -2,14 +2,479 @@
 //!
 //! This module provides the `LED` struct, which represents a single LED on the Automation HAT.
 //! Each LED has a brightness level that can be controlled from 0.0 to 1.0.
+//!
+//! # Driver configuration
+//!
+//! The SN3218 has no PWM frequency or gamma registers to configure: its register map
+//! is limited to software shutdown, the 18 PWM duty values, the channel enable bits,
+//! an update latch and a reset. The PWM frequency is fixed by the chip's internal
+//! oscillator, so flicker seen on camera cannot be tuned away in software, and
+//! brightness values are written to the duty registers linearly, without gamma
+//! correction.
+
+use crate::animation::{self, Animation, AnimationEnd};
+use crate::error::HatError;
+use crate::instrumentation;
 
 use linux_embedded_hal::I2cdev;
 use sn3218_hal::SN3218;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Interval between brightness updates of async animations (50 frames per second)
+#[cfg(feature = "async")]
+const ASYNC_FRAME: Duration = Duration::from_millis(20);
+
+// Global scale factor applied to every channel when a frame is flushed, 255 = full
+// brightness. The per-channel state above stays unscaled so the scale can be changed
+// without losing it.
+static MASTER_SCALE: AtomicU8 = AtomicU8::new(255);
+
+// Every live LED bank, so that LEDs created on the same driver share one
+static BANKS: Mutex<Vec<Weak<LedBank>>> = Mutex::new(Vec::new());
+
+// Source of flash numbers
+static NEXT_FLASH: AtomicU64 = AtomicU64::new(0);
+
+// Most frames flushed to the SN3218 per second, 0 to flush every update immediately.
+// Only changed while `PENDING_FLUSH` is locked, so no update is left behind when
+// rate limiting is switched off.
+static MAX_FPS: AtomicU32 = AtomicU32::new(0);
+
+// The banks with updates waiting for the next rate-limited flush, empty if every
+// SN3218 is up to date. The updates themselves are already in each bank's levels, so
+// coalescing keeps only the latest value of each channel.
+static PENDING_FLUSH: Mutex<Vec<Arc<LedBank>>> = Mutex::new(Vec::new());
+
+// The background thread flushing rate-limited frames, while rate limiting is on
+static FLUSHER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+// Whether a failed write restores the previous values of the channels it was setting,
+// i.e. `LedFailurePolicy::Rollback`
+static ROLLBACK_ON_FAILURE: AtomicBool = AtomicBool::new(false);
+
+/// An SN3218 LED driver together with the crate's record of what it shows.
+///
+/// There is one bank per driver: every `LED` created on a driver, by the board or
+/// with `LED::new`, shares its bank, while separate boards get their own.
+pub(crate) struct LedBank {
+    /// The driver; its mutex also serializes flushing frames to it
+    driver: Arc<Mutex<SN3218<I2cdev>>>,
+    /// Tracked 0-255 value of each channel. Each channel is updated without locking;
+    /// only flushing a frame to the chip is serialized, by the driver's mutex.
+    levels: [AtomicU8; 18],
+    /// Flashes still lit, per channel: the number of the latest flash, which is the
+    /// one that restores the channel, and the value to restore. Overlapping flashes
+    /// share the value from before the first of them.
+    flashes: Mutex<[Option<(u64, u8)>; 18]>,
+    /// The last frame successfully written to the chip, used to skip redundant writes.
+    /// Only updated while the driver mutex is held. Cleared when a write fails, since
+    /// the chip may then hold any mix of old and new values.
+    last_frame: Mutex<Option<[u8; 18]>>,
+}
+
+impl LedBank {
+    /// Returns the bank of `driver`, creating it if no LED uses the driver yet.
+    pub(crate) fn of(driver: Arc<Mutex<SN3218<I2cdev>>>) -> Arc<LedBank> {
+        let mut banks = BANKS.lock().unwrap();
+        banks.retain(|bank| bank.strong_count() > 0);
+        // A live bank holds its driver, so the pointer cannot have been reused
+        if let Some(bank) = banks
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|bank| Arc::ptr_eq(&bank.driver, &driver))
+        {
+            return bank;
+        }
+        let bank = Arc::new(LedBank {
+            driver,
+            levels: [const { AtomicU8::new(0) }; 18],
+            flashes: Mutex::new([None; 18]),
+            last_frame: Mutex::new(None),
+        });
+        banks.push(Arc::downgrade(&bank));
+        bank
+    }
 
-// Shared global state to track LED brightness values across the system
-static LED_STATE: OnceLock<Mutex<HashMap<u8, u8>>> = OnceLock::new();
+    /// Returns the SN3218 driver of this bank.
+    pub(crate) fn driver(&self) -> &Arc<Mutex<SN3218<I2cdev>>> {
+        &self.driver
+    }
+
+    /// Returns the tracked 0-255 value of every channel, indexed by channel.
+    pub(crate) fn levels(&self) -> [u8; 18] {
+        std::array::from_fn(|channel| self.levels[channel].load(Ordering::Relaxed))
+    }
+
+    /// Replaces the tracked value of every channel without writing to the chip.
+    pub(crate) fn set_levels(&self, levels: &[u8; 18]) {
+        for (level, value) in self.levels.iter().zip(levels) {
+            level.store(*value, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Stores `updates` of channel and value in `levels` without locking or allocating.
+///
+/// # Returns
+///
+/// For each channel updated, the value now stored and the value it had before the
+/// first of its updates, indexed by channel; channels beyond 17 are skipped
+fn store_levels(levels: &[AtomicU8; 18], updates: &[(u8, u8)]) -> [Option<(u8, u8)>; 18] {
+    let mut replaced = [None; 18];
+    for (channel, value) in updates {
+        if let Some(level) = levels.get(*channel as usize) {
+            let previous = level.swap(*value, Ordering::Relaxed);
+            let slot = &mut replaced[*channel as usize];
+            *slot = Some((*value, slot.map_or(previous, |(_, first)| first)));
+        }
+    }
+    replaced
+}
+
+/// What the crate's LED state does when writing a frame to the SN3218 fails.
+///
+/// A failed write can leave the chip with any mix of old and new values, so with
+/// either policy the next frame is written in full rather than skipped as unchanged,
+/// re-asserting every channel. The policies differ in what the crate records for the
+/// channels the failed write was setting. Set with `AutomationHAT::set_led_failure_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LedFailurePolicy {
+    /// Keep the new values: the next successful write shows them, so the LEDs catch
+    /// up with the intended state once the bus recovers
+    #[default]
+    Reassert,
+    /// Restore the previous values of the channels being set, and the `brightness`
+    /// of the `LED` being set, so the state only reflects writes that succeeded. A
+    /// channel changed again by another writer in the meantime keeps that change.
+    Rollback,
+}
+
+/// Names the LEDs on the full-size Automation HAT by their board label.
+///
+/// Each label maps onto a fixed channel of the SN3218 LED driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedLabel {
+    /// Analog Input 1 indicator
+    Analog1,
+    /// Analog Input 2 indicator
+    Analog2,
+    /// Analog Input 3 indicator
+    Analog3,
+    /// Digital Output 1 indicator
+    Output1,
+    /// Digital Output 2 indicator
+    Output2,
+    /// Digital Output 3 indicator
+    Output3,
+    /// Relay 1 normally open indicator
+    Relay1No,
+    /// Relay 1 normally closed indicator
+    Relay1Nc,
+    /// Relay 2 normally open indicator
+    Relay2No,
+    /// Relay 2 normally closed indicator
+    Relay2Nc,
+    /// Relay 3 normally open indicator
+    Relay3No,
+    /// Relay 3 normally closed indicator
+    Relay3Nc,
+    /// Digital Input 3 indicator
+    Input3,
+    /// Digital Input 2 indicator
+    Input2,
+    /// Digital Input 1 indicator
+    Input1,
+    /// Warning indicator
+    Warn,
+    /// Communications indicator
+    Comms,
+    /// Power indicator
+    Power,
+}
+
+impl LedLabel {
+    /// Every LED label, ordered by SN3218 channel.
+    pub const ALL: [LedLabel; 18] = [
+        LedLabel::Analog1,
+        LedLabel::Analog2,
+        LedLabel::Analog3,
+        LedLabel::Output1,
+        LedLabel::Output2,
+        LedLabel::Output3,
+        LedLabel::Relay1No,
+        LedLabel::Relay1Nc,
+        LedLabel::Relay2No,
+        LedLabel::Relay2Nc,
+        LedLabel::Relay3No,
+        LedLabel::Relay3Nc,
+        LedLabel::Input3,
+        LedLabel::Input2,
+        LedLabel::Input1,
+        LedLabel::Warn,
+        LedLabel::Comms,
+        LedLabel::Power,
+    ];
+
+    /// Returns the SN3218 channel (0-17) driving this LED.
+    pub fn channel(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// Updates several LED channels and writes the resulting frame to the SN3218 once.
+///
+/// If the resulting frame is identical to the last one written, no I2C traffic is
+/// generated. While a frame rate limit is set (see `set_max_fps`), the frame is left
+/// for the background flusher instead and this always succeeds; write failures are
+/// then only counted by the instrumentation, and never rolled back. If the write
+/// fails, the tracked state is kept or rolled back according to the
+/// `LedFailurePolicy`.
+///
+/// # Arguments
+///
+/// * `bank` - The SN3218 LED driver and its state
+/// * `updates` - Pairs of channel number (0-17) and hardware brightness (0-255)
+///
+/// # Returns
+///
+/// * `Ok(())` - If the frame was written
+/// * `Err(HatError::Led)` - If communication with the LED driver failed
+pub(crate) fn write_frame(bank: &Arc<LedBank>, updates: &[(u8, u8)]) -> crate::Result<()> {
+    // Update the state for each channel, remembering the values replaced
+    let replaced = store_levels(&bank.levels, updates);
+
+    {
+        let mut pending = PENDING_FLUSH.lock().unwrap();
+        if MAX_FPS.load(Ordering::Relaxed) > 0 {
+            if !pending.iter().any(|waiting| Arc::ptr_eq(waiting, bank)) {
+                pending.push(bank.clone());
+            }
+            for (channel, value) in updates {
+                instrumentation::led_brightness(*channel, *value as f64 / 255.0);
+            }
+            return Ok(());
+        }
+    }
+    if let Err(error) = flush_frame(bank) {
+        if ROLLBACK_ON_FAILURE.load(Ordering::Relaxed) {
+            for (level, stored) in bank.levels.iter().zip(replaced) {
+                if let Some((value, previous)) = stored {
+                    let _ = level.compare_exchange(
+                        value,
+                        previous,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    );
+                }
+            }
+        }
+        return Err(error);
+    }
+    for (channel, value) in updates {
+        instrumentation::led_brightness(*channel, *value as f64 / 255.0);
+    }
+    Ok(())
+}
+
+/// Returns the frame showing `levels` at the global `scale` (255 = full brightness),
+/// or `None` if it matches `last_frame` so writing it would change nothing.
+fn next_frame(levels: [u8; 18], scale: u8, last_frame: Option<[u8; 18]>) -> Option<[u8; 18]> {
+    let frame = levels.map(|level| (level as u16 * scale as u16 / 255) as u8);
+    (last_frame != Some(frame)).then_some(frame)
+}
+
+/// Returns the SN3218 channel enable bits for the lit channels of `frame`.
+fn enable_mask(frame: &[u8; 18]) -> u32 {
+    frame
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value > 0)
+        .fold(0, |mask, (channel, _)| mask | 1 << channel)
+}
+
+/// Writes the tracked state of every channel to the SN3218, unless it matches the
+/// last frame written.
+fn flush_frame(bank: &LedBank) -> crate::Result<()> {
+    // Snapshot every channel under the driver lock, so the flushed frame includes
+    // any updates other threads stored before this flush
+    let mut driver = bank.driver.lock().unwrap();
+    let levels = bank.levels();
+    let mut last_frame = bank.last_frame.lock().unwrap();
+    let Some(values) = next_frame(levels, MASTER_SCALE.load(Ordering::Relaxed), *last_frame) else {
+        return Ok(());
+    };
+
+    let written = driver
+        .enable_leds(enable_mask(&values))
+        .map_err(|error| HatError::Led(format!("Failed to enable LEDs: {:?}", error)))
+        .and_then(|_| {
+            driver
+                .output(&values)
+                .map_err(|error| HatError::Led(format!("Failed to write LED values: {:?}", error)))
+        });
+    match written {
+        Ok(()) => *last_frame = Some(values),
+        Err(_) => {
+            instrumentation::i2c_error("sn3218");
+            // The chip may hold part of this frame, so the next one must not be skipped
+            *last_frame = None;
+        }
+    }
+    written
+}
+
+/// Sets what the tracked LED state does when writing a frame fails.
+pub(crate) fn set_failure_policy(policy: LedFailurePolicy) {
+    ROLLBACK_ON_FAILURE.store(policy == LedFailurePolicy::Rollback, Ordering::Relaxed);
+}
+
+/// Returns what the tracked LED state does when writing a frame fails.
+pub(crate) fn failure_policy() -> LedFailurePolicy {
+    match ROLLBACK_ON_FAILURE.load(Ordering::Relaxed) {
+        true => LedFailurePolicy::Rollback,
+        false => LedFailurePolicy::Reassert,
+    }
+}
+
+/// Writes the full tracked LED state to the SN3218, even if it matches the last frame.
+///
+/// Used after the driver has been re-opened, when the chip's registers can no longer
+/// be assumed to hold the last frame written. The frame is written immediately, even
+/// while a frame rate limit is set.
+///
+/// # Arguments
+///
+/// * `bank` - The SN3218 LED driver and its state
+///
+/// # Returns
+///
+/// * `Ok(())` - If the frame was written
+/// * `Err(HatError::Led)` - If communication with the LED driver failed
+pub(crate) fn rewrite_frame(bank: &LedBank) -> crate::Result<()> {
+    *bank.last_frame.lock().unwrap() = None;
+    flush_frame(bank)
+}
+
+/// Checks that the SN3218 acknowledges a write, without changing what it shows.
+///
+/// The chip is write-only, so presence can only be probed by writing; this rewrites
+/// the shutdown register to normal operation, which every frame written by the crate
+/// already assumes.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the chip acknowledged the write
+/// * `Err(HatError::Led)` - If the write was not acknowledged
+pub(crate) fn probe(bank: &LedBank) -> crate::Result<()> {
+    bank.driver
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .enable()
+        .map_err(|error| {
+            instrumentation::i2c_error("sn3218");
+            HatError::Led(format!("LED driver did not respond: {:?}", error))
+        })
+}
+
+/// Limits how often LED updates are flushed to the SN3218, or writes every update
+/// immediately when `fps` is 0.
+///
+/// While limited, updates only change the tracked state and a background thread
+/// writes the latest frame at most `fps` times per second. Switching back to
+/// immediate mode flushes any waiting frame before returning.
+///
+/// # Returns
+///
+/// * `Ok(())` - If the limit is in effect
+/// * `Err(HatError::Io)` - If the flusher thread could not be started
+pub(crate) fn set_max_fps(fps: u32) -> crate::Result<()> {
+    let mut flusher = FLUSHER.lock().unwrap();
+    if fps > 0 {
+        MAX_FPS.store(fps, Ordering::Relaxed);
+        if flusher.is_none() {
+            let thread = thread::Builder::new()
+                .name("automation-hat-led-flusher".to_string())
+                .spawn(flush_loop)
+                .map_err(|error| {
+                    MAX_FPS.store(0, Ordering::Relaxed);
+                    HatError::Io(error.to_string())
+                })?;
+            *flusher = Some(thread);
+        }
+        return Ok(());
+    }
+
+    let pending = {
+        let mut pending = PENDING_FLUSH.lock().unwrap();
+        MAX_FPS.store(0, Ordering::Relaxed);
+        std::mem::take(&mut *pending)
+    };
+    if let Some(thread) = flusher.take() {
+        thread.thread().unpark();
+        let _ = thread.join();
+    }
+    // Every bank is flushed even if an earlier one fails; the first failure is reported
+    pending
+        .iter()
+        .map(|bank| flush_frame(bank))
+        .fold(Ok(()), Result::and)
+}
+
+/// Returns the frame rate limit of LED updates, 0 if every update is written immediately.
+pub(crate) fn max_fps() -> u32 {
+    MAX_FPS.load(Ordering::Relaxed)
+}
+
+/// Body of the flusher thread: writes the waiting frame once per frame period until
+/// rate limiting is switched off.
+fn flush_loop() {
+    loop {
+        let fps = MAX_FPS.load(Ordering::Relaxed);
+        if fps == 0 {
+            break;
+        }
+        thread::park_timeout(Duration::from_secs_f64(1.0 / fps as f64));
+        let pending = std::mem::take(&mut *PENDING_FLUSH.lock().unwrap());
+        for bank in pending {
+            // Failures are counted by the instrumentation; the next update retries
+            let _ = flush_frame(&bank);
+        }
+    }
+}
+
+/// Sets the global scale factor applied to every LED, from 0.0 to 1.0.
+///
+/// Takes effect on the next frame written; callers flush with `write_frame(driver, &[])`.
+pub(crate) fn set_master_scale(scale: f64) {
+    MASTER_SCALE.store(
+        (scale.clamp(0.0, 1.0) * 255.0).round() as u8,
+        Ordering::Relaxed,
+    );
+}
+
+/// Returns the global scale factor applied to every LED.
+pub(crate) fn master_scale() -> f64 {
+    MASTER_SCALE.load(Ordering::Relaxed) as f64 / 255.0
+}
+
+/// The crate's logical LED state, captured by `AutomationHAT::led_state` so it can
+/// be saved and re-asserted by `AutomationHAT::restore_led_state` after a restart.
+///
+/// The SN3218 keeps showing its last values when the process exits, but it is
+/// write-only, so a new process cannot read them back; saving this state is the
+/// only way to carry the indication across. It serializes with the `serde` feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LedState {
+    /// Tracked 0-255 value of each SN3218 channel, indexed by channel, before the
+    /// global LED scale is applied
+    pub levels: [u8; 18],
+    /// Global LED scale from 0.0 to 1.0 (see `AutomationHAT::set_led_scale`)
+    pub scale: f64,
+}
 
 /// Represents a single LED on the Automation HAT.
 ///
@@ -17,8 +482,8 @@ static LED_STATE: OnceLock<Mutex<HashMap<u8, u8>>> = OnceLock::new();
 /// or set to a specific brightness level. LEDs are controlled through the SN3218 LED driver
 /// chip which supports 18 channels with 255 brightness levels each.
 pub struct LED {
-    /// Reference to the SN3218 LED driver
-    driver: Arc<Mutex<SN3218<I2cdev>>>,
+    /// The SN3218 LED driver and its state, shared with every LED on the driver
+    bank: Arc<LedBank>,
     /// Channel number on the SN3218 (0-17)
     channel: u8,
     /// Current brightness value (0.0-1.0)
@@ -39,23 +504,59 @@ impl LED {
     ///
     /// A new `LED` instance initialized to off (brightness 0.0)
     pub fn new(driver: Arc<Mutex<SN3218<I2cdev>>>, channel: u8) -> Self {
-        // Initialize global LED state if not already done
-        LED_STATE.get_or_init(|| Mutex::new(HashMap::new()));
-
         LED {
-            driver,
+            bank: LedBank::of(driver),
             channel,
             brightness: 0.0,
             max_brightness: 255,
         }
     }
 
+    /// Returns the tracked 0-255 value of this LED's channel.
+    ///
+    /// Reflects the most recent write from any `LED` or board method sharing the
+    /// channel, before the global LED scale is applied. Only available with the
+    /// `testing` feature.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn current_hardware_value(&self) -> u8 {
+        self.level()
+    }
+
+    /// Returns the tracked 0-255 value of this LED's channel.
+    fn level(&self) -> u8 {
+        self.bank
+            .levels
+            .get(self.channel as usize)
+            .map_or(0, |level| level.load(Ordering::Relaxed))
+    }
+
+    /// Returns the PWM duty (0-255) last written to this LED's channel on the SN3218.
+    ///
+    /// This is the end of the chain from `brightness`: the logical 0.0-1.0 value is
+    /// mapped onto a tracked 0-255 value, which the global LED scale (see
+    /// `AutomationHAT::set_led_scale`) then reduces to the value sent to the chip. It
+    /// lags the tracked value while a frame rate limit holds the frame back, and stays
+    /// at the last value that was written if a write failed.
+    ///
+    /// # Returns
+    ///
+    /// The duty last written, or 0 if no frame has been written to this LED's driver
+    /// yet, or since it was re-opened or its last write failed
+    pub fn hardware_value(&self) -> u8 {
+        self.bank
+            .last_frame
+            .lock()
+            .unwrap()
+            .and_then(|frame| frame.get(self.channel as usize).copied())
+            .unwrap_or(0)
+    }
+
     /// Turns the LED on at full brightness.
     ///
     /// # Returns
     ///
     /// A `Result` indicating success or containing an error
-    pub fn on(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn on(&mut self) -> crate::Result<()> {
         self.set_brightness(1.0)
     }
 
@@ -64,7 +565,7 @@ impl LED {
     /// # Returns
     ///
     /// A `Result` indicating success or containing an error
-    pub fn off(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn off(&mut self) -> crate::Result<()> {
         self.set_brightness(0.0)
     }
 
@@ -76,7 +577,7 @@ impl LED {
     /// # Returns
     ///
     /// A `Result` indicating success or containing an error
-    pub fn toggle(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn toggle(&mut self) -> crate::Result<()> {
         if self.brightness == 0.0 {
             self.on()
         } else {
@@ -96,53 +597,397 @@ impl LED {
     ///
     /// # Errors
     ///
-    /// Returns an error if the brightness value is outside the valid range of 0.0 to 1.0,
-    /// or if communication with the LED driver fails.
-    pub fn set_brightness(&mut self, brightness: f64) -> Result<(), Box<dyn std::error::Error>> {
-        if brightness < 0.0 || brightness > 1.0 {
-            return Err("Brightness must be between 0.0 and 1.0".into());
+    /// Returns `HatError::InvalidBrightness` if the brightness value is NaN or outside
+    /// the valid range of 0.0 to 1.0, or an error if communication with the LED driver
+    /// fails.
+    pub fn set_brightness(&mut self, brightness: f64) -> crate::Result<()> {
+        let value = brightness_level(brightness, self.max_brightness)?;
+        let previous = std::mem::replace(&mut self.brightness, brightness);
+
+        if let Err(error) = write_frame(&self.bank, &[(self.channel, value)]) {
+            if failure_policy() == LedFailurePolicy::Rollback {
+                self.brightness = previous;
+            }
+            return Err(error);
         }
 
-        self.brightness = brightness;
-        let value = (brightness * self.max_brightness as f64) as u8;
+        Ok(())
+    }
+
+    /// Records a brightness without writing it, for callers that flush several LEDs
+    /// in a single `write_frame`.
+    ///
+    /// # Returns
+    ///
+    /// The channel and hardware value to pass to `write_frame`
+    pub(crate) fn stage(&mut self, brightness: f64) -> (u8, u8) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+        (
+            self.channel,
+            (self.brightness * self.max_brightness as f64) as u8,
+        )
+    }
+
+    /// Returns the SN3218 driver and state this LED is written through.
+    pub(crate) fn bank(&self) -> &Arc<LedBank> {
+        &self.bank
+    }
 
-        let led_state_mutex = LED_STATE.get_or_init(|| Mutex::new(HashMap::new()));
-        let mut led_state = led_state_mutex.lock().unwrap();
+    /// Sets the LED brightness, clamping the value into range instead of rejecting it.
+    ///
+    /// Values below 0.0 turn the LED off and values above 1.0 light it fully, so small
+    /// floating-point overshoots or over-range readings do not produce errors. Use
+    /// `set_brightness` when out-of-range values should be reported.
+    ///
+    /// NaN has no place in the range to clamp to, so it is passed through unchanged and
+    /// rejected by `set_brightness` rather than turning the LED on or off.
+    ///
+    /// # Arguments
+    ///
+    /// * `brightness` - The desired brightness; clamped to 0.0-1.0
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or containing an error
+    ///
+    /// # Errors
+    ///
+    /// Returns `HatError::InvalidBrightness` if `brightness` is NaN, or an error if
+    /// communication with the LED driver fails.
+    pub fn set_brightness_clamped(&mut self, brightness: f64) -> crate::Result<()> {
+        self.set_brightness(brightness.clamp(0.0, 1.0))
+    }
 
-        // Update the state for this channel
-        led_state.insert(self.channel, value);
+    /// Alias for `set_brightness` - sets the LED to a specific brightness.
+    ///
+    /// # Arguments
+    ///
+    /// * `brightness` - A value between 0.0 (off) and 1.0 (full brightness)
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or containing an error
+    pub fn set(&mut self, brightness: f64) -> crate::Result<()> {
+        self.set_brightness(brightness)
+    }
 
-        // Prepare values array with current state of all channels
-        let mut values = [0u8; 18];
-        let mut led_mask = 0u32;
+    /// Lights the LED fully for `duration`, then restores what it showed before.
+    ///
+    /// This is the quick "flash to acknowledge" idiom, e.g. for a pulse-count event,
+    /// rather than a continuous `blink`. The LED is lit before this returns and a
+    /// background thread restores it, so the caller is not held up. The brightness
+    /// restored is the channel's value from before the flash, whichever `LED` or board
+    /// method set it, so a flash composes with whatever the LED was showing. If
+    /// something else changes the LED while it is lit, that change is kept. Flashing
+    /// again before a flash has ended extends it. `brightness` is not changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - How long the LED stays lit; zero does nothing
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the LED was lit
+    /// * `Err(HatError)` - If the LED could not be written or the thread could not be
+    ///   started
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::LED;
+    /// use std::time::Duration;
+    ///
+    /// # fn run(led: &mut LED) -> automation_hat::Result<()> {
+    /// // Acknowledge a counted pulse without waiting
+    /// led.flash(Duration::from_millis(50))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn flash(&mut self, duration: Duration) -> crate::Result<()> {
+        if duration.is_zero() {
+            return Ok(());
+        }
+        let id = NEXT_FLASH.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut flashes = self.bank.flashes.lock().unwrap();
+            if let Some(slot) = flashes.get_mut(self.channel as usize) {
+                let prior = slot.map_or(self.level(), |(_, prior)| prior);
+                *slot = Some((id, prior));
+            }
+        }
+        if let Err(error) = write_frame(&self.bank, &[(self.channel, self.max_brightness)]) {
+            self.end_flash(id);
+            return Err(error);
+        }
 
-        for (channel, brightness) in led_state.iter() {
-            if *channel < 18 {
-                values[*channel as usize] = *brightness;
-                if *brightness > 0 {
-                    led_mask |= 1u32 << channel;
+        let led = self.clone();
+        thread::Builder::new()
+            .name(format!("automation-hat-led-flash-{}", self.channel))
+            .spawn(move || {
+                thread::sleep(duration);
+                led.end_flash(id);
+            })
+            .map_err(|error| {
+                self.end_flash(id);
+                HatError::Io(error.to_string())
+            })?;
+        Ok(())
+    }
+
+    /// Restores the channel after flash number `id`, unless a later flash has taken
+    /// over or the LED was changed while lit.
+    fn end_flash(&self, id: u64) {
+        let mut flashes = self.bank.flashes.lock().unwrap();
+        let Some(slot) = flashes.get_mut(self.channel as usize) else {
+            return;
+        };
+        let Some((latest, prior)) = *slot else {
+            return;
+        };
+        if latest != id {
+            return;
+        }
+        *slot = None;
+        if self.level() == self.max_brightness {
+            let _ = write_frame(&self.bank, &[(self.channel, prior)]);
+        }
+    }
+
+    /// Blinks the LED continuously on a background thread.
+    ///
+    /// The LED alternates between full brightness for `on_time` and off for
+    /// `off_time` until the returned handle is stopped or
+    /// [`AutomationHAT::stop_all_animations`](crate::AutomationHAT::stop_all_animations) is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_time` - How long the LED stays on in each cycle
+    /// * `off_time` - How long the LED stays off in each cycle
+    ///
+    /// # Returns
+    ///
+    /// An `Animation` handle that can be used to stop the blinking
+    pub fn blink(&self, on_time: Duration, off_time: Duration) -> Animation {
+        let mut lit = false;
+        animation::spawn(self.clone(), move |led| {
+            lit = !lit;
+            match lit {
+                true => {
+                    let _ = led.on();
+                    Some(on_time)
+                }
+                false => {
+                    let _ = led.off();
+                    Some(off_time)
                 }
             }
-        }
+        })
+    }
 
-        let mut driver = self.driver.lock().unwrap();
-        driver.enable_leds(led_mask).unwrap();
-        driver.output(&values).unwrap();
+    /// Blinks the LED a fixed number of times on a background thread, then stops.
+    ///
+    /// This is the usual "flash three times to acknowledge" feedback. The animation
+    /// ends by itself after the last off period begins, leaving the LED in the state
+    /// chosen by `end`; like `blink`, it can also be stopped early through the returned
+    /// handle or [`AutomationHAT::stop_all_animations`](crate::AutomationHAT::stop_all_animations).
+    ///
+    /// # Arguments
+    ///
+    /// * `times` - How many times the LED lights; 0 applies `end` immediately
+    /// * `on_time` - How long the LED stays on in each cycle
+    /// * `off_time` - How long the LED stays off between cycles
+    /// * `end` - The state to leave the LED in once the blinks are done; `Restore`
+    ///   returns it to its brightness before blinking
+    ///
+    /// # Returns
+    ///
+    /// An `Animation` handle whose `is_running` turns false once the blinks are done
+    pub fn blink_n(
+        &self,
+        times: u32,
+        on_time: Duration,
+        off_time: Duration,
+        end: AnimationEnd,
+    ) -> Animation {
+        let prior = self.brightness;
+        let frames = times.saturating_mul(2);
+        let mut frame = 0u32;
+        animation::spawn(self.clone(), move |led| {
+            let delay = match frame < frames {
+                true if frame.is_multiple_of(2) => {
+                    let _ = led.on();
+                    Some(on_time)
+                }
+                true if frame + 1 < frames => {
+                    let _ = led.off();
+                    Some(off_time)
+                }
+                _ => {
+                    let _ = match end {
+                        AnimationEnd::Off | AnimationEnd::LastFrame => led.off(),
+                        AnimationEnd::Restore => led.set_brightness(prior),
+                    };
+                    None
+                }
+            };
+            frame += 1;
+            delay
+        })
+    }
 
-        Ok(())
+    /// Blinks the LED on the async runtime's timer instead of a dedicated thread.
+    ///
+    /// The LED alternates between full brightness for `on_time` and off for
+    /// `off_time` for as long as the future is polled. To stop it, drop the future,
+    /// e.g. by racing it against another future or wrapping it in a timeout; the LED
+    /// is turned off when the future is dropped. Requires a Tokio runtime with its
+    /// timer enabled.
+    ///
+    /// Each I2C write runs on Tokio's blocking thread pool, so a slow bus or a busy
+    /// LED driver does not stall other tasks. The final write that turns the LED off
+    /// when the future is dropped is the exception: it runs on the dropping thread,
+    /// as `Drop` cannot await. Async animations are not tracked by
+    /// [`AutomationHAT::stop_all_animations`](crate::AutomationHAT::stop_all_animations).
+    ///
+    /// # Arguments
+    ///
+    /// * `on_time` - How long the LED stays on in each cycle
+    /// * `off_time` - How long the LED stays off in each cycle
+    ///
+    /// # Returns
+    ///
+    /// A future that only completes if the LED could not be updated, with
+    /// `Err(HatError::Led)`
+    #[cfg(feature = "async")]
+    pub async fn blink_async(
+        &mut self,
+        on_time: Duration,
+        off_time: Duration,
+    ) -> crate::Result<()> {
+        let mut guard = OffOnDrop::new(self);
+        loop {
+            guard.write(LED::on).await?;
+            tokio::time::sleep(on_time).await;
+            guard.write(LED::off).await?;
+            tokio::time::sleep(off_time).await;
+        }
     }
 
-    /// Alias for `set_brightness` - sets the LED to a specific brightness.
+    /// Pulses the LED smoothly on the async runtime's timer instead of a dedicated thread.
+    ///
+    /// The brightness follows a raised cosine from off to full and back once per
+    /// `period`, updated every 20ms, for as long as the future is polled. As with
+    /// `blink_async`, drop the future to stop it; the LED is turned off when the
+    /// future is dropped. Requires a Tokio runtime with its timer enabled, and like
+    /// `blink_async` it makes its I2C writes on the blocking thread pool.
     ///
     /// # Arguments
     ///
-    /// * `brightness` - A value between 0.0 (off) and 1.0 (full brightness)
+    /// * `period` - The time for one full off-on-off cycle
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or containing an error
-    pub fn set(&mut self, brightness: f64) -> Result<(), Box<dyn std::error::Error>> {
-        self.set_brightness(brightness)
+    /// A future that only completes with an error: `HatError::Config` if `period` is
+    /// zero, or `HatError::Led` if the LED could not be updated
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::LED;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run(led: &mut LED) {
+    /// // Pulse for up to ten seconds while connecting, then leave the LED off
+    /// let pulse = led.pulse_async(Duration::from_secs(2));
+    /// let _ = tokio::time::timeout(Duration::from_secs(10), pulse).await;
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn pulse_async(&mut self, period: Duration) -> crate::Result<()> {
+        if period.is_zero() {
+            return Err(HatError::Config(
+                "Pulse period must be greater than zero".to_string(),
+            ));
+        }
+        let mut guard = OffOnDrop::new(self);
+        let started = tokio::time::Instant::now();
+        let mut frames = tokio::time::interval(ASYNC_FRAME);
+        frames.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            frames.tick().await;
+            let phase = (started.elapsed().as_secs_f64() / period.as_secs_f64()).fract();
+            let level = 0.5 - 0.5 * (phase * std::f64::consts::TAU).cos();
+            guard
+                .write(move |led| led.set_brightness_clamped(level))
+                .await?;
+        }
+    }
+}
+
+/// Converts a 0.0-1.0 brightness into the hardware value of an LED whose full
+/// brightness is `max_brightness`.
+///
+/// # Returns
+///
+/// * `Ok(u8)` - The hardware value
+/// * `Err(HatError::InvalidBrightness)` - If `brightness` is NaN, infinite or outside
+///   0.0-1.0
+fn brightness_level(brightness: f64, max_brightness: u8) -> Result<u8, HatError> {
+    // A range check rather than two comparisons, so that NaN is rejected too
+    if !(0.0..=1.0).contains(&brightness) {
+        return Err(HatError::InvalidBrightness(brightness));
+    }
+    Ok((brightness * max_brightness as f64) as u8)
+}
+
+/// Turns an LED off when dropped, leaving it in a defined state once an async
+/// animation's future is cancelled.
+#[cfg(feature = "async")]
+struct OffOnDrop<'a> {
+    led: &'a mut LED,
+    /// Set once the guard is dropped, so a write still running on the blocking pool
+    /// knows to undo itself
+    dropped: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "async")]
+impl<'a> OffOnDrop<'a> {
+    fn new(led: &'a mut LED) -> Self {
+        OffOnDrop {
+            led,
+            dropped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Applies `update` to the LED on Tokio's blocking thread pool, so neither the
+    /// I2C write nor the wait for the driver lock stalls the runtime.
+    async fn write<F>(&mut self, update: F) -> crate::Result<()>
+    where
+        F: FnOnce(&mut LED) -> crate::Result<()> + Send + 'static,
+    {
+        let mut led = self.led.clone();
+        let dropped = Arc::clone(&self.dropped);
+        let (led, result) = tokio::task::spawn_blocking(move || {
+            let result = update(&mut led);
+            // The future may have been dropped while this write waited for the driver,
+            // after the guard already turned the LED off; turn it off again
+            if dropped.load(Ordering::SeqCst) {
+                let _ = led.off();
+            }
+            (led, result)
+        })
+        .await
+        .map_err(|error| HatError::Led(error.to_string()))?;
+        *self.led = led;
+        result
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for OffOnDrop<'_> {
+    fn drop(&mut self) {
+        self.dropped.store(true, Ordering::SeqCst);
+        let _ = self.led.off();
     }
 }
 
@@ -151,7 +996,7 @@ impl LED {
 impl Clone for LED {
     fn clone(&self) -> Self {
         LED {
-            driver: Arc::clone(&self.driver),
+            bank: Arc::clone(&self.bank),
             channel: self.channel,
             brightness: self.brightness,
             max_brightness: self.max_brightness,
@@ -169,3 +1014,140 @@ impl std::fmt::Debug for LED {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_frame_is_always_written() {
+        assert_eq!(next_frame([0; 18], 255, None), Some([0; 18]));
+    }
+
+    #[test]
+    fn redundant_frame_is_not_written() {
+        let mut levels = [0; 18];
+        levels[6] = 255;
+        let written = next_frame(levels, 255, None).unwrap();
+        // Setting a channel to the value it already has leaves the frame unchanged
+        levels[6] = 255;
+        assert_eq!(next_frame(levels, 255, Some(written)), None);
+    }
+
+    #[test]
+    fn changed_channel_is_written() {
+        let written = next_frame([0; 18], 255, None).unwrap();
+        let mut levels = [0; 18];
+        levels[17] = 1;
+        assert_eq!(next_frame(levels, 255, Some(written)), Some(levels));
+    }
+
+    #[test]
+    fn scale_is_applied_and_a_new_scale_rewrites_the_frame() {
+        let levels = [255; 18];
+        let full = next_frame(levels, 255, None).unwrap();
+        assert_eq!(full, [255; 18]);
+        assert_eq!(next_frame(levels, 128, Some(full)), Some([128; 18]));
+    }
+
+    #[test]
+    fn enable_mask_lights_only_non_zero_channels() {
+        let mut frame = [0; 18];
+        assert_eq!(enable_mask(&frame), 0);
+        frame[0] = 1;
+        frame[7] = 255;
+        frame[17] = 10;
+        assert_eq!(enable_mask(&frame), 1 | 1 << 7 | 1 << 17);
+    }
+
+    #[test]
+    fn brightness_level_rejects_nan_and_infinities() {
+        for brightness in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                brightness_level(brightness, 255),
+                Err(HatError::InvalidBrightness(rejected))
+                    if rejected.to_bits() == brightness.to_bits()
+            ));
+        }
+    }
+
+    #[test]
+    fn brightness_level_accepts_the_closed_unit_range() {
+        assert_eq!(brightness_level(-0.0, 255), Ok(0));
+        assert_eq!(brightness_level(0.0, 255), Ok(0));
+        assert_eq!(brightness_level(0.5, 255), Ok(127));
+        assert_eq!(brightness_level(1.0, 255), Ok(255));
+    }
+
+    #[test]
+    fn brightness_level_rejects_values_just_out_of_range() {
+        assert_eq!(
+            brightness_level(1.0 + f64::EPSILON, 255),
+            Err(HatError::InvalidBrightness(1.0 + f64::EPSILON))
+        );
+        assert_eq!(
+            brightness_level(-f64::MIN_POSITIVE, 255),
+            Err(HatError::InvalidBrightness(-f64::MIN_POSITIVE))
+        );
+    }
+
+    #[test]
+    fn store_levels_returns_replaced_values_and_skips_unknown_channels() {
+        let levels = [const { AtomicU8::new(0) }; 18];
+        levels[3].store(40, Ordering::Relaxed);
+        let replaced = store_levels(&levels, &[(3, 200), (18, 1), (3, 220)]);
+        // A channel set twice keeps its value from before both updates
+        assert_eq!(replaced[3], Some((220, 40)));
+        assert_eq!(replaced.iter().flatten().count(), 1);
+        assert_eq!(levels[3].load(Ordering::Relaxed), 220);
+    }
+
+    /// Compares storing LED updates from several threads in per-channel atomics, as
+    /// `write_frame` does, with the single `Mutex<HashMap>` the state used to be.
+    /// Flushing to the chip is serialized either way and needs hardware, so only the
+    /// state update is timed. Run with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore = "timing comparison, run on demand"]
+    fn concurrent_led_updates_timing() {
+        use std::collections::HashMap;
+        use std::time::Instant;
+
+        const THREADS: u8 = 8;
+        const UPDATES: u32 = 200_000;
+
+        let atomics = [const { AtomicU8::new(0) }; 18];
+        let started = Instant::now();
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let atomics = &atomics;
+                scope.spawn(move || {
+                    for update in 0..UPDATES {
+                        let channel = (thread + update as u8) % 18;
+                        store_levels(atomics, &[(channel, update as u8)]);
+                    }
+                });
+            }
+        });
+        let lock_free = started.elapsed();
+
+        let mutex: Mutex<HashMap<u8, u8>> = Mutex::new(HashMap::new());
+        let started = Instant::now();
+        thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let mutex = &mutex;
+                scope.spawn(move || {
+                    for update in 0..UPDATES {
+                        let channel = (thread + update as u8) % 18;
+                        mutex.lock().unwrap().insert(channel, update as u8);
+                    }
+                });
+            }
+        });
+        let locked = started.elapsed();
+
+        println!(
+            "{} threads x {} updates: atomics {:?}, mutex {:?}",
+            THREADS, UPDATES, lock_free, locked
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! MQTT bridge for Automation HAT boards.
+//!
+//! This module is only compiled with the `mqtt` cargo feature. `MqttBridge` publishes
+//! digital input and analog input changes to MQTT topics and subscribes to command
+//! topics for the relays and digital outputs, turning the board into a ready-made
+//! device for Home Assistant and similar systems.
+//!
+//! # Topics
+//!
+//! With the default base topic of `automation-hat`:
+//!
+//! | Topic                             | Direction | Payload |
+//! |-----------------------------------|-----------|---------|
+//! | `automation-hat/input/{1-3}`      | published | `ON` / `OFF` |
+//! | `automation-hat/analog/{1-4}`     | published | terminal voltage in volts |
+//! | `automation-hat/relay/{1-3}`      | published | `ON` / `OFF` |
+//! | `automation-hat/output/{1-3}`     | published | `ON` / `OFF` |
+//! | `automation-hat/relay/{1-3}/set`  | subscribed | `ON` / `OFF` (also `1`/`0`, `true`/`false`) |
+//! | `automation-hat/output/{1-3}/set` | subscribed | `ON` / `OFF` (also `1`/`0`, `true`/`false`) |
+//!
+//! State topics are retained, so subscribers see the current state immediately.
+
+use crate::channel::Channel;
+use crate::worker::{StopFlag, Worker, lock};
+use crate::{AutomationHAT, HatError, Result};
+
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long the connection thread waits for an event before checking for shutdown
+static RECV_TIMEOUT: Duration = Duration::from_millis(200);
+/// How long to wait before reconnecting after a connection error
+static RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Connection and publishing options for `MqttBridge`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::MqttConfig;
+/// use std::time::Duration;
+///
+/// let config = MqttConfig::new("broker.local")
+///     .base_topic("greenhouse/hat")
+///     .poll_interval(Duration::from_millis(250));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MqttConfig {
+    /// Broker host name or address
+    pub host: String,
+    /// Broker port
+    pub port: u16,
+    /// Client identifier presented to the broker
+    pub client_id: String,
+    /// Prefix for every topic
+    pub base_topic: String,
+    /// How often the inputs are read
+    pub poll_interval: Duration,
+    /// Minimum change in volts before an analog input is republished
+    pub analog_deadband: f64,
+}
+
+impl MqttConfig {
+    /// Creates a configuration for the given broker with default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - Broker host name or address
+    ///
+    /// # Returns
+    ///
+    /// A `MqttConfig` using port 1883, client id and base topic `automation-hat`,
+    /// a 100ms poll interval and a 0.1V analog deadband
+    pub fn new(host: impl Into<String>) -> Self {
+        MqttConfig {
+            host: host.into(),
+            port: 1883,
+            client_id: "automation-hat".to_string(),
+            base_topic: "automation-hat".to_string(),
+            poll_interval: Duration::from_millis(100),
+            analog_deadband: 0.1,
+        }
+    }
+
+    /// Sets the broker port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Sets the client identifier, which must be unique per broker.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Sets the prefix for every topic.
+    pub fn base_topic(mut self, base_topic: impl Into<String>) -> Self {
+        self.base_topic = base_topic.into();
+        self
+    }
+
+    /// Sets how often the inputs are read.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the minimum change in volts before an analog input is republished.
+    pub fn analog_deadband(mut self, volts: f64) -> Self {
+        self.analog_deadband = volts;
+        self
+    }
+}
+
+/// A running MQTT bridge, stopped with `stop` or when dropped.
+pub struct MqttBridge {
+    /// Client used to disconnect on stop
+    client: Client,
+    /// The polling and connection threads
+    workers: Vec<Worker>,
+}
+
+impl MqttBridge {
+    /// Connects to the broker and starts bridging the board.
+    ///
+    /// Two background threads are started: one reads the inputs every
+    /// `poll_interval` and publishes changes, the other maintains the connection
+    /// (reconnecting as needed) and applies relay and output commands. Both lock
+    /// `hat` only while touching the board.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board to bridge, shared with the rest of the application
+    /// * `config` - Broker connection and publishing options
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MqttBridge)` - A handle to the running bridge
+    /// * `Err(HatError::Config)` - If `poll_interval` is zero
+    /// * `Err(HatError)` - If the background threads could not be started
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType, MqttBridge, MqttConfig};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let hat = Arc::new(Mutex::new(AutomationHAT::new(HatType::AutomationHAT)));
+    /// let bridge = MqttBridge::start(hat, MqttConfig::new("localhost"))?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn start(hat: Arc<Mutex<AutomationHAT>>, config: MqttConfig) -> Result<Self> {
+        if config.poll_interval.is_zero() {
+            return Err(HatError::Config(
+                "MQTT poll interval must be greater than zero".to_string(),
+            ));
+        }
+        let options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        let (client, mut connection) = Client::new(options, 64);
+
+        let mut bridge = MqttBridge {
+            client: client.clone(),
+            workers: Vec::new(),
+        };
+
+        let poller = {
+            let (hat, client, config) = (hat.clone(), client.clone(), config.clone());
+            Worker::spawn("automation-hat-mqtt-poll", move |stop| {
+                poll_loop(&hat, &client, &stop, &config)
+            })?
+        };
+        bridge.workers.push(poller);
+
+        let connection_worker = Worker::spawn("automation-hat-mqtt", move |stop| {
+            let base = config.base_topic;
+            while !stop.is_set() {
+                match connection.recv_timeout(RECV_TIMEOUT) {
+                    Ok(Ok(Event::Incoming(Packet::ConnAck(_)))) => {
+                        for kind in ["relay", "output"] {
+                            let topic = format!("{}/{}/+/set", base, kind);
+                            let _ = client.try_subscribe(topic, QoS::AtLeastOnce);
+                        }
+                    }
+                    Ok(Ok(Event::Incoming(Packet::Publish(publish)))) => {
+                        // The new state is published by the poll thread
+                        if let Some((channel, on)) =
+                            parse_command(&base, &publish.topic, &publish.payload)
+                        {
+                            let _ = apply(&mut lock(&hat), channel, on);
+                        }
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(_)) => stop.sleep_until(Instant::now() + RECONNECT_DELAY),
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        })?;
+        bridge.workers.push(connection_worker);
+
+        Ok(bridge)
+    }
+
+    /// Stops the bridge, disconnecting from the broker and waiting for the
+    /// background threads to exit. Relays and outputs are left as they are.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for MqttBridge {
+    fn drop(&mut self) {
+        let _ = self.client.try_disconnect();
+        self.workers.clear();
+    }
+}
+
+/// Reads the inputs every poll interval and publishes the ones that changed.
+fn poll_loop(hat: &Mutex<AutomationHAT>, client: &Client, stop: &StopFlag, config: &MqttConfig) {
+    let base = &config.base_topic;
+    let mut inputs: [Option<bool>; 3] = [None; 3];
+    let mut analog: [Option<f64>; 4] = [None; 4];
+    let mut outputs: [Option<(bool, bool)>; 3] = [None; 3];
+
+    while !stop.is_set() {
+        let started = Instant::now();
+        let mut input_values = [None; 3];
+        let mut analog_values = [None; 4];
+        let mut output_values = [None; 3];
+        {
+            let mut hat = lock(hat);
+            for index in 1..=3 {
+                input_values[index - 1] = hat
+                    .inputs
+                    .get_mut(index)
+                    .and_then(|input| input.read().ok());
+                output_values[index - 1] = Some((
+                    hat.relays
+                        .get_mut(index)
+                        .is_some_and(|relay| relay.is_energized()),
+                    hat.outputs
+                        .get_mut(index)
+                        .is_some_and(|output| output.value),
+                ));
+            }
+            for index in 1..=4 {
+                analog_values[index - 1] = hat
+                    .analog_inputs
+                    .get_mut(index)
+                    .and_then(|input| input.read_voltage().ok());
+            }
+        }
+
+        for (index, value) in input_values.into_iter().enumerate() {
+            if let Some(value) = value
+                && inputs[index] != Some(value)
+            {
+                inputs[index] = Some(value);
+                let topic = format!("{}/input/{}", base, index + 1);
+                let _ = client.try_publish(topic, QoS::AtLeastOnce, true, on_off(value));
+            }
+        }
+        for (index, value) in analog_values.into_iter().enumerate() {
+            if let Some(volts) = value
+                && analog[index].is_none_or(|last| (volts - last).abs() >= config.analog_deadband)
+            {
+                analog[index] = Some(volts);
+                let topic = format!("{}/analog/{}", base, index + 1);
+                let _ = client.try_publish(topic, QoS::AtLeastOnce, true, format!("{:.2}", volts));
+            }
+        }
+        // Relay and output state can also change through direct API use, so it is polled too
+        for (index, value) in output_values.into_iter().enumerate() {
+            if let Some((relay, output)) = value
+                && outputs[index] != value
+            {
+                outputs[index] = value;
+                publish_state(client, base, Channel::Relay(index + 1), relay);
+                publish_state(client, base, Channel::Output(index + 1), output);
+            }
+        }
+
+        stop.sleep_until(started + config.poll_interval);
+    }
+}
+
+/// Parses a `{base}/{relay|output}/{n}/set` command into a channel and state.
+fn parse_command(base: &str, topic: &str, payload: &[u8]) -> Option<(Channel, bool)> {
+    let rest = topic.strip_prefix(base)?.strip_prefix('/')?;
+    let mut parts = rest.split('/');
+    let (kind, index, set) = (parts.next()?, parts.next()?, parts.next()?);
+    if set != "set" || parts.next().is_some() || !matches!(kind, "relay" | "output") {
+        return None;
+    }
+    let channel: Channel = format!("{}{}", kind, index).parse().ok()?;
+    let on = match std::str::from_utf8(payload)
+        .ok()?
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "on" | "1" | "true" => true,
+        "off" | "0" | "false" => false,
+        _ => return None,
+    };
+    Some((channel, on))
+}
+
+/// Switches a relay or digital output; commands that fail are dropped.
+fn apply(hat: &mut AutomationHAT, channel: Channel, on: bool) -> Result<()> {
+    match channel {
+        Channel::Relay(index) => hat.relays.try_write(index, on),
+        Channel::Output(index) => hat
+            .outputs
+            .get_mut(index)
+            .ok_or(HatError::InvalidChannel(index))?
+            .write(on),
+        _ => Err(HatError::InvalidChannel(channel.index())),
+    }
+}
+
+/// Publishes the retained state of a relay or digital output.
+fn publish_state(client: &Client, base: &str, channel: Channel, on: bool) {
+    let kind = match channel {
+        Channel::Relay(_) => "relay",
+        _ => "output",
+    };
+    let topic = format!("{}/{}/{}", base, kind, channel.index());
+    let _ = client.try_publish(topic, QoS::AtLeastOnce, true, on_off(on));
+}
+
+fn on_off(value: bool) -> &'static str {
+    match value {
+        true => "ON",
+        false => "OFF",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_relay_and_output_commands() {
+        assert_eq!(
+            parse_command("hat", "hat/relay/1/set", b"on"),
+            Some((Channel::Relay(1), true))
+        );
+        assert_eq!(
+            parse_command("site/hat", "site/hat/output/3/set", b"0"),
+            Some((Channel::Output(3), false))
+        );
+    }
+
+    #[test]
+    fn accepts_each_payload_spelling() {
+        for (payload, on) in [
+            (&b"ON"[..], true),
+            (b"1", true),
+            (b" true\n", true),
+            (b"Off", false),
+            (b"0", false),
+            (b"false", false),
+        ] {
+            assert_eq!(
+                parse_command("hat", "hat/relay/2/set", payload),
+                Some((Channel::Relay(2), on))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_other_topics() {
+        for topic in [
+            "other/relay/1/set",
+            "hatrelay/1/set",
+            "hat/relay/1",
+            "hat/relay/1/get",
+            "hat/relay/1/set/now",
+            "hat/input/1/set",
+            "hat/analog/1/set",
+            "hat/relay/4/set",
+            "hat/relay/x/set",
+        ] {
+            assert_eq!(parse_command("hat", topic, b"on"), None, "{}", topic);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_payloads() {
+        for payload in [&b"yes"[..], b"", b"2", &[0xff, 0xfe]] {
+            assert_eq!(parse_command("hat", "hat/relay/1/set", payload), None);
+        }
+    }
+}
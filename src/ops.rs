@@ -0,0 +1,110 @@
+//! One-shot operations for scripts and small command-line tools.
+//!
+//! Each function in this module constructs an `AutomationHAT`, performs a single
+//! operation, and drops the board again before returning. Only the subsystems the
+//! operation needs are initialized, and the GPIO lines are released when the board
+//! is dropped so the next invocation can claim them.
+//!
+//! Releasing a GPIO line does not reset it: relays and outputs keep the level they
+//! were last driven to after the function returns.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use automation_hat::{HatType, ops};
+//!
+//! fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     ops::set_relay(HatType::AutomationHAT, 1, true)?;
+//!     let level = ops::read_analog(HatType::AutomationHAT, 2)?;
+//!     println!("Analog 2: {}", level);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{AutomationHAT, HatConfig, HatError, HatType, Result};
+
+/// Opens the board with only the GPIO lines and (optionally) the ADC initialized.
+fn open(hat_type: HatType, analog: bool) -> AutomationHAT {
+    let config = HatConfig::new().enable_analog(analog).enable_display(false);
+    AutomationHAT::with_config(hat_type, config)
+}
+
+/// Switches a single relay on or off.
+///
+/// # Arguments
+///
+/// * `hat_type` - The type of Automation HAT to operate
+/// * `index` - The relay number as printed on the board (1-3)
+/// * `on` - The desired relay state
+///
+/// # Returns
+///
+/// * `Ok(())` - If the relay was switched
+/// * `Err(HatError)` - If `index` is invalid, the relay is not fitted on this board,
+///   or the relay could not be switched
+pub fn set_relay(hat_type: HatType, index: usize, on: bool) -> Result<()> {
+    let mut hat = open(hat_type, false);
+    hat.relays.try_write(index, on)
+}
+
+/// Sets a single digital output high or low.
+///
+/// # Arguments
+///
+/// * `hat_type` - The type of Automation HAT to operate
+/// * `index` - The output number as printed on the board (1-3)
+/// * `on` - The desired output state
+///
+/// # Returns
+///
+/// * `Ok(())` - If the output was set
+/// * `Err(HatError)` - If `index` is invalid or the output could not be set
+pub fn set_output(hat_type: HatType, index: usize, on: bool) -> Result<()> {
+    let mut hat = open(hat_type, false);
+    let output = hat
+        .outputs
+        .get_mut(index)
+        .ok_or(HatError::InvalidChannel(index))?;
+    output.write(on)
+}
+
+/// Reads a single digital input.
+///
+/// # Arguments
+///
+/// * `hat_type` - The type of Automation HAT to operate
+/// * `index` - The input number as printed on the board (1-3)
+///
+/// # Returns
+///
+/// * `Ok(bool)` - The state of the input
+/// * `Err(HatError)` - If `index` is invalid or the input could not be read
+pub fn read_input(hat_type: HatType, index: usize) -> Result<bool> {
+    let mut hat = open(hat_type, false);
+    let input = hat
+        .inputs
+        .get_mut(index)
+        .ok_or(HatError::InvalidChannel(index))?;
+    input.read()
+}
+
+/// Reads a single analog input.
+///
+/// # Arguments
+///
+/// * `hat_type` - The type of Automation HAT to operate
+/// * `channel` - The analog input number as printed on the board (1-4)
+///
+/// # Returns
+///
+/// * `Ok(f64)` - The normalized input value between 0.0 and 1.0
+/// * `Err(HatError)` - If `channel` is invalid or the input could not be read
+pub fn read_analog(hat_type: HatType, channel: usize) -> Result<f64> {
+    let mut hat = open(hat_type, true);
+    let input = hat
+        .analog_inputs
+        .get_mut(channel)
+        .ok_or(HatError::InvalidChannel(channel))?;
+    input.ensure_available()?;
+    input.read()
+}
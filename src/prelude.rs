@@ -0,0 +1,22 @@
+//! Convenience re-exports for Automation HAT applications.
+//!
+//! Glob-importing this module brings the board handle, its component types and the
+//! crate's error types into scope:
+//!
+//! ```rust,no_run
+//! use automation_hat::prelude::*;
+//!
+//! fn main() -> Result<()> {
+//!     let mut hat = AutomationHAT::try_new(HatType::AutomationHAT)?;
+//!     hat.relays.try_write(1, true)?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Note that this includes the crate's [`Result`] alias, which shadows
+//! `std::result::Result` wherever the prelude is imported.
+
+pub use crate::{
+    AnalogInput, AutomationHAT, Channel, DigitalInput, DigitalOutput, HatConfig, HatError, HatType,
+    LED, LedLabel, Relay, Result,
+};
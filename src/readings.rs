@@ -0,0 +1,94 @@
+//! A complete reading of the board in one call.
+//!
+//! This module provides `HatReadings`, returned by `AutomationHAT::read_all`, which
+//! gathers every input, analog channel, relay, output and LED into one value. It suits
+//! web and dashboard backends that report the whole board on each request, and
+//! serializes with the `serde` feature.
+
+use crate::analog_input::AnalogSnapshot;
+use crate::{AutomationHAT, Result};
+
+use std::time::SystemTime;
+
+/// The state of every channel of the board at one moment.
+///
+/// Arrays are indexed by board label minus one, so `inputs[0]` is Input 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HatReadings {
+    /// Wall-clock time at which the reading started
+    pub timestamp: SystemTime,
+    /// Level of each digital input
+    pub inputs: [bool; 3],
+    /// Every analog channel, converted under one lock of the ADC
+    pub analog: AnalogSnapshot,
+    /// Whether each relay is energized; `None` for relays not fitted to this board
+    pub relays: [Option<bool>; 3],
+    /// Commanded state of each digital output
+    pub outputs: [bool; 3],
+    /// Tracked 0-255 value of each SN3218 channel (see `AutomationHAT::led_state`);
+    /// `None` on boards without LEDs
+    pub leds: Option<[u8; 18]>,
+}
+
+impl AutomationHAT {
+    /// Reads every input and collects the state of every output in one pass.
+    ///
+    /// The digital inputs are read as by `read`, including debouncing and LED
+    /// updates. The analog channels are converted in turn under a single lock of the
+    /// ADC, as by `AnalogInputs::snapshot`, which sets how long this takes. Relays,
+    /// outputs and LEDs are reported from the state the crate tracks, without touching
+    /// the hardware.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HatReadings)` - The state of the whole board
+    /// * `Err(HatError)` - If an input or analog channel could not be read
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::try_new(HatType::AutomationHAT)?;
+    /// let readings = hat.read_all()?;
+    /// if readings.inputs[0] {
+    ///     println!("Input 1 is high");
+    /// }
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn read_all(&mut self) -> Result<HatReadings> {
+        let timestamp = SystemTime::now();
+        let mut inputs = [false; 3];
+        for (level, index) in inputs.iter_mut().zip(1..) {
+            if let Some(input) = self.inputs.get_mut(index) {
+                *level = input.read()?;
+            }
+        }
+        let analog = self.analog_inputs.snapshot()?;
+
+        let mut relays = [None; 3];
+        for (state, index) in relays.iter_mut().zip(1..) {
+            if self.relays.is_present(index)
+                && let Some(relay) = self.relays.get_mut(index)
+            {
+                *state = Some(relay.is_energized());
+            }
+        }
+        let outputs = [
+            self.outputs.one.value,
+            self.outputs.two.value,
+            self.outputs.three.value,
+        ];
+        let leds = self.led_driver.as_ref().map(|bank| bank.levels());
+
+        Ok(HatReadings {
+            timestamp,
+            inputs,
+            analog,
+            relays,
+            outputs,
+            leds,
+        })
+    }
+}
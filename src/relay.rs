@@ -2,15 +2,170 @@
 //!
 //! This module provides control for the relay outputs on Automation HAT boards.
 //! Each relay has both normally open (NO) and normally closed (NC) terminals,
-//! and can be controlled with indicator LEDs showing the current state.
+//! and can be controlled with indicator LEDs showing the current state. Relays can
+//! also run in momentary mode, switching back off on their own after a set time, and
+//! be associated with a digital input wired to their contacts to confirm they moved,
+//! and be interlocked so that energizing one switches the others in its group off first.
 
+use crate::channel::Channel;
+use crate::digital_input::DigitalInput;
+use crate::error::{HatError, Result};
+use crate::instrumentation;
 use crate::lights::LED;
+use crate::worker::Worker;
 
 use embedded_hal::digital::{OutputPin, PinState};
 use linux_embedded_hal::{
     CdevPin,
     gpio_cdev::{Line, LineRequestFlags},
 };
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long `Relay::measure_latency` waits for the feedback input before giving up
+const LATENCY_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Chooses which indicator LED lights while the relay is energized.
+///
+/// By default the NO LED shows that the relay is energized and the NC LED that it
+/// is at rest. Installations with the load wired to the NC terminal can swap the
+/// two, so the LED that is lit always marks the side of the relay the user treats
+/// as "load active".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LedConvention {
+    /// The NO LED lights while the relay is energized, the NC LED while it is at rest
+    #[default]
+    NormallyOpen,
+    /// The NC LED lights while the relay is energized, the NO LED while it is at rest
+    NormallyClosed,
+}
+
+/// How a relay responds to being switched on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelayMode {
+    /// The relay stays in whatever state it was last written to
+    #[default]
+    Latched,
+    /// `write(true)` energizes the relay for `duration`, then it switches back off,
+    /// like a door-release button
+    Momentary {
+        /// How long the relay stays energized after `write(true)`
+        duration: Duration,
+    },
+}
+
+/// The commanded and sensed state of a relay with contact feedback, returned by
+/// `AutomationHAT::confirmed_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelayConfirmation {
+    /// The state the relay was last written to
+    pub commanded: bool,
+    /// The state reported by the feedback input
+    pub sensed: bool,
+}
+
+impl RelayConfirmation {
+    /// Returns whether the contacts are in the commanded state.
+    pub fn is_confirmed(&self) -> bool {
+        self.commanded == self.sensed
+    }
+}
+
+/// Switches a relay off from outside its `Relay`, for interlock peers.
+#[derive(Clone)]
+pub(crate) struct OffSwitch {
+    /// GPIO line offset of the relay pin, identifying the relay within its group
+    offset: u32,
+    /// GPIO pin controlling the relay
+    pin: Arc<Mutex<CdevPin>>,
+    /// Live relay state
+    energized: Arc<AtomicBool>,
+    /// The indicator LEDs and convention, if they follow the relay automatically
+    leds: Option<(Option<LED>, Option<LED>, LedConvention)>,
+}
+
+impl OffSwitch {
+    /// Switches the relay off if it is energized.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If the relay was energized and has been switched off
+    /// * `Ok(false)` - If the relay was already off
+    /// * `Err(HatError::Gpio)` - If the pin could not be driven
+    fn release(&mut self) -> Result<bool> {
+        let mut pin = self
+            .pin
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !self.energized.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        pin.set_low().map_err(|error| {
+            HatError::Gpio(format!("Unable to release an interlocked relay: {}", error))
+        })?;
+        self.energized.store(false, Ordering::Relaxed);
+        instrumentation::relay_switched(self.offset);
+        if let Some((no_led, nc_led, convention)) = &mut self.leds {
+            light_leds(no_led.as_mut(), nc_led.as_mut(), *convention, false);
+        }
+        Ok(true)
+    }
+}
+
+/// A group of relays that must never be energized at the same time.
+///
+/// Shared by every member, so that each member's `write` can switch the others off.
+pub(crate) struct Interlock {
+    /// Every relay in the group
+    members: Vec<OffSwitch>,
+    /// How long to wait between switching a member off and energizing another
+    dead_time: Duration,
+}
+
+/// Handle to an interlock group, held by each member relay
+pub(crate) type SharedInterlock = Arc<Mutex<Interlock>>;
+
+impl Interlock {
+    /// Creates a group of the given relays.
+    pub(crate) fn new(members: Vec<OffSwitch>, dead_time: Duration) -> SharedInterlock {
+        Arc::new(Mutex::new(Interlock { members, dead_time }))
+    }
+
+    /// Sets how long to wait between switching a member off and energizing another.
+    pub(crate) fn set_dead_time(&mut self, dead_time: Duration) {
+        self.dead_time = dead_time;
+    }
+
+    /// Switches off every member other than the relay on line `offset`, then waits out
+    /// the dead time if any of them was energized.
+    fn release_others(&mut self, offset: u32) -> Result<()> {
+        let mut released = false;
+        for member in self
+            .members
+            .iter_mut()
+            .filter(|member| member.offset != offset)
+        {
+            released |= member.release()?;
+        }
+        if released && !self.dead_time.is_zero() {
+            thread::sleep(self.dead_time);
+        }
+        Ok(())
+    }
+
+    /// Replaces the stored switch of a member, e.g. after its LED convention changed.
+    fn refresh(&mut self, switch: OffSwitch) {
+        if let Some(member) = self
+            .members
+            .iter_mut()
+            .find(|member| member.offset == switch.offset)
+        {
+            *member = switch;
+        }
+    }
+}
 
 /// Controls a relay output on the Automation HAT.
 ///
@@ -18,16 +173,39 @@ use linux_embedded_hal::{
 /// Relays have both normally open (NO) and normally closed (NC) terminals,
 /// which can be used to switch external circuits.
 pub struct Relay {
-    /// GPIO pin controlling the relay
-    pin: CdevPin,
+    /// GPIO pin controlling the relay, shared with the momentary timer
+    pin: Arc<Mutex<CdevPin>>,
+    /// GPIO line offset of the relay pin
+    offset: u32,
     /// LED indicating the normally open contact state
     no_led: Option<LED>,
     /// LED indicating the normally closed contact state
     nc_led: Option<LED>,
     /// Whether LEDs should automatically reflect the relay state
     _auto_light: bool,
+    /// Which LED lights while the relay is energized
+    led_convention: LedConvention,
+    /// Whether the indicator LEDs are kept dark whatever the relay state
+    leds_disabled: bool,
     /// Current state of the relay (true = activated/on, false = deactivated/off)
+    ///
+    /// In momentary mode this catches up with an automatic switch-off on the next
+    /// call into the relay; `is_energized` always returns the live state.
     pub value: bool,
+    /// Number of state changes since creation or the last reset
+    cycles: u64,
+    /// Whether `write(true)` latches or switches back off on its own
+    mode: RelayMode,
+    /// Live relay state, cleared by the momentary timer when it switches the relay off
+    energized: Arc<AtomicBool>,
+    /// The pending automatic switch-off, if any; dropping it cancels the switch-off
+    timer: Option<Worker>,
+    /// Board label of the digital input wired to the relay's contacts, if any
+    feedback_input: Option<usize>,
+    /// How long `write` waits after switching for the contacts to settle
+    settle_time: Duration,
+    /// The interlock group this relay belongs to, if any
+    interlock: Option<SharedInterlock>,
 }
 
 impl Relay {
@@ -43,16 +221,27 @@ impl Relay {
     ///
     /// A new `Relay` instance configured with automatic LED indication
     pub fn new(line: Line, no_led: Option<LED>, nc_led: Option<LED>) -> Self {
+        let offset = line.offset();
         let line = line
             .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
             .unwrap();
-        let pin = CdevPin::new(line).unwrap();
+        let pin = Arc::new(Mutex::new(CdevPin::new(line).unwrap()));
         Relay {
             pin,
+            offset,
             no_led,
             nc_led,
             _auto_light: true,
+            led_convention: LedConvention::default(),
+            leds_disabled: false,
             value: false,
+            cycles: 0,
+            mode: RelayMode::default(),
+            energized: Arc::new(AtomicBool::new(false)),
+            timer: None,
+            feedback_input: None,
+            settle_time: Duration::ZERO,
+            interlock: None,
         }
     }
 
@@ -74,17 +263,54 @@ impl Relay {
         nc_led: Option<LED>,
         auto_light: bool,
     ) -> Self {
+        Self::try_new_with_auto_light(line, no_led, nc_led, auto_light).unwrap()
+    }
+
+    /// Creates a new relay instance with configurable LED indication, returning an error
+    /// instead of panicking if the GPIO line cannot be requested.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - GPIO line connected to the relay
+    /// * `no_led` - Optional LED for the normally open contact indicator
+    /// * `nc_led` - Optional LED for the normally closed contact indicator
+    /// * `auto_light` - Whether LEDs should automatically reflect relay state
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Relay)` - If the line was requested successfully
+    /// * `Err(HatError)` - If the line is unavailable or already in use
+    pub fn try_new_with_auto_light(
+        line: Line,
+        no_led: Option<LED>,
+        nc_led: Option<LED>,
+        auto_light: bool,
+    ) -> Result<Self> {
+        let offset = line.offset();
         let line = line
             .request(LineRequestFlags::OUTPUT, 0, "AutomationHAT Rust SDK")
-            .unwrap();
-        let pin = CdevPin::new(line).unwrap();
-        Relay {
+            .map_err(|error| {
+                HatError::Gpio(format!("Failed to request line {}: {}", offset, error))
+            })?;
+        let pin = CdevPin::new(line).map_err(|error| HatError::Gpio(error.to_string()))?;
+        let pin = Arc::new(Mutex::new(pin));
+        Ok(Relay {
             pin,
+            offset,
             no_led,
             nc_led,
             _auto_light: auto_light,
+            led_convention: LedConvention::default(),
+            leds_disabled: false,
             value: false,
-        }
+            cycles: 0,
+            mode: RelayMode::default(),
+            energized: Arc::new(AtomicBool::new(false)),
+            timer: None,
+            feedback_input: None,
+            settle_time: Duration::ZERO,
+            interlock: None,
+        })
     }
 
     /// Sets the state of the relay.
@@ -93,11 +319,26 @@ impl Relay {
     /// - The normally open (NO) contacts close
     /// - The normally closed (NC) contacts open
     /// - If auto_light is enabled, the NO LED lights up and NC LED turns off
+    ///   (swapped under `LedConvention::NormallyClosed`)
     ///
     /// When `open` is false, the relay is deactivated:
     /// - The normally open (NO) contacts open
     /// - The normally closed (NC) contacts close
     /// - If auto_light is enabled, the NO LED turns off and NC LED lights up
+    ///   (swapped under `LedConvention::NormallyClosed`)
+    ///
+    /// In `RelayMode::Momentary`, activating the relay also starts a background timer
+    /// that deactivates it after the configured duration. Writing true again restarts
+    /// the timer, and writing false cancels it.
+    ///
+    /// If the relay is interlocked with others (see `Relays::set_interlock`), activating
+    /// it first switches off any energized relay in its group and waits out the group's
+    /// dead time. If another relay cannot be switched off, this one is left off and an
+    /// error is returned.
+    ///
+    /// If a settle time is set with `set_settle_time`, a write that changes the relay's
+    /// state blocks for that long before returning, so inputs read afterwards see the
+    /// contacts in their new position.
     ///
     /// # Arguments
     ///
@@ -105,32 +346,464 @@ impl Relay {
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or an error message if the operation failed
-    pub fn write(&mut self, open: bool) -> Result<(), &str> {
-        if self._auto_light {
-            let no_brightness = match open {
-                true => 1.0,
-                false => 0.0,
-            };
-            let nc_brightness = match open {
-                true => 0.0,
-                false => 1.0,
-            };
-            if self.no_led.is_some() {
-                let _ = self.no_led.as_mut().unwrap().set(no_brightness);
-            }
-            if self.nc_led.is_some() {
-                let _ = self.nc_led.as_mut().unwrap().set(nc_brightness);
+    /// * `Ok(())` - If the relay was set
+    /// * `Err(HatError::Gpio)` - If the relay, or an interlocked relay, could not be driven
+    /// * `Err(HatError::Io)` - If the momentary timer could not be started
+    pub fn write(&mut self, open: bool) -> Result<()> {
+        self.timer = None;
+        self.sync();
+        // Held until this relay is energized, so no other member can slip in between
+        let interlock = self.interlock.clone();
+        let _interlock = match (open, &interlock) {
+            (true, Some(interlock)) => {
+                let mut group = interlock
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                group.release_others(self.offset)?;
+                Some(group)
             }
-        }
-        match self.pin.set_state(match open {
-            true => PinState::High,
-            false => PinState::Low,
-        }) {
-            Ok(_) => {}
-            Err(_) => return Err("Unable to set value"),
+            _ => None,
         };
+        if self.leds_follow() {
+            self.update_leds(open);
+        }
+        self.pin
+            .lock()
+            .unwrap()
+            .set_state(match open {
+                true => PinState::High,
+                false => PinState::Low,
+            })
+            .map_err(|error| HatError::Gpio(format!("Unable to set value: {}", error)))?;
+        let switched = self.value != open;
+        if switched {
+            instrumentation::relay_switched(self.offset);
+            self.cycles += 1;
+        }
         self.value = open;
+        self.energized.store(open, Ordering::Relaxed);
+        if let (true, RelayMode::Momentary { duration }) = (open, self.mode) {
+            self.timer = Some(self.start_timer(duration)?);
+        }
+        if switched && !self.settle_time.is_zero() {
+            thread::sleep(self.settle_time);
+        }
+        Ok(())
+    }
+
+    /// Sets how long `write` waits after switching the relay for its contacts to settle.
+    ///
+    /// The relays on the Automation HAT take around 5-10ms to move and stop bouncing.
+    /// A control loop that switches a relay and then reads an input fed through its
+    /// contacts reads the old state if it does so immediately; with a settle time of
+    /// 10-20ms, every `write` that changes the relay's state returns only once the
+    /// contacts have settled. Writes that leave the state unchanged do not wait.
+    ///
+    /// The default of zero keeps `write` non-blocking. The automatic switch-off of
+    /// `RelayMode::Momentary` runs in the background and does not wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `settle_time` - How long to wait after each state change
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::time::Duration;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.relays.one.set_settle_time(Duration::from_millis(15));
+    /// hat.relays.one.write(true).unwrap();
+    /// // The contacts have closed, so the input wired through them reads the new state
+    /// let powered = hat.inputs.one.read().unwrap();
+    /// ```
+    pub fn set_settle_time(&mut self, settle_time: Duration) {
+        self.settle_time = settle_time;
+    }
+
+    /// Returns how long `write` waits after switching the relay.
+    pub fn settle_time(&self) -> Duration {
+        self.settle_time
+    }
+
+    /// Sets whether `write(true)` latches or switches back off on its own.
+    ///
+    /// Changing the mode cancels any pending automatic switch-off, leaving the relay
+    /// in its current state.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The new relay mode
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType, RelayMode};
+    /// use std::time::Duration;
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.relays.one.set_mode(RelayMode::Momentary {
+    ///     duration: Duration::from_millis(500),
+    /// });
+    /// hat.relays.one.write(true).unwrap(); // releases the door for 500ms
+    /// ```
+    pub fn set_mode(&mut self, mode: RelayMode) {
+        self.timer = None;
+        self.mode = mode;
+    }
+
+    /// Returns the mode currently used by this relay.
+    pub fn mode(&self) -> RelayMode {
+        self.mode
+    }
+
+    /// Returns whether the relay is currently energized.
+    ///
+    /// Unlike `value`, this reflects an automatic switch-off in momentary mode as
+    /// soon as it happens.
+    pub fn is_energized(&self) -> bool {
+        self.energized.load(Ordering::Relaxed)
+    }
+
+    /// Associates a digital input wired to this relay's contacts, so that
+    /// `AutomationHAT::confirmed_state` can check the relay actually moved.
+    ///
+    /// The board has no contact feedback of its own; it must be wired externally.
+    /// For a DC load circuit of 5-24V, connect the digital input (and the load supply's
+    /// ground to the board's GND) to the relay's NO terminal, so the input reads high
+    /// whenever the contacts are closed. For mains or other loads the inputs cannot
+    /// see directly, use an auxiliary contact on the contactor or an isolated sensor
+    /// that drives the input high while the load is switched on.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The digital input number as printed on the board (1-3)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the association was recorded
+    /// * `Err(HatError::InvalidChannel)` - If no such input exists
+    pub fn with_feedback(&mut self, input: usize) -> Result<()> {
+        Channel::Input(input).validate()?;
+        self.feedback_input = Some(input);
         Ok(())
     }
+
+    /// Removes the contact feedback association.
+    pub fn clear_feedback(&mut self) {
+        self.feedback_input = None;
+    }
+
+    /// Returns the board label of the digital input wired to this relay's contacts.
+    pub fn feedback_input(&self) -> Option<usize> {
+        self.feedback_input
+    }
+
+    /// Measures how long the relay takes to close its contacts, for diagnostics.
+    ///
+    /// Energizes the relay and polls `feedback` until it reads high, then switches the
+    /// relay off again. The relays on the Automation HAT actuate in around 5-10ms;
+    /// a relay whose latency creeps up over time is wearing and due for replacement.
+    ///
+    /// `feedback` must be wired to the relay's contacts as described in
+    /// `with_feedback`: a 5-24V supply to COM, the digital input to NO, and the
+    /// supply's ground to the board's GND, so the input reads high only while the
+    /// relay is energized. Timing starts once the relay line has been driven, so LED
+    /// updates, interlock dead time and the settle time are not included. The input
+    /// is read directly, without its debounce, so the first contact closure counts;
+    /// its LED is left alone.
+    ///
+    /// # Arguments
+    ///
+    /// * `feedback` - The digital input wired to the relay's NO terminal
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Duration)` - The time from energizing the relay to the input reading high
+    /// * `Err(HatError::Refused)` - If the relay is already energized
+    /// * `Err(HatError::Config)` - If the input reads high before the relay is
+    ///   energized, or does not read high within 250ms; check the wiring
+    /// * `Err(HatError::Gpio)` - If the relay could not be switched or the input read
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let latency = hat.relays.one.measure_latency(&mut hat.inputs.one)?;
+    /// println!("Relay 1 closed in {:?}", latency);
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn measure_latency(&mut self, feedback: &mut DigitalInput) -> Result<Duration> {
+        if self.is_energized() {
+            return Err(HatError::Refused(
+                "The relay must be off to measure its latency".to_string(),
+            ));
+        }
+        if feedback.sample()? {
+            return Err(HatError::Config(
+                "Feedback input reads high with the relay off; check the wiring".to_string(),
+            ));
+        }
+
+        let settle_time = std::mem::take(&mut self.settle_time);
+        let result = self.time_closure(feedback);
+        let released = self.write(false);
+        self.settle_time = settle_time;
+        let latency = result?;
+        released?;
+        Ok(latency)
+    }
+
+    /// Energizes the relay and returns how long `feedback` takes to read high.
+    fn time_closure(&mut self, feedback: &mut DigitalInput) -> Result<Duration> {
+        self.write(true)?;
+        let started = Instant::now();
+        loop {
+            if feedback.sample()? {
+                return Ok(started.elapsed());
+            }
+            if started.elapsed() > LATENCY_TIMEOUT {
+                return Err(HatError::Config(format!(
+                    "Feedback input did not read high within {:?}; check the wiring",
+                    LATENCY_TIMEOUT
+                )));
+            }
+        }
+    }
+
+    /// Brings `value` and the cycle count up to date after an automatic switch-off.
+    fn sync(&mut self) {
+        if self.value && !self.energized.load(Ordering::Relaxed) {
+            self.value = false;
+            self.cycles += 1;
+        }
+    }
+
+    /// Starts a thread that switches the relay off after `duration` unless cancelled.
+    fn start_timer(&self, duration: Duration) -> Result<Worker> {
+        let pin = self.pin.clone();
+        let energized = self.energized.clone();
+        let leds = match self.leds_follow() {
+            true => Some((
+                self.no_led.clone(),
+                self.nc_led.clone(),
+                self.led_convention,
+            )),
+            false => None,
+        };
+        let offset = self.offset;
+        Worker::spawn("automation-hat-relay-timer", move |cancel| {
+            cancel.sleep_until(Instant::now() + duration);
+            // Checked under the pin lock so a concurrent write cannot be undone
+            let mut pin = pin.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if cancel.is_set() || pin.set_low().is_err() {
+                return;
+            }
+            energized.store(false, Ordering::Relaxed);
+            instrumentation::relay_switched(offset);
+            if let Some((mut no_led, mut nc_led, convention)) = leds {
+                light_leds(no_led.as_mut(), nc_led.as_mut(), convention, false);
+            }
+        })
+    }
+
+    /// Returns whether the indicator LEDs automatically reflect the relay state.
+    pub fn auto_light(&self) -> bool {
+        self._auto_light
+    }
+
+    /// Returns the GPIO line offset of this relay on its gpiochip.
+    pub fn line_offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Returns how many times the relay has changed state.
+    ///
+    /// Only writes that actually switch the relay are counted, so repeatedly writing
+    /// the same state does not wear the contacts or advance the count. Compare this
+    /// against the relay's rated mechanical/electrical life for maintenance planning.
+    /// The count is kept in memory and starts at zero when the relay is created.
+    ///
+    /// # Returns
+    ///
+    /// The number of state changes since creation or the last `reset_cycle_count`
+    pub fn cycle_count(&self) -> u64 {
+        // Include an automatic switch-off that `sync` has not recorded yet
+        let pending = self.value && !self.is_energized();
+        self.cycles + u64::from(pending)
+    }
+
+    /// Resets the cycle count to zero, e.g. after the relay has been replaced.
+    pub fn reset_cycle_count(&mut self) {
+        self.sync();
+        self.cycles = 0;
+    }
+
+    /// Sets which indicator LED lights while the relay is energized.
+    ///
+    /// If auto_light is enabled, the LEDs are updated immediately to match the
+    /// current relay state under the new convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The LED that should represent the energized relay
+    pub fn set_led_convention(&mut self, convention: LedConvention) {
+        self.led_convention = convention;
+        if self.leds_follow() {
+            self.update_leds(self.value);
+        }
+        self.refresh_interlock();
+    }
+
+    /// Returns the LED convention currently used by this relay.
+    pub fn led_convention(&self) -> LedConvention {
+        self.led_convention
+    }
+
+    /// Keeps both indicator LEDs dark while the relay keeps switching normally.
+    ///
+    /// On installations where the relay LEDs are distracting, or their current is
+    /// unwanted, this turns both LEDs off immediately and stops the relay from
+    /// lighting them on any later switch, including interlock releases and momentary
+    /// switch-offs started after this call (one already pending keeps the setting it
+    /// started with). The LEDs can still be driven manually, e.g. through an `LED`
+    /// created for their `LedLabel`. Re-enabling them shows the current relay state
+    /// again if auto_light is enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `disabled` - Whether the indicator LEDs should be kept dark
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// hat.relays.one.set_leds_disabled(true);
+    /// hat.relays.one.write(true).unwrap(); // switches with both LEDs dark
+    /// ```
+    pub fn set_leds_disabled(&mut self, disabled: bool) {
+        self.leds_disabled = disabled;
+        self.sync();
+        match (disabled, self._auto_light) {
+            (true, _) => {
+                for led in [self.no_led.as_mut(), self.nc_led.as_mut()]
+                    .into_iter()
+                    .flatten()
+                {
+                    let _ = led.set_brightness_clamped(0.0);
+                }
+            }
+            (false, true) => self.update_leds(self.value),
+            (false, false) => {}
+        }
+        self.refresh_interlock();
+    }
+
+    /// Returns whether the indicator LEDs are kept dark.
+    pub fn leds_disabled(&self) -> bool {
+        self.leds_disabled
+    }
+
+    /// Swaps the indicator LEDs, e.g. when the board is reconfigured for another
+    /// variant, and shows the relay's state on the new ones.
+    pub(crate) fn replace_leds(
+        &mut self,
+        no_led: Option<LED>,
+        nc_led: Option<LED>,
+        auto_light: bool,
+    ) {
+        self.no_led = no_led;
+        self.nc_led = nc_led;
+        self._auto_light = auto_light;
+        self.sync();
+        if self.leds_follow() {
+            self.update_leds(self.value);
+        }
+        self.refresh_interlock();
+    }
+
+    /// Returns whether the relay lights its indicator LEDs when it switches.
+    fn leds_follow(&self) -> bool {
+        self._auto_light && !self.leds_disabled
+    }
+
+    /// Updates this relay's entry in its interlock group after an LED setting changed.
+    fn refresh_interlock(&self) {
+        if let Some(interlock) = &self.interlock {
+            let switch = self.off_switch();
+            interlock
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .refresh(switch);
+        }
+    }
+
+    /// Returns whether the relay belongs to an interlock group.
+    pub fn is_interlocked(&self) -> bool {
+        self.interlock.is_some()
+    }
+
+    /// Returns a handle that can switch this relay off on behalf of an interlock peer.
+    pub(crate) fn off_switch(&self) -> OffSwitch {
+        OffSwitch {
+            offset: self.offset,
+            pin: self.pin.clone(),
+            energized: self.energized.clone(),
+            leds: match self.leds_follow() {
+                true => Some((
+                    self.no_led.clone(),
+                    self.nc_led.clone(),
+                    self.led_convention,
+                )),
+                false => None,
+            },
+        }
+    }
+
+    /// Adds the relay to an interlock group, or removes it with `None`.
+    pub(crate) fn set_interlock(&mut self, interlock: Option<SharedInterlock>) {
+        self.interlock = interlock;
+    }
+
+    /// Lights the indicator LEDs to reflect the given relay state.
+    fn update_leds(&mut self, energized: bool) {
+        light_leds(
+            self.no_led.as_mut(),
+            self.nc_led.as_mut(),
+            self.led_convention,
+            energized,
+        );
+    }
+}
+
+/// Lights a relay's indicator LEDs to reflect the given relay state.
+fn light_leds(
+    no_led: Option<&mut LED>,
+    nc_led: Option<&mut LED>,
+    convention: LedConvention,
+    energized: bool,
+) {
+    let no_lit = match convention {
+        LedConvention::NormallyOpen => energized,
+        LedConvention::NormallyClosed => !energized,
+    };
+    let no_brightness = match no_lit {
+        true => 1.0,
+        false => 0.0,
+    };
+    let nc_brightness = match no_lit {
+        true => 0.0,
+        false => 1.0,
+    };
+    if let Some(led) = no_led {
+        let _ = led.set_brightness_clamped(no_brightness);
+    }
+    if let Some(led) = nc_led {
+        let _ = led.set_brightness_clamped(nc_brightness);
+    }
 }
@@ -0,0 +1,253 @@
+//! Background sampling of the analog inputs.
+//!
+//! This module provides `AnalogSampler`, which owns a thread that cycles through the
+//! available analog channels on the shared ADS1015 and caches the latest conversion of
+//! each. While it runs, reads on the board's `AnalogInput`s return the cached value
+//! immediately instead of performing a conversion, so a fast control loop neither
+//! waits on the I2C bus nor contends with other readers for the ADC lock.
+//!
+//! It also provides `AnalogWatcher`, returned by `AnalogInput::on_change`, which
+//! samples a single input in the background and calls back when its smoothed voltage
+//! moves far enough.
+
+use crate::analog_input::{self, SharedAdc};
+use crate::instrumentation;
+use crate::worker::{StopFlag, Worker};
+use crate::{AutomationHAT, HatError, Result};
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often an `AnalogWatcher` samples its input
+static WATCH_INTERVAL: Duration = Duration::from_millis(20);
+/// Number of samples averaged before change detection, a 100ms window
+const WATCH_WINDOW: usize = 5;
+
+/// Latest conversion of each ADS1015 channel, shared between the sampler thread and
+/// the analog inputs it serves.
+pub(crate) struct SampleCache {
+    /// Whether the sampler is still updating the cache
+    running: AtomicBool,
+    /// Latest raw reading or conversion error per channel, `None` until first sampled
+    latest: Mutex<[Option<Result<i16>>; 4]>,
+}
+
+impl SampleCache {
+    /// Returns the latest raw reading of `channel`, or `None` if the sampler has
+    /// stopped and the caller should convert directly.
+    pub(crate) fn latest(&self, channel: u8) -> Option<Result<i16>> {
+        if !self.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        let latest = self.latest.lock().unwrap();
+        Some(match latest.get(channel as usize).cloned().flatten() {
+            Some(result) => result,
+            None => Err(HatError::Analog(format!(
+                "Analog input {} has not been sampled yet",
+                channel + 1
+            ))),
+        })
+    }
+}
+
+/// Samples the analog inputs on a background thread.
+///
+/// Dropping the sampler stops the thread; the analog inputs then go back to
+/// converting on every read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AnalogSampler, AutomationHAT, HatType};
+/// use std::time::Duration;
+///
+/// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+/// let sampler = AnalogSampler::start(&mut hat, Duration::from_millis(10)).unwrap();
+/// loop {
+///     // Returns the most recent sample without touching the I2C bus
+///     let level = hat.analog_inputs.one.read().unwrap();
+///     println!("{}", level);
+/// }
+/// ```
+pub struct AnalogSampler {
+    /// Cache shared with the analog inputs
+    cache: Arc<SampleCache>,
+    /// The sampling thread
+    worker: Worker,
+}
+
+impl AnalogSampler {
+    /// Starts sampling every available analog input of `hat`.
+    ///
+    /// Each cycle converts every available channel in turn, then waits until
+    /// `interval` has passed since the cycle began. In continuous mode each channel
+    /// waits for the ADC to settle on it, about 1.4 ms on the ADS1015 and 17 ms on the
+    /// ADS1115, so a four-channel cycle takes about 6 ms or 70 ms and intervals shorter
+    /// than that run back to back. Each sample is a conversion of its own channel.
+    ///
+    /// Once started, `read` and the other read methods of the inputs return the
+    /// latest cached sample; they report an error until the first cycle completes,
+    /// and return the conversion error if the latest sample of a channel failed.
+    /// `read_ratiometric` still converts directly so both of its readings are taken
+    /// together.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board whose analog inputs should be sampled
+    /// * `interval` - Time between the starts of consecutive sampling cycles
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(AnalogSampler)` - The running sampler
+    /// * `Err(HatError)` - If the board has no ADC or the thread could not be started
+    pub fn start(hat: &mut AutomationHAT, interval: Duration) -> Result<Self> {
+        let Some(driver) = hat.analog_driver.clone() else {
+            return Err(HatError::Analog(
+                "Analog inputs are not available on this board".to_string(),
+            ));
+        };
+
+        let cache = Arc::new(SampleCache {
+            running: AtomicBool::new(true),
+            latest: Mutex::new([const { None }; 4]),
+        });
+
+        let mut channels = Vec::new();
+        for index in 1..=4 {
+            if let Some(input) = hat.analog_inputs.get_mut(index)
+                && input.is_available()
+            {
+                channels.push(input.attach_sampler(cache.clone()));
+            }
+        }
+
+        let thread_cache = cache.clone();
+        let worker = Worker::spawn("automation-hat-sampler", move |stop| {
+            sample_loop(driver, thread_cache, channels, interval, &stop)
+        })
+        // Without a thread the cache never fills, so the inputs go back to converting
+        .inspect_err(|_| cache.running.store(false, Ordering::SeqCst))?;
+
+        Ok(AnalogSampler { cache, worker })
+    }
+
+    /// Returns whether the sampler is still running.
+    pub fn is_running(&self) -> bool {
+        self.cache.running.load(Ordering::SeqCst)
+    }
+
+    /// Stops the sampling thread and waits for it to exit.
+    ///
+    /// The analog inputs go back to converting on every read.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.cache.running.store(false, Ordering::SeqCst);
+        self.worker.join();
+    }
+}
+
+impl Drop for AnalogSampler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Body of the sampling thread.
+fn sample_loop(
+    driver: SharedAdc,
+    cache: Arc<SampleCache>,
+    channels: Vec<u8>,
+    interval: Duration,
+    stop: &StopFlag,
+) {
+    while !stop.is_set() {
+        let cycle_started = Instant::now();
+        for &channel in &channels {
+            let started = instrumentation::analog_read_started();
+            let result = analog_input::convert(&mut driver.lock().unwrap(), channel);
+            if result.is_ok() {
+                instrumentation::analog_read_finished(channel, started);
+            }
+            cache.latest.lock().unwrap()[channel as usize] = Some(result);
+        }
+        stop.sleep_until(cycle_started + interval);
+    }
+}
+
+/// Watches one analog input on a background thread, returned by `AnalogInput::on_change`.
+///
+/// Dropping the watcher stops the thread.
+pub struct AnalogWatcher {
+    /// The watching thread
+    worker: Worker,
+}
+
+impl AnalogWatcher {
+    /// Starts watching `channel` of `driver`, converting each raw sample to a corrected
+    /// terminal voltage with `to_volts`.
+    ///
+    /// Samples come from `cache` while an `AnalogSampler` is running, and from a
+    /// direct conversion otherwise.
+    pub(crate) fn start(
+        driver: SharedAdc,
+        cache: Option<Arc<SampleCache>>,
+        channel: u8,
+        to_volts: impl Fn(i16) -> f64 + Send + 'static,
+        min_delta: f64,
+        callback: impl FnMut(f64) + Send + 'static,
+    ) -> Result<Self> {
+        let worker = Worker::spawn(
+            format!("automation-hat-analog-watch-{}", channel),
+            move |stop| watch_loop(driver, cache, channel, to_volts, min_delta, callback, &stop),
+        )?;
+        Ok(AnalogWatcher { worker })
+    }
+
+    /// Stops the watching thread and waits for it to exit.
+    ///
+    /// The callback is not called again once this returns.
+    pub fn stop(mut self) {
+        self.worker.join();
+    }
+}
+
+/// Body of the watching thread.
+fn watch_loop(
+    driver: SharedAdc,
+    cache: Option<Arc<SampleCache>>,
+    channel: u8,
+    to_volts: impl Fn(i16) -> f64,
+    min_delta: f64,
+    mut callback: impl FnMut(f64),
+    stop: &StopFlag,
+) {
+    let mut window = VecDeque::with_capacity(WATCH_WINDOW);
+    let mut notified: Option<f64> = None;
+    while !stop.is_set() {
+        let started = Instant::now();
+        let raw = match cache.as_ref().and_then(|cache| cache.latest(channel)) {
+            Some(result) => result,
+            None => analog_input::convert(&mut driver.lock().unwrap(), channel),
+        };
+        // Failed conversions are skipped rather than reported; the next one may succeed
+        if let Ok(raw) = raw {
+            if window.len() == WATCH_WINDOW {
+                window.pop_front();
+            }
+            window.push_back(to_volts(raw));
+            let smoothed = window.iter().sum::<f64>() / window.len() as f64;
+            if window.len() == WATCH_WINDOW
+                && notified.is_none_or(|last| (smoothed - last).abs() >= min_delta)
+            {
+                notified = Some(smoothed);
+                callback(smoothed);
+            }
+        }
+        stop.sleep_until(started + WATCH_INTERVAL);
+    }
+}
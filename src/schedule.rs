@@ -0,0 +1,244 @@
+//! Time-of-day dimming of the Automation HAT LEDs.
+//!
+//! This module provides `BrightnessSchedule`, a list of time-of-day ranges each mapped
+//! to a global LED scale factor, and `AutomationHAT::set_brightness_schedule`, which
+//! applies it from a background thread so indicators dim automatically at night.
+//!
+//! # Time zones
+//!
+//! The standard library can read the system clock but not the system time zone, so
+//! schedule times are UTC plus a fixed offset set with
+//! [`BrightnessSchedule::utc_offset`]. The offset does not follow daylight saving
+//! changes; adjust it (or the ranges) when the clocks change.
+
+use crate::lights::{self, LedBank};
+use crate::worker::{StopFlag, Worker};
+use crate::{AutomationHAT, Result};
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How often the schedule thread re-evaluates the current time
+static CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Seconds in a day
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Maps time-of-day ranges to a global LED scale factor.
+///
+/// Ranges are checked in the order they were added and the first one containing the
+/// current time wins; outside every range the scale is 1.0. A range whose end is
+/// before its start wraps past midnight.
+///
+/// Times are UTC plus a fixed offset, since the standard library cannot read the
+/// system time zone. The offset does not follow daylight saving changes.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::BrightnessSchedule;
+/// use std::time::Duration;
+///
+/// // Dim to 10% from 22:00 to 07:00 local time in UTC+1
+/// let schedule = BrightnessSchedule::new()
+///     .utc_offset(Duration::from_secs(60 * 60), true)
+///     .range((22, 0), (7, 0), 0.1);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BrightnessSchedule {
+    /// Start second of day, end second of day and scale factor of each range
+    ranges: Vec<(u32, u32, f64)>,
+    /// Offset of local time from UTC in seconds
+    utc_offset: i64,
+}
+
+impl BrightnessSchedule {
+    /// Creates an empty schedule in UTC, which leaves the LEDs unscaled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the offset of the schedule's times from UTC.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The size of the offset
+    /// * `ahead` - Whether local time is ahead of UTC (east of Greenwich)
+    pub fn utc_offset(mut self, offset: Duration, ahead: bool) -> Self {
+        let seconds = offset.as_secs() as i64 % DAY_SECONDS;
+        self.utc_offset = match ahead {
+            true => seconds,
+            false => -seconds,
+        };
+        self
+    }
+
+    /// Adds a time-of-day range with its LED scale factor.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - Hour and minute the range begins, inclusive
+    /// * `end` - Hour and minute the range ends, exclusive
+    /// * `scale` - LED scale factor within the range, clamped to 0.0-1.0
+    pub fn range(mut self, start: (u8, u8), end: (u8, u8), scale: f64) -> Self {
+        self.ranges.push((
+            second_of_day(start),
+            second_of_day(end),
+            scale.clamp(0.0, 1.0),
+        ));
+        self
+    }
+
+    /// Returns the scale factor for a given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time to evaluate, usually `SystemTime::now()`
+    ///
+    /// # Returns
+    ///
+    /// The scale factor of the first range containing `time`, or 1.0 if none does
+    pub fn scale_at(&self, time: SystemTime) -> f64 {
+        let utc = match time.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        };
+        let now = (utc + self.utc_offset).rem_euclid(DAY_SECONDS) as u32;
+        self.ranges
+            .iter()
+            .find(|(start, end, _)| match start <= end {
+                true => (*start..*end).contains(&now),
+                false => now >= *start || now < *end,
+            })
+            .map_or(1.0, |(_, _, scale)| *scale)
+    }
+}
+
+impl AutomationHAT {
+    /// Dims the LEDs automatically according to a time-of-day schedule.
+    ///
+    /// A background thread applies the schedule's scale factor through
+    /// `set_led_scale` straight away and then re-checks the clock every 30 seconds,
+    /// so a range takes effect up to 30 seconds after it starts. Setting a new
+    /// schedule replaces the previous one. Passing `None` disables scheduling and
+    /// restores the LEDs to an unscaled brightness of 1.0.
+    ///
+    /// Schedule times use the system clock, interpreted in the schedule's fixed UTC
+    /// offset (see `BrightnessSchedule::utc_offset`).
+    ///
+    /// # Arguments
+    ///
+    /// * `schedule` - The schedule to apply, or `None` to disable scheduling
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the schedule was started or disabled
+    /// * `Err(HatError::NoLeds)` - If the board has no LEDs
+    /// * `Err(HatError)` - If the thread could not be started
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, BrightnessSchedule, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// let night = BrightnessSchedule::new().range((22, 0), (7, 0), 0.1);
+    /// hat.set_brightness_schedule(Some(night))?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn set_brightness_schedule(&mut self, schedule: Option<BrightnessSchedule>) -> Result<()> {
+        // Stop the previous thread before touching the scale, so it cannot overwrite it
+        self.brightness_schedule = None;
+        let driver = self.leds()?.clone();
+
+        let Some(schedule) = schedule else {
+            return self.set_led_scale(1.0);
+        };
+
+        self.brightness_schedule = Some(Worker::spawn("automation-hat-brightness", move |stop| {
+            schedule_loop(&driver, &schedule, &stop)
+        })?);
+        Ok(())
+    }
+}
+
+/// Body of the schedule thread.
+fn schedule_loop(driver: &Arc<LedBank>, schedule: &BrightnessSchedule, stop: &StopFlag) {
+    while !stop.is_set() {
+        lights::set_master_scale(schedule.scale_at(SystemTime::now()));
+        // A failed write is retried on the next check
+        let _ = lights::write_frame(driver, &[]);
+        stop.sleep_until(Instant::now() + CHECK_INTERVAL);
+    }
+}
+
+fn second_of_day((hour, minute): (u8, u8)) -> u32 {
+    (hour as u32 % 24) * 3600 + (minute as u32 % 60) * 60
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The given UTC hour and minute on 1 January 2024
+    fn utc(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_704_067_200 + hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn empty_schedule_leaves_leds_unscaled() {
+        assert_eq!(BrightnessSchedule::new().scale_at(utc(12, 0)), 1.0);
+    }
+
+    #[test]
+    fn range_includes_start_and_excludes_end() {
+        let schedule = BrightnessSchedule::new().range((8, 0), (17, 30), 0.5);
+        assert_eq!(schedule.scale_at(utc(7, 59)), 1.0);
+        assert_eq!(schedule.scale_at(utc(8, 0)), 0.5);
+        assert_eq!(schedule.scale_at(utc(17, 29)), 0.5);
+        assert_eq!(schedule.scale_at(utc(17, 30)), 1.0);
+    }
+
+    #[test]
+    fn range_ending_before_it_starts_wraps_past_midnight() {
+        let schedule = BrightnessSchedule::new().range((22, 0), (7, 0), 0.1);
+        assert_eq!(schedule.scale_at(utc(23, 0)), 0.1);
+        assert_eq!(schedule.scale_at(utc(0, 0)), 0.1);
+        assert_eq!(schedule.scale_at(utc(6, 59)), 0.1);
+        assert_eq!(schedule.scale_at(utc(7, 0)), 1.0);
+        assert_eq!(schedule.scale_at(utc(21, 59)), 1.0);
+    }
+
+    #[test]
+    fn first_matching_range_wins() {
+        let schedule = BrightnessSchedule::new()
+            .range((20, 0), (23, 0), 0.3)
+            .range((18, 0), (6, 0), 0.1);
+        assert_eq!(schedule.scale_at(utc(19, 0)), 0.1);
+        assert_eq!(schedule.scale_at(utc(21, 0)), 0.3);
+    }
+
+    #[test]
+    fn offset_shifts_utc_to_local_time() {
+        let hour = Duration::from_secs(3600);
+        let night = BrightnessSchedule::new().range((22, 0), (7, 0), 0.1);
+        // 21:30 UTC is 22:30 in UTC+1 and 19:30 in UTC-2
+        assert_eq!(
+            night.clone().utc_offset(hour, true).scale_at(utc(21, 30)),
+            0.1
+        );
+        assert_eq!(night.utc_offset(hour * 2, false).scale_at(utc(21, 30)), 1.0);
+        // 00:30 UTC is 22:30 the previous day in UTC-2
+        let night = BrightnessSchedule::new().range((22, 0), (23, 0), 0.1);
+        assert_eq!(night.utc_offset(hour * 2, false).scale_at(utc(0, 30)), 0.1);
+    }
+
+    #[test]
+    fn scale_is_clamped_to_the_unit_range() {
+        let schedule =
+            BrightnessSchedule::new()
+                .range((0, 0), (12, 0), 2.0)
+                .range((12, 0), (0, 0), -1.0);
+        assert_eq!(schedule.scale_at(utc(6, 0)), 1.0);
+        assert_eq!(schedule.scale_at(utc(18, 0)), 0.0);
+    }
+}
@@ -0,0 +1,217 @@
+//! Power-on self-test for Automation HAT boards.
+//!
+//! This module provides `AutomationHAT::self_test`, which exercises every relay,
+//! digital output, LED and analog input in turn and reports which of them responded,
+//! and `AutomationHAT::loopback_test`, which checks a digital output against a digital
+//! input wired back to it.
+
+use crate::lights::{self, LED, LedLabel};
+use crate::{AutomationHAT, HatError, Result};
+
+use std::thread;
+use std::time::Duration;
+
+/// How long each relay and digital output is held on during the self-test
+static PULSE_TIME: Duration = Duration::from_millis(100);
+/// How long each LED is lit during the self-test
+static BLINK_TIME: Duration = Duration::from_millis(50);
+/// How long a loopback test waits after switching the output before reading the input
+static LOOPBACK_SETTLE_TIME: Duration = Duration::from_millis(10);
+
+/// Outcome of a single self-test check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckResult {
+    /// Human-readable name of the component that was checked (e.g. "Relay 1")
+    pub name: String,
+    /// The error raised by the component, or `None` if it responded
+    pub error: Option<HatError>,
+}
+
+impl CheckResult {
+    /// Returns whether the component responded without error.
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Per-component results of `AutomationHAT::self_test`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SelfTestReport {
+    /// Results for every component checked, in test order
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    /// Returns whether every check passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(CheckResult::passed)
+    }
+
+    /// Returns the checks that failed.
+    pub fn failures(&self) -> impl Iterator<Item = &CheckResult> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+
+    /// Records the outcome of a check.
+    fn record(&mut self, name: String, result: Result<()>) {
+        self.checks.push(CheckResult {
+            name,
+            error: result.err(),
+        });
+    }
+}
+
+impl AutomationHAT {
+    /// Runs a self-test of the board and reports which components responded.
+    ///
+    /// Each relay and digital output is switched on briefly and back off, the LED
+    /// driver is probed and each LED blinked, and each analog input is read. When the
+    /// test finishes, whether or not it passed, every relay and output is off and the
+    /// LEDs show what they showed before it started.
+    ///
+    /// A check passes when the component accepted its command on the GPIO line or I2C
+    /// bus. Outputs are not verified electrically: a relay with an open coil, a
+    /// disconnected output or a dead LED still passes. Use `loopback_test` to check an
+    /// output against an input wired back to it.
+    ///
+    /// **Disconnect all loads before running the self-test.** Relays and outputs are
+    /// energized during the test, which will operate anything wired to them. As a
+    /// guard, the test refuses to start while any relay or output is already on,
+    /// since that indicates the board is controlling something.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SelfTestReport)` - The per-component results; check `passed()` for the verdict
+    /// * `Err(HatError)` - If the test refused to start because the board is in use
+    pub fn self_test(&mut self) -> Result<SelfTestReport> {
+        let relays_on = [&self.relays.one, &self.relays.two, &self.relays.three]
+            .iter()
+            .any(|relay| relay.is_energized());
+        let outputs_on = [&self.outputs.one, &self.outputs.two, &self.outputs.three]
+            .iter()
+            .any(|output| output.value);
+        if relays_on || outputs_on {
+            return Err(HatError::Refused(
+                "Self-test will not run while a relay or output is on".to_string(),
+            ));
+        }
+
+        let mut report = SelfTestReport::default();
+        let leds = self.led_state();
+
+        for index in 1..=3 {
+            if let Some(relay) = self.relays.get_mut(index) {
+                let result = relay.write(true).and_then(|_| {
+                    thread::sleep(PULSE_TIME);
+                    relay.write(false)
+                });
+                report.record(format!("Relay {}", index), result);
+            }
+        }
+
+        for index in 1..=3 {
+            if let Some(output) = self.outputs.get_mut(index) {
+                let result = output.write(true).and_then(|_| {
+                    thread::sleep(PULSE_TIME);
+                    output.write(false)
+                });
+                report.record(format!("Output {}", index), result);
+            }
+        }
+
+        if let Some(driver) = &self.led_driver {
+            report.record("LED driver".to_string(), lights::probe(driver));
+            for label in LedLabel::ALL {
+                let mut led = LED::new(driver.driver().clone(), label.channel());
+                let result = led.on().and_then(|_| {
+                    thread::sleep(BLINK_TIME);
+                    led.off()
+                });
+                report.record(format!("LED {:?}", label), result);
+            }
+        }
+
+        for index in 1..=4 {
+            if let Some(input) = self.analog_inputs.get_mut(index) {
+                let result = input.read_voltage().map(|_| ());
+                report.record(format!("Analog {}", index), result);
+            }
+        }
+
+        // Leave the board in a known safe state regardless of the results, with the
+        // LEDs showing what they did before the test
+        let _ = self.all_off();
+        if self.led_driver.is_some() {
+            let _ = self.restore_led_state(&leds);
+        }
+
+        Ok(report)
+    }
+
+    /// Checks that a digital input follows a digital output wired back to it.
+    ///
+    /// **This test requires an external jumper** from the output terminal to the input
+    /// terminal, plus a pull-up from the input to the 5V supply: the outputs are
+    /// open-collector sinking drivers, so the input reads low while the output is on
+    /// and is only pulled high while it is off. Remove any load from the output first,
+    /// as it is switched during the test.
+    ///
+    /// The output is switched on and then off, and the input is read after each
+    /// change. The test passes if the input reads a different level in each state,
+    /// so it does not depend on the output's polarity. The output is restored to
+    /// its previous state afterwards, even if the test fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_idx` - The digital output to drive (1-3)
+    /// * `input_idx` - The digital input wired to it (1-3)
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If the input followed the output
+    /// * `Ok(false)` - If the input did not change with the output
+    /// * `Err(HatError)` - If either index is invalid or a line could not be driven or read
+    pub fn loopback_test(&mut self, output_idx: usize, input_idx: usize) -> Result<bool> {
+        if self.inputs.get_mut(input_idx).is_none() {
+            return Err(HatError::InvalidChannel(input_idx));
+        }
+        let previous = match self.outputs.get_mut(output_idx) {
+            Some(output) => output.value,
+            None => return Err(HatError::InvalidChannel(output_idx)),
+        };
+
+        let result = self
+            .sample_loopback(output_idx, input_idx, true)
+            .and_then(|when_on| {
+                let when_off = self.sample_loopback(output_idx, input_idx, false)?;
+                Ok(when_on != when_off)
+            });
+
+        // Leave the output as it was found regardless of the result
+        let restored = self
+            .outputs
+            .get_mut(output_idx)
+            .map_or(Ok(()), |output| output.write(previous));
+
+        let matched = result?;
+        restored?;
+        Ok(matched)
+    }
+
+    /// Drives an output to `state`, waits for it to settle and reads an input.
+    fn sample_loopback(
+        &mut self,
+        output_idx: usize,
+        input_idx: usize,
+        state: bool,
+    ) -> Result<bool> {
+        if let Some(output) = self.outputs.get_mut(output_idx) {
+            output.write(state)?;
+        }
+        thread::sleep(LOOPBACK_SETTLE_TIME);
+        match self.inputs.get_mut(input_idx) {
+            Some(input) => input.read(),
+            None => Err(HatError::InvalidChannel(input_idx)),
+        }
+    }
+}
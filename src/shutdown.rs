@@ -0,0 +1,89 @@
+//! Signal-driven safe shutdown for Automation HAT boards.
+//!
+//! This module is only compiled with the `signal-hook` cargo feature. It installs a
+//! handler for SIGTERM and SIGINT that switches every relay and digital output off
+//! before the process exits, so stopping a service does not leave loads energized.
+//!
+//! # Caveats
+//!
+//! Rust code cannot safely do GPIO work inside a real signal handler, so the signals
+//! are received on a dedicated background thread via `signal_hook::iterator::Signals`.
+//! That thread must lock the shared `AutomationHAT`; if another thread holds the lock
+//! and never releases it, shutdown will wait for it. After the board is made safe the
+//! process exits with `128 + signal` without running destructors on other threads,
+//! so `HatConfig::all_off_on_drop` does not apply; the two complement each other,
+//! covering signals and ordinary returns or panics respectively. A relay or output
+//! that fails to switch off is counted by the `metrics` instrumentation, as there is
+//! no caller left to return the error to. SIGKILL and power loss cannot be
+//! intercepted.
+
+use crate::instrumentation;
+use crate::worker::lock;
+use crate::{AutomationHAT, HatError, Result};
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Whether the shutdown handler has already been installed in this process
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+impl AutomationHAT {
+    /// Installs a SIGTERM/SIGINT handler that switches all relays and outputs off
+    /// before the process exits.
+    ///
+    /// The handler is installed at most once per process; later calls return `Ok(())`
+    /// without installing another handler.
+    ///
+    /// Signals are received on a background thread rather than in signal context, as
+    /// GPIO work is not async-signal-safe. That thread must lock `hat`, so shutdown
+    /// waits for any other thread holding the lock. Once the board is safe the process
+    /// exits with `128 + signal`, without dropping the board. SIGKILL and power loss
+    /// cannot be intercepted.
+    ///
+    /// Enable `HatConfig::all_off_on_drop` as well to make the board safe when the
+    /// application returns or panics instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board to make safe, shared with the rest of the application
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the handler is installed
+    /// * `Err(HatError)` - If the signal handlers could not be registered
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let hat = Arc::new(Mutex::new(AutomationHAT::new(HatType::AutomationHAT)));
+    /// AutomationHAT::install_signal_safe_shutdown(hat.clone())?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn install_signal_safe_shutdown(hat: Arc<Mutex<AutomationHAT>>) -> Result<()> {
+        if INSTALLED.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let mut signals = Signals::new([SIGTERM, SIGINT]).map_err(|error| {
+            INSTALLED.store(false, Ordering::SeqCst);
+            HatError::Io(format!("Failed to register signal handlers: {}", error))
+        })?;
+
+        thread::spawn(move || {
+            if let Some(signal) = signals.forever().next() {
+                if lock(&hat).all_off().is_err() {
+                    instrumentation::safe_shutdown_failed();
+                }
+                std::process::exit(128 + signal);
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+//! Supply rail monitoring for Automation HAT boards.
+//!
+//! This module provides `AutomationHAT::supply_voltage` and `supply_ok`, which read
+//! the fourth analog channel as a measurement of the board's supply rail. The channel
+//! must be tied to the rail being monitored, either directly for the 3.3V rail or
+//! through a divider with its full-scale voltage set via
+//! [`HatConfig::analog_max_value`](crate::HatConfig::analog_max_value).
+
+use crate::{AutomationHAT, Result};
+
+/// Fraction of the nominal voltage the rail may deviate by and still be healthy
+const SUPPLY_TOLERANCE: f64 = 0.1;
+
+impl AutomationHAT {
+    /// Reads the voltage of the supply rail tied to the fourth analog channel.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The measured rail voltage in volts
+    /// * `Err(HatError)` - If the analog inputs are unavailable or the read failed
+    pub fn supply_voltage(&mut self) -> Result<f64> {
+        self.analog_inputs.four.read_voltage()
+    }
+
+    /// Checks whether the supply rail is within 10% of its nominal voltage.
+    ///
+    /// The nominal voltage is the fourth channel's full-scale value (`max_value`),
+    /// 3.3V by default. A sagging rail, for example from relay coil inrush, reads as
+    /// unhealthy, letting a controller back off before the board browns out.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - If the rail is within tolerance
+    /// * `Ok(false)` - If the rail is too low or too high
+    /// * `Err(HatError)` - If the rail could not be read
+    pub fn supply_ok(&mut self) -> Result<bool> {
+        let nominal = self.analog_inputs.four.max_value;
+        let volts = self.supply_voltage()?;
+        Ok((volts - nominal).abs() <= nominal * SUPPLY_TOLERANCE)
+    }
+}
@@ -0,0 +1,470 @@
+//! Periodic telemetry logging for Automation HAT boards.
+//!
+//! This module provides `TelemetryLogger`, which samples a chosen set of channels on a
+//! background thread and appends one row per sample to any `std::io::Write` sink, as
+//! CSV with a header row or as InfluxDB line protocol. Write errors are counted and
+//! reported through the logger instead of stopping it, so a full disk or a dropped
+//! network connection does not take the sampler down. Channels can be given a
+//! `ChannelMeta` with a name, unit and scale, so the output describes the quantities
+//! measured rather than the board's terminals.
+
+use crate::channel::Channel;
+use crate::worker::{StopFlag, Worker, lock};
+use crate::{AutomationHAT, HatError, Result};
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Output format of a `TelemetryLogger`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TelemetryFormat {
+    /// Comma-separated values with a header row; failed reads leave the field empty
+    #[default]
+    Csv,
+    /// InfluxDB line protocol; failed reads are omitted from the line
+    LineProtocol,
+}
+
+/// Describes what a channel measures, for self-describing telemetry output.
+///
+/// The logged value is the channel's reading multiplied by `scale`, and its column or
+/// field is named `<name>_<unit>`, or just `<name>` without a unit.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::ChannelMeta;
+///
+/// // A level sensor reading 0-10V over 0-200cm, logged as `tank_level_cm`
+/// let meta = ChannelMeta::new("tank_level").unit("cm").scale(20.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelMeta {
+    /// Name of the measured quantity, e.g. `tank_level`
+    pub name: String,
+    /// Unit of the logged value, e.g. `cm`, appended to the column name
+    pub unit: Option<String>,
+    /// Factor applied to the channel's reading (volts for analog inputs, 1 or 0 for
+    /// the others) to give the logged value
+    pub scale: f64,
+}
+
+impl ChannelMeta {
+    /// Creates metadata naming the channel, with no unit and a scale of 1.0.
+    pub fn new(name: impl Into<String>) -> Self {
+        ChannelMeta {
+            name: name.into(),
+            unit: None,
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the unit appended to the column name.
+    pub fn unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = Some(unit.into());
+        self
+    }
+
+    /// Sets the factor applied to the channel's reading.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns the column or field name, e.g. `tank_level_cm`.
+    pub fn column(&self) -> String {
+        match &self.unit {
+            Some(unit) => format!("{}_{}", self.name, unit),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Checks that the name and unit are usable as CSV columns and line protocol fields.
+    fn validate(&self) -> Result<()> {
+        let valid = |part: &str| {
+            !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        };
+        if !valid(&self.name) || !self.unit.as_deref().is_none_or(valid) {
+            return Err(HatError::Config(format!(
+                "Telemetry column {:?} must be non-empty letters, digits and underscores",
+                self.column()
+            )));
+        }
+        if !self.scale.is_finite() {
+            return Err(HatError::Config(format!(
+                "Scale of telemetry column {} must be finite",
+                self.column()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Sampling and formatting options for `TelemetryLogger`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{Channel, TelemetryConfig};
+/// use std::time::Duration;
+///
+/// let config = TelemetryConfig::new()
+///     .interval(Duration::from_secs(10))
+///     .channels([Channel::Analog(1), Channel::Input(1)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// Time between the starts of consecutive samples
+    pub interval: Duration,
+    /// The channels recorded in each row, in column order
+    pub channels: Vec<Channel>,
+    /// Output format
+    pub format: TelemetryFormat,
+    /// Measurement name used by the line protocol format
+    pub measurement: String,
+    /// Names, units and scales of channels; channels without an entry are logged as
+    /// read, under their board name such as `analog_1`
+    pub meta: HashMap<Channel, ChannelMeta>,
+}
+
+impl TelemetryConfig {
+    /// Creates a configuration with default options.
+    ///
+    /// # Returns
+    ///
+    /// A `TelemetryConfig` sampling every second, recording the three digital inputs
+    /// and four analog inputs as CSV
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the time between the starts of consecutive samples.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the channels recorded in each row, in column order.
+    pub fn channels(mut self, channels: impl IntoIterator<Item = Channel>) -> Self {
+        self.channels = channels.into_iter().collect();
+        self
+    }
+
+    /// Sets the output format.
+    pub fn format(mut self, format: TelemetryFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the measurement name used by the line protocol format.
+    pub fn measurement(mut self, measurement: impl Into<String>) -> Self {
+        self.measurement = measurement.into();
+        self
+    }
+
+    /// Describes what `channel` measures, replacing any earlier description.
+    pub fn meta(mut self, channel: Channel, meta: ChannelMeta) -> Self {
+        self.meta.insert(channel, meta);
+        self
+    }
+
+    /// Returns the column or field name of a channel, e.g. `analog_1` or `tank_level_cm`.
+    fn column(&self, channel: Channel) -> String {
+        match self.meta.get(&channel) {
+            Some(meta) => meta.column(),
+            None => channel.to_string().to_ascii_lowercase().replace(' ', "_"),
+        }
+    }
+
+    /// Checks the interval, the channels and their metadata, and that no two columns
+    /// share a name.
+    fn validate(&self) -> Result<()> {
+        if self.interval.is_zero() {
+            return Err(HatError::Config(
+                "Telemetry interval must be greater than zero".to_string(),
+            ));
+        }
+        for channel in &self.channels {
+            channel.validate()?;
+        }
+        for meta in self.meta.values() {
+            meta.validate()?;
+        }
+        let mut columns: Vec<String> = self.channels.iter().map(|c| self.column(*c)).collect();
+        columns.sort();
+        if let Some(pair) = columns.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(HatError::Config(format!(
+                "Telemetry column {} is used by more than one channel",
+                pair[0]
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            interval: Duration::from_secs(1),
+            channels: (1..=3)
+                .map(Channel::Input)
+                .chain((1..=4).map(Channel::Analog))
+                .collect(),
+            format: TelemetryFormat::default(),
+            measurement: "automation_hat".to_string(),
+            meta: HashMap::new(),
+        }
+    }
+}
+
+/// A running telemetry logger, stopped with `stop` or when dropped.
+pub struct TelemetryLogger {
+    /// Number of rows that could not be written
+    write_errors: Arc<AtomicU64>,
+    /// The most recent write error
+    last_error: Arc<Mutex<Option<String>>>,
+    /// The logging thread
+    worker: Worker,
+}
+
+impl TelemetryLogger {
+    /// Starts logging the board to `sink`.
+    ///
+    /// For CSV, a header row is written first. Each row starts with the sample time;
+    /// CSV uses Unix seconds with millisecond precision and line protocol uses Unix
+    /// nanoseconds. Digital inputs, relays and outputs are recorded as 1 or 0 and
+    /// analog inputs as terminal voltage. The board is locked only while sampling,
+    /// and the sink is flushed after every row.
+    ///
+    /// # Arguments
+    ///
+    /// * `hat` - The board to sample, shared with the rest of the application
+    /// * `sink` - Where rows are written, e.g. a `File` or `TcpStream`
+    /// * `config` - Sampling and formatting options
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(TelemetryLogger)` - A handle to the running logger
+    /// * `Err(HatError)` - If the interval is zero, a channel is out of range, a
+    ///   `ChannelMeta` is invalid, two channels share a column name, or the thread could
+    ///   not be started
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType, TelemetryConfig, TelemetryLogger};
+    /// use std::fs::File;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let hat = Arc::new(Mutex::new(AutomationHAT::new(HatType::AutomationHAT)));
+    /// let file = File::create("telemetry.csv").unwrap();
+    /// let logger = TelemetryLogger::start(hat, file, TelemetryConfig::new())?;
+    /// # Ok::<(), automation_hat::HatError>(())
+    /// ```
+    pub fn start<W>(
+        hat: Arc<Mutex<AutomationHAT>>,
+        sink: W,
+        config: TelemetryConfig,
+    ) -> Result<Self>
+    where
+        W: Write + Send + 'static,
+    {
+        config.validate()?;
+
+        let write_errors = Arc::new(AtomicU64::new(0));
+        let last_error = Arc::new(Mutex::new(None));
+
+        let worker = {
+            let (write_errors, last_error) = (write_errors.clone(), last_error.clone());
+            Worker::spawn("automation-hat-telemetry", move |stop| {
+                log_loop(&hat, sink, &config, &stop, &write_errors, &last_error)
+            })?
+        };
+
+        Ok(TelemetryLogger {
+            write_errors,
+            last_error,
+            worker,
+        })
+    }
+
+    /// Returns how many rows could not be written to the sink.
+    pub fn write_errors(&self) -> u64 {
+        self.write_errors.load(Ordering::Relaxed)
+    }
+
+    /// Returns the most recent write error, if any row failed to write.
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Stops the logger and waits for the logging thread to exit.
+    pub fn stop(mut self) {
+        self.worker.join();
+    }
+}
+
+/// Writes the header, then samples and writes a row every interval until stopped.
+fn log_loop<W: Write>(
+    hat: &Mutex<AutomationHAT>,
+    mut sink: W,
+    config: &TelemetryConfig,
+    stop: &StopFlag,
+    write_errors: &AtomicU64,
+    last_error: &Mutex<Option<String>>,
+) {
+    let record = |sink: &mut W, row: String| {
+        if let Err(error) = sink.write_all(row.as_bytes()).and_then(|_| sink.flush()) {
+            write_errors.fetch_add(1, Ordering::Relaxed);
+            *last_error
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(error.to_string());
+        }
+    };
+
+    if config.format == TelemetryFormat::Csv {
+        record(&mut sink, csv_header(config));
+    }
+
+    while !stop.is_set() {
+        let started = Instant::now();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let values: Vec<Option<f64>> = {
+            let mut hat = lock(hat);
+            config
+                .channels
+                .iter()
+                .map(|channel| {
+                    let scale = config.meta.get(channel).map_or(1.0, |meta| meta.scale);
+                    sample(&mut hat, *channel).map(|value| value * scale)
+                })
+                .collect()
+        };
+
+        let row = match config.format {
+            TelemetryFormat::Csv => csv_row(timestamp, &values),
+            TelemetryFormat::LineProtocol => line_protocol_row(config, timestamp, &values),
+        };
+        // Rows with no fields are not valid line protocol, so they are skipped
+        if !row.is_empty() {
+            record(&mut sink, row);
+        }
+
+        stop.sleep_until(started + config.interval);
+    }
+}
+
+/// Reads one channel; inputs and outputs are 1 or 0 and analog inputs are volts.
+fn sample(hat: &mut AutomationHAT, channel: Channel) -> Option<f64> {
+    let index = channel.index();
+    let flag = |on: bool| if on { 1.0 } else { 0.0 };
+    match channel {
+        Channel::Input(_) => hat.inputs.get_mut(index)?.read().ok().map(flag),
+        Channel::Relay(_) => hat
+            .relays
+            .get_mut(index)
+            .map(|relay| flag(relay.is_energized())),
+        Channel::Output(_) => hat.outputs.get_mut(index).map(|output| flag(output.value)),
+        Channel::Analog(_) => hat.analog_inputs.get_mut(index)?.read_voltage().ok(),
+    }
+}
+
+fn csv_header(config: &TelemetryConfig) -> String {
+    let mut header = String::from("timestamp");
+    for channel in &config.channels {
+        header.push(',');
+        header.push_str(&config.column(*channel));
+    }
+    header.push('\n');
+    header
+}
+
+fn csv_row(timestamp: Duration, values: &[Option<f64>]) -> String {
+    let mut row = format!("{:.3}", timestamp.as_secs_f64());
+    for value in values {
+        row.push(',');
+        if let Some(value) = value {
+            row.push_str(&format_value(*value));
+        }
+    }
+    row.push('\n');
+    row
+}
+
+fn line_protocol_row(
+    config: &TelemetryConfig,
+    timestamp: Duration,
+    values: &[Option<f64>],
+) -> String {
+    let fields: Vec<String> = config
+        .channels
+        .iter()
+        .zip(values)
+        .filter_map(|(channel, value)| {
+            value.map(|value| format!("{}={}", config.column(*channel), format_value(value)))
+        })
+        .collect();
+    if fields.is_empty() {
+        return String::new();
+    }
+    let measurement = config.measurement.replace(',', "\\,").replace(' ', "\\ ");
+    format!(
+        "{} {} {}\n",
+        measurement,
+        fields.join(","),
+        timestamp.as_nanos()
+    )
+}
+
+/// Formats a reading, keeping 1/0 states free of decimals.
+fn format_value(value: f64) -> String {
+    match value.fract() == 0.0 {
+        true => format!("{}", value),
+        false => format!("{:.3}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_row_leaves_unread_values_empty() {
+        let row = csv_row(
+            Duration::from_millis(1_500),
+            &[Some(1.0), None, Some(12.3456)],
+        );
+        assert_eq!(row, "1.500,1,,12.346\n");
+    }
+
+    #[test]
+    fn line_protocol_row_names_fields_by_column() {
+        let config = TelemetryConfig::new()
+            .channels([Channel::Input(1), Channel::Analog(2), Channel::Analog(1)])
+            .measurement("pump house")
+            .meta(
+                Channel::Analog(1),
+                ChannelMeta::new("tank_level").unit("cm"),
+            );
+        let row = line_protocol_row(
+            &config,
+            Duration::from_secs(2),
+            &[Some(0.0), None, Some(81.25)],
+        );
+        assert_eq!(
+            row,
+            "pump\\ house input_1=0,tank_level_cm=81.250 2000000000\n"
+        );
+    }
+
+    #[test]
+    fn line_protocol_row_is_empty_without_readings() {
+        let config = TelemetryConfig::new().channels([Channel::Analog(1)]);
+        assert_eq!(line_protocol_row(&config, Duration::ZERO, &[None]), "");
+    }
+}
@@ -0,0 +1,58 @@
+//! CPU temperature readings for Automation HAT boards.
+//!
+//! None of the Automation HAT variants carry a temperature sensor, so this module
+//! provides `AutomationHAT::cpu_temperature`, which reads the Raspberry Pi's own SoC
+//! temperature from sysfs. Relay-heavy controllers in enclosures can use it to back off
+//! before the Pi throttles.
+
+use crate::{AutomationHAT, HatError, Result};
+
+use std::io::ErrorKind;
+
+/// Sysfs file reporting the SoC temperature in millidegrees Celsius
+const CPU_THERMAL_ZONE: &str = "/sys/class/thermal/thermal_zone0/temp";
+
+impl AutomationHAT {
+    /// Reads the temperature of the Raspberry Pi's SoC.
+    ///
+    /// The value comes from `/sys/class/thermal/thermal_zone0/temp`, not from the
+    /// board, so it reflects the Pi's processor rather than the air around the relays.
+    /// It is still a useful proxy for enclosure temperature under a steady load.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(f64)` - The temperature in degrees Celsius
+    /// * `Err(HatError::NotPresent)` - If the thermal zone does not exist, e.g. when not
+    ///   running on a Raspberry Pi
+    /// * `Err(HatError::Io)` - If the file could not be read or parsed
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use automation_hat::{AutomationHAT, HatType};
+    ///
+    /// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+    /// if hat.cpu_temperature()? > 70.0 {
+    ///     hat.relays.one.write(false)?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn cpu_temperature(&self) -> Result<f64> {
+        let contents =
+            std::fs::read_to_string(CPU_THERMAL_ZONE).map_err(|error| match error.kind() {
+                ErrorKind::NotFound => {
+                    HatError::NotPresent(format!("CPU thermal zone {}", CPU_THERMAL_ZONE))
+                }
+                _ => HatError::Io(format!("Failed to read {}: {}", CPU_THERMAL_ZONE, error)),
+            })?;
+        let millidegrees: i64 = contents.trim().parse().map_err(|error| {
+            HatError::Io(format!(
+                "Unexpected contents in {}: {:?} ({})",
+                CPU_THERMAL_ZONE,
+                contents.trim(),
+                error
+            ))
+        })?;
+        Ok(millidegrees as f64 / 1000.0)
+    }
+}
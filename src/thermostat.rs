@@ -0,0 +1,159 @@
+//! Thermostat-style control of a relay from an analog input.
+//!
+//! This module provides `Thermostat`, which reads one analog input and switches one
+//! relay with hysteresis, the building block of heaters, coolers, pumps and other
+//! on/off controllers. If the sensor keeps failing, the relay is driven to a
+//! configurable safe state.
+
+use crate::{AnalogInput, Relay, Result};
+
+/// Whether the relay drives something that raises or lowers the measured value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThermostatMode {
+    /// The relay is switched on when the reading is too low
+    #[default]
+    Heating,
+    /// The relay is switched on when the reading is too high
+    Cooling,
+}
+
+/// Switches a relay on and off to hold an analog reading near a setpoint.
+///
+/// The relay switches when the terminal voltage leaves a band of `hysteresis` volts
+/// centered on the setpoint, so a noisy reading near the setpoint does not make it
+/// chatter.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use automation_hat::{AutomationHAT, HatType, Thermostat};
+/// use std::{thread, time::Duration};
+///
+/// let mut hat = AutomationHAT::new(HatType::AutomationHAT);
+/// let mut thermostat = Thermostat::new(&mut hat.analog_inputs.one, &mut hat.relays.one);
+/// thermostat.set_setpoint(2.5);
+/// thermostat.set_hysteresis(0.2);
+/// loop {
+///     let heating = thermostat.update();
+///     println!("Heater: {:?}", heating);
+///     thread::sleep(Duration::from_secs(1));
+/// }
+/// ```
+pub struct Thermostat<'a> {
+    /// The sensor input
+    input: &'a mut AnalogInput,
+    /// The relay switching the load
+    relay: &'a mut Relay,
+    /// Target terminal voltage
+    setpoint: f64,
+    /// Width of the switching band in volts
+    hysteresis: f64,
+    /// Direction of control
+    mode: ThermostatMode,
+    /// Consecutive failed reads before the failsafe state is applied
+    max_failures: u32,
+    /// Relay state applied when the sensor keeps failing
+    failsafe_state: bool,
+    /// Consecutive failed reads so far
+    failures: u32,
+}
+
+impl<'a> Thermostat<'a> {
+    /// Creates a heating thermostat with a setpoint of 0V and no hysteresis.
+    ///
+    /// By default the relay is switched off after 3 consecutive failed reads.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The analog input reading the sensor
+    /// * `relay` - The relay switching the load
+    ///
+    /// # Returns
+    ///
+    /// A new `Thermostat`; the relay is not touched until the first `update`
+    pub fn new(input: &'a mut AnalogInput, relay: &'a mut Relay) -> Self {
+        Thermostat {
+            input,
+            relay,
+            setpoint: 0.0,
+            hysteresis: 0.0,
+            mode: ThermostatMode::default(),
+            max_failures: 3,
+            failsafe_state: false,
+            failures: 0,
+        }
+    }
+
+    /// Sets the target terminal voltage.
+    pub fn set_setpoint(&mut self, volts: f64) {
+        self.setpoint = volts;
+    }
+
+    /// Sets the width of the switching band in volts; negative values are treated as 0.
+    pub fn set_hysteresis(&mut self, volts: f64) {
+        self.hysteresis = volts.max(0.0);
+    }
+
+    /// Sets whether the relay heats (on when low) or cools (on when high).
+    pub fn set_mode(&mut self, mode: ThermostatMode) {
+        self.mode = mode;
+    }
+
+    /// Sets how the thermostat reacts to a failing sensor.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_failures` - Consecutive failed reads before the relay is forced; 0 is treated as 1
+    /// * `state` - The relay state to force, usually `false` (off)
+    pub fn set_failsafe(&mut self, max_failures: u32, state: bool) {
+        self.max_failures = max_failures.max(1);
+        self.failsafe_state = state;
+    }
+
+    /// Reads the sensor and switches the relay if the reading has left the band.
+    ///
+    /// Inside the band the relay keeps its current state. If the read fails, the
+    /// relay is left alone until `max_failures` consecutive reads have failed, after
+    /// which it is driven to the failsafe state; the read error is returned either way.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(bool)` - The relay state after the update
+    /// * `Err(HatError)` - If the sensor could not be read or the relay could not be switched
+    pub fn update(&mut self) -> Result<bool> {
+        let volts = match self.input.read_voltage() {
+            Ok(volts) => {
+                self.failures = 0;
+                volts
+            }
+            Err(error) => {
+                self.failures = self.failures.saturating_add(1);
+                if self.failures >= self.max_failures {
+                    self.write(self.failsafe_state)?;
+                }
+                return Err(error);
+            }
+        };
+
+        let low = volts < self.setpoint - self.hysteresis / 2.0;
+        let high = volts > self.setpoint + self.hysteresis / 2.0;
+        let state = match self.mode {
+            ThermostatMode::Heating if low => true,
+            ThermostatMode::Heating if high => false,
+            ThermostatMode::Cooling if high => true,
+            ThermostatMode::Cooling if low => false,
+            _ => self.relay.is_energized(),
+        };
+        self.write(state)?;
+        Ok(state)
+    }
+
+    /// Returns whether the failsafe state is currently being applied.
+    pub fn in_failsafe(&self) -> bool {
+        self.failures >= self.max_failures
+    }
+
+    fn write(&mut self, state: bool) -> Result<()> {
+        self.relay.write(state)
+    }
+}
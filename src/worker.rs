@@ -0,0 +1,119 @@
+//! Background threads of the long-running helpers.
+//!
+//! Samplers, watchers, loggers, bridges and the other helpers that keep working after
+//! they are started each own a thread that runs until the helper is stopped or
+//! dropped. This module provides `Worker`, which owns such a thread and the flag
+//! telling it to exit, and `lock`, for threads sharing the board behind a mutex.
+
+use crate::{AutomationHAT, HatError, Result};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Locks the board, recovering it if another thread panicked while holding it.
+pub(crate) fn lock(hat: &Mutex<AutomationHAT>) -> MutexGuard<'_, AutomationHAT> {
+    hat.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Flag telling a worker thread to exit, checked by the thread between steps.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StopFlag(Arc<AtomicBool>);
+
+impl StopFlag {
+    /// Returns whether the thread has been asked to exit.
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Marks the thread as stopping, e.g. once it finishes on its own.
+    pub(crate) fn set(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Waits until `deadline`, returning early once the flag is set.
+    ///
+    /// The thread parks rather than sleeps, so stopping does not wait out a long
+    /// interval.
+    pub(crate) fn sleep_until(&self, deadline: Instant) {
+        while !self.is_set() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+}
+
+/// A background thread, stopped and joined when dropped.
+pub(crate) struct Worker<T = ()> {
+    /// Tells the thread to exit
+    stop: StopFlag,
+    /// The thread, `None` once joined
+    thread: Option<JoinHandle<T>>,
+}
+
+impl<T: Send + 'static> Worker<T> {
+    /// Starts `body` on a thread named `name`, handing it the flag set by `join`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Worker)` - The running thread
+    /// * `Err(HatError::Io)` - If the thread could not be started
+    pub(crate) fn spawn(
+        name: impl Into<String>,
+        body: impl FnOnce(StopFlag) -> T + Send + 'static,
+    ) -> Result<Self> {
+        let name = name.into();
+        let stop = StopFlag::default();
+        let thread_stop = stop.clone();
+        let thread = thread::Builder::new()
+            .name(name.clone())
+            .spawn(move || body(thread_stop))
+            .map_err(|error| HatError::Io(format!("Unable to start {}: {}", name, error)))?;
+        Ok(Worker {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl<T> Worker<T> {
+    /// Returns the flag the thread checks, for handles that report whether it runs.
+    pub(crate) fn stop_flag(&self) -> StopFlag {
+        self.stop.clone()
+    }
+
+    /// Wakes the thread if it is waiting in `StopFlag::sleep_until` or parked.
+    #[cfg(feature = "display")]
+    pub(crate) fn unpark(&self) {
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+
+    /// Returns whether the thread has exited.
+    pub(crate) fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Asks the thread to exit and waits for it.
+    ///
+    /// # Returns
+    ///
+    /// What the thread returned, or `None` if it panicked or was already joined
+    pub(crate) fn join(&mut self) -> Option<T> {
+        self.stop.set();
+        let thread = self.thread.take()?;
+        thread.thread().unpark();
+        thread.join().ok()
+    }
+}
+
+impl<T> Drop for Worker<T> {
+    fn drop(&mut self) {
+        self.join();
+    }
+}